@@ -1,16 +1,64 @@
+use crate::api::transport::{HttpTransport, ReqwestTransport};
+use crate::error::CliError;
 use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Client, Method, RequestBuilder};
 use serde::de::DeserializeOwned;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Network settings for a [`BitbucketClient`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientOptions {
+    /// How long to wait for a connection, and for more data once a response
+    /// is arriving; a slow but steady download (e.g. a large diff) runs to the end
+    pub timeout: Duration,
+    /// Extra attempts for idempotent requests that time out, fail to
+    /// connect, or get a 429/502/503/504 response
+    pub retries: u32,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(crate::constants::DEFAULT_TIMEOUT_SECS),
+            retries: crate::constants::DEFAULT_RETRIES,
+        }
+    }
+}
+
+/// Wait before the first retry, doubled for each one after
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Longest `Retry-After` wait honored before retrying
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
 
 /// Bitbucket API Client
 ///
 /// Handles communication with the Bitbucket Cloud API v2.0.
 /// Supports authentication via Basic Auth (App Password).
+///
+/// One client is built per command and every request goes through it, so
+/// pagination and follow-up requests can reuse pooled keep-alive connections
+/// (multiplexed over HTTP/2 when the server negotiates it) instead of paying
+/// a TCP + TLS handshake each time. Clones share the same pool. `--verbose`
+/// logs how long each request took to answer.
+///
+/// Requests are sent through an [`HttpTransport`], by default one backed by
+/// the same pooled `reqwest` client; see [`Self::with_transport`].
 #[derive(Clone)]
 pub struct BitbucketClient {
+    /// Builds requests; sending is left to `transport`
     client: Client,
+    transport: Arc<dyn HttpTransport>,
     base_url: String,
     auth_header: Option<(String, String)>,
+    /// Static headers sent with every request (e.g. for corporate gateways)
+    extra_headers: HeaderMap,
+    /// Print requests instead of sending them (`--explain`)
+    explain: bool,
+    options: ClientOptions,
 }
 
 impl BitbucketClient {
@@ -19,20 +67,86 @@ impl BitbucketClient {
     /// # Arguments
     ///
     /// * `base_url` - The base URL for the Bitbucket API
-    /// * `base_url` - The base URL for the Bitbucket API
     /// * `auth` - Optional tuple of (username, password/token) for Basic Auth
-    pub fn new(base_url: String, auth: Option<(String, String)>) -> Result<Self> {
+    /// * `options` - Request timeout and retries
+    pub fn new(
+        base_url: String,
+        auth: Option<(String, String)>,
+        options: ClientOptions,
+    ) -> Result<Self> {
         let client = Client::builder()
+            .connect_timeout(options.timeout)
+            .read_timeout(options.timeout)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(Duration::from_secs(60))
             .build()
             .context("Failed to build HTTP client")?;
 
         Ok(Self {
+            transport: Arc::new(ReqwestTransport::new(client.clone())),
             client,
             base_url,
             auth_header: auth,
+            extra_headers: HeaderMap::new(),
+            explain: false,
+            options,
         })
     }
 
+    /// A client for other credentials at `base_url`
+    ///
+    /// Keeps this client's connection pool, timeout, retries and `--explain`,
+    /// so a login check behaves like every other request. Custom headers are
+    /// dropped; apply the right profile's with [`Self::with_headers`].
+    pub fn with_credentials(&self, base_url: String, auth: (String, String)) -> Self {
+        Self {
+            base_url,
+            auth_header: Some(auth),
+            extra_headers: HeaderMap::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Send these headers with every request, applied after authentication
+    ///
+    /// Names must be valid HTTP header names and values visible ASCII.
+    pub fn with_headers(
+        mut self,
+        headers: &std::collections::HashMap<String, String>,
+    ) -> Result<Self> {
+        self.extra_headers = parse_headers(headers)?;
+        Ok(self)
+    }
+
+    /// Send requests through `transport` instead of the network
+    #[cfg(test)]
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Print each request instead of sending it (see [`CliError::Explained`])
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// Whether requests are printed instead of sent
+    pub fn explains(&self) -> bool {
+        self.explain
+    }
+
+    /// Base URL of the web UI for pages the API doesn't link to
+    ///
+    /// Only known for Bitbucket Cloud (`https://bitbucket.org/`); a
+    /// Server/Data Center API gives `None`.
+    pub fn web_base_url(&self) -> Option<reqwest::Url> {
+        let api = reqwest::Url::parse(&self.base_url).ok()?;
+        matches!(api.host_str(), Some("api.bitbucket.org" | "bitbucket.org"))
+            .then(|| reqwest::Url::parse("https://bitbucket.org/").ok())
+            .flatten()
+    }
+
     pub(crate) fn build_request(&self, method: Method, path: &str) -> RequestBuilder {
         let url = if path.starts_with("http://") || path.starts_with("https://") {
             path.to_string()
@@ -55,14 +169,63 @@ impl BitbucketClient {
             crate::utils::debug::log("No Auth header present for this request.");
         }
 
+        if !self.extra_headers.is_empty() {
+            request = request.headers(self.extra_headers.clone());
+        }
+
         request
     }
 
     /// Send a request and handle common error checking
+    ///
+    /// With `--explain` the request is printed instead and [`CliError::Explained`]
+    /// is returned, which ends the command.
     async fn send_request(&self, request: RequestBuilder) -> Result<reqwest::Response> {
-        let response = request.send().await.context("Failed to send request")?;
+        let request = request.build().context("Failed to build request")?;
+        if self.explain {
+            print!("{}", format_explained_request(&request));
+            return Err(CliError::Explained.into());
+        }
 
-        crate::utils::debug::log(&format!("Response status: {}", response.status()));
+        let mut request = request;
+        let mut attempt = 0;
+        let response = loop {
+            // Only requests that are safe to repeat are retried
+            let retry = if attempt < self.options.retries && is_idempotent(request.method()) {
+                request.try_clone()
+            } else {
+                None
+            };
+            let started = std::time::Instant::now();
+            let result = self.transport.execute(request).await;
+            // Until the response headers arrive; the body is read afterwards
+            crate::utils::debug::log(&match &result {
+                Ok(response) => format!(
+                    "Response status: {} ({:?}) after {} ms",
+                    response.status(),
+                    response.version(),
+                    started.elapsed().as_millis()
+                ),
+                Err(_) => format!("No response after {} ms", started.elapsed().as_millis()),
+            });
+
+            let (reason, retry_after) = match (&result, &retry) {
+                (Ok(response), Some(_)) if is_retryable_status(response.status()) => (
+                    response.status().to_string(),
+                    retry_after(response.status(), response.headers(), chrono::Utc::now()),
+                ),
+                (Err(e), Some(_)) if is_transient_error(e) => (format!("{:#}", e), None),
+                _ => break result?,
+            };
+            attempt += 1;
+            let delay = retry_after.unwrap_or(RETRY_BACKOFF * 2u32.pow(attempt - 1));
+            crate::utils::debug::log(&format!(
+                "Retrying in {:?} ({} of {}): {}",
+                delay, attempt, self.options.retries, reason
+            ));
+            tokio::time::sleep(delay).await;
+            request = retry.expect("retry request checked above");
+        };
 
         if !response.status().is_success() {
             let status = response.status();
@@ -70,10 +233,10 @@ impl BitbucketClient {
                 .text()
                 .await
                 .unwrap_or_else(|_| "Could not read error body".to_string());
-            return Err(anyhow::anyhow!(
-                "API request failed ({}) : {}",
+            return Err(error_for_status(
                 status,
-                error_text
+                &error_text,
+                self.auth_header.is_some(),
             ));
         }
 
@@ -96,40 +259,101 @@ impl BitbucketClient {
         Ok(data)
     }
 
+    /// Perform a GET request and return the response body as sent
+    ///
+    /// Nothing is deserialized, so fields our models don't cover survive.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The API path (relative to base URL) or full URL
+    pub async fn get_raw(&self, path: &str) -> Result<String> {
+        let request = self.build_request(Method::GET, path);
+        let response = self.send_request(request).await?;
+
+        response
+            .text()
+            .await
+            .context("Failed to read response body")
+    }
+
     /// List pull requests for a repository
     ///
     /// # Arguments
     ///
     /// * `workspace` - The workspace ID or slug
     /// * `repo` - The repository slug
-    /// * `state` - Filter by PR state (e.g., "OPEN", "MERGED", "DECLINED")
+    /// * `states` - Filter by PR states (e.g., "OPEN", "MERGED", "DECLINED"); each is sent as a separate `state` param
+    /// * `query` - Optional BBQL `q` filter (see [`pr_search_query`])
     /// * `limit` - Optional maximum number of PRs to return
+    /// * `page_size` - Optional `pagelen` per request (see [`page_len`])
     pub async fn list_pull_requests(
         &self,
         workspace: &str,
         repo: &str,
-        state: &str,
+        states: &[String],
+        query: Option<&str>,
+        limit: Option<u32>,
+        page_size: Option<u32>,
+    ) -> Result<Vec<crate::api::models::PullRequest>> {
+        let path = pull_requests_path(workspace, repo, states, query, page_len(limit, page_size));
+        self.collect_pages(path, limit).await
+    }
+
+    /// List pull requests like [`Self::list_pull_requests`], keeping each PR
+    /// as the exact JSON text the API returned rather than a [`PullRequest`]
+    ///
+    /// [`PullRequest`]: crate::api::models::PullRequest
+    pub async fn list_pull_requests_raw(
+        &self,
+        workspace: &str,
+        repo: &str,
+        states: &[String],
+        query: Option<&str>,
+        limit: Option<u32>,
+        page_size: Option<u32>,
+    ) -> Result<Vec<Box<serde_json::value::RawValue>>> {
+        let path = pull_requests_path(workspace, repo, states, query, page_len(limit, page_size));
+        self.collect_pages(path, limit).await
+    }
+
+    /// List pull requests like [`Self::list_pull_requests`], including each
+    /// PR's participants (which list responses leave out by default), so
+    /// approvals can be read without a request per PR
+    pub async fn list_pull_requests_with_participants(
+        &self,
+        workspace: &str,
+        repo: &str,
+        states: &[String],
+        query: Option<&str>,
         limit: Option<u32>,
+        page_size: Option<u32>,
     ) -> Result<Vec<crate::api::models::PullRequest>> {
-        let mut all_prs = Vec::new();
-        // Use pagelen=100 (max) or limit if smaller to optimize API calls
-        let page_len = limit.map(|l| std::cmp::min(l, 100)).unwrap_or(100);
-        let mut path = format!(
-            "/repositories/{}/{}/pullrequests?state={}&pagelen={}",
-            workspace, repo, state, page_len
+        let path = format!(
+            "{}&{}",
+            pull_requests_path(workspace, repo, states, query, page_len(limit, page_size)),
+            encode_query(&[("fields", "+values.participants")])
         );
+        self.collect_pages(path, limit).await
+    }
+
+    /// Follow `next` links from `path` until `limit` items are fetched or pages run out
+    async fn collect_pages<T: DeserializeOwned>(
+        &self,
+        mut path: String,
+        limit: Option<u32>,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
 
         loop {
-            let response: crate::api::models::PaginatedResponse<crate::api::models::PullRequest> =
-                self.get(&path).await?;
+            let response: crate::api::models::PaginatedResponse<T> = self.get(&path).await?;
 
-            all_prs.extend(response.values);
+            items.extend(response.values);
 
             // Check if we've reached the limit
-            let limit_reached = limit.is_some_and(|max| all_prs.len() >= max as usize);
+            let limit_reached = limit.is_some_and(|max| items.len() >= max as usize);
 
             if limit_reached {
-                all_prs.truncate(limit.unwrap() as usize);
+                items.truncate(limit.unwrap() as usize);
                 break;
             }
 
@@ -139,7 +363,102 @@ impl BitbucketClient {
             }
         }
 
-        Ok(all_prs)
+        Ok(items)
+    }
+
+    /// Count pull requests for a repository without fetching every page
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `states` - Filter by PR states, as for [`Self::list_pull_requests`]
+    /// * `query` - Optional BBQL `q` filter
+    pub async fn count_pull_requests(
+        &self,
+        workspace: &str,
+        repo: &str,
+        states: &[String],
+        query: Option<&str>,
+    ) -> Result<u32> {
+        let path = pull_requests_path(workspace, repo, states, query, 100);
+        self.count_paginated::<crate::api::models::PullRequest>(&path)
+            .await
+    }
+
+    /// Count repositories in a workspace without fetching every page
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `role` - Optional membership filter (`member`, `contributor`, `admin`, `owner`)
+    pub async fn count_repositories(&self, workspace: &str, role: Option<&str>) -> Result<u32> {
+        let path = repositories_path(workspace, role, 100);
+        self.count_paginated::<crate::api::models::Repository>(&path)
+            .await
+    }
+
+    /// Count the items of a paginated endpoint
+    ///
+    /// Uses the `size` reported on the first page when present (a single
+    /// request); otherwise follows `next` links and counts the fetched items.
+    async fn count_paginated<T: DeserializeOwned>(&self, path: &str) -> Result<u32> {
+        let first: crate::api::models::PaginatedResponse<T> = self.get(path).await?;
+        if let Some(size) = first.size {
+            return Ok(size);
+        }
+
+        let mut count = first.values.len();
+        let mut next = first.next;
+        while let Some(next_url) = next {
+            let page: crate::api::models::PaginatedResponse<T> = self.get(&next_url).await?;
+            count += page.values.len();
+            next = page.next;
+        }
+
+        Ok(count as u32)
+    }
+
+    /// Get a single repository
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    pub async fn get_repository(
+        &self,
+        workspace: &str,
+        repo: &str,
+    ) -> Result<crate::api::models::Repository> {
+        let path = format!("/repositories/{}/{}", workspace, repo);
+        self.get(&path).await
+    }
+
+    /// Change a repository's default branch (requires admin access)
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `branch` - Name of an existing branch to make the default
+    pub async fn set_default_branch(
+        &self,
+        workspace: &str,
+        repo: &str,
+        branch: &str,
+    ) -> Result<crate::api::models::Repository> {
+        let path = format!("/repositories/{}/{}", workspace, repo);
+
+        let request = self
+            .build_request(Method::PUT, &path)
+            .json(&serde_json::json!({ "mainbranch": { "name": branch } }));
+        let response = self.send_request(request).await?;
+
+        let repository = response
+            .json::<crate::api::models::Repository>()
+            .await
+            .context("Failed to parse JSON response")?;
+        Ok(repository)
     }
 
     /// List repositories in a workspace
@@ -147,16 +466,19 @@ impl BitbucketClient {
     /// # Arguments
     ///
     /// * `workspace` - The workspace ID or slug
+    /// * `role` - Optional membership filter (`member`, `contributor`, `admin`, `owner`)
     /// * `limit` - Optional maximum number of repositories to return
+    /// * `page_size` - Optional `pagelen` per request (see [`page_len`])
     pub async fn list_repositories(
         &self,
         workspace: &str,
+        role: Option<&str>,
         limit: Option<u32>,
+        page_size: Option<u32>,
     ) -> Result<Vec<crate::api::models::Repository>> {
         let mut all_repos = Vec::new();
-        // Use pagelen=100 (max) or limit if smaller to optimize API calls
-        let page_len = limit.map(|l| std::cmp::min(l, 100)).unwrap_or(100);
-        let mut path = format!("/repositories/{}?pagelen={}", workspace, page_len);
+        let page_len = page_len(limit, page_size);
+        let mut path = repositories_path(workspace, role, page_len);
 
         loop {
             let response: crate::api::models::PaginatedResponse<crate::api::models::Repository> =
@@ -198,31 +520,102 @@ impl BitbucketClient {
         self.get(&path).await
     }
 
-    /// Get the diff for a pull request
+    /// Stream a pull request's diff into `sink` without buffering the whole body
+    ///
+    /// `on_progress` is called with the running byte count after every chunk.
+    /// If the connection drops mid-body and the server advertised
+    /// `Accept-Ranges: bytes`, the rest is requested with a `Range` header, up
+    /// to [`MAX_DIFF_RESUMES`] times. Returns the total number of bytes written.
+    ///
+    /// [`MAX_DIFF_RESUMES`]: crate::constants::MAX_DIFF_RESUMES
     ///
     /// # Arguments
     ///
     /// * `workspace` - The workspace ID or slug
     /// * `repo` - The repository slug
     /// * `id` - The pull request ID
-    pub async fn get_pull_request_diff(
+    /// * `sink` - Destination for the raw diff bytes
+    /// * `on_progress` - Progress callback receiving bytes downloaded so far
+    pub async fn stream_pull_request_diff<W: Write>(
         &self,
         workspace: &str,
         repo: &str,
         id: u32,
-    ) -> Result<String> {
+        sink: &mut W,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<u64> {
         let path = format!(
             "/repositories/{}/{}/pullrequests/{}/diff",
             workspace, repo, id
         );
         let request = self.build_request(Method::GET, &path);
+        let mut response = self.send_request(request).await?;
+        let resumable = accepts_byte_ranges(response.headers());
+
+        let mut written: u64 = 0;
+        let mut resumes = 0;
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) if resumable && resumes < crate::constants::MAX_DIFF_RESUMES => {
+                    resumes += 1;
+                    crate::utils::debug::log(&format!(
+                        "Diff stream failed after {} bytes, resuming ({} of {}): {:#}",
+                        written,
+                        resumes,
+                        crate::constants::MAX_DIFF_RESUMES,
+                        e
+                    ));
+                    let request = self
+                        .build_request(Method::GET, &path)
+                        .header(reqwest::header::RANGE, format!("bytes={}-", written));
+                    response = self.send_request(request).await?;
+                    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT
+                        || content_range_start(response.headers()) != Some(written)
+                    {
+                        anyhow::bail!(
+                            "Failed to resume the diff download at byte {}: server answered {}",
+                            written,
+                            response.status()
+                        );
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e).context("Failed to read diff stream"),
+            };
+            sink.write_all(&chunk)
+                .context("Failed to write diff output")?;
+            written += chunk.len() as u64;
+            on_progress(written);
+        }
+        sink.flush().context("Failed to write diff output")?;
+
+        Ok(written)
+    }
+
+    /// Get the diff between two branches or commits
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `spec` - A `source..destination` spec; it is URL-encoded as a single path segment
+    pub async fn get_branch_diff(&self, workspace: &str, repo: &str, spec: &str) -> Result<String> {
+        let mut url = reqwest::Url::parse(&self.base_url).context("Invalid base URL")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid base URL"))?
+            .pop_if_empty()
+            .extend(["repositories", workspace, repo, "diff", spec]);
+
+        let request = self.build_request(Method::GET, url.as_str());
         let response = self.send_request(request).await?;
 
         let text = response.text().await.context("Failed to get diff text")?;
         Ok(text)
     }
 
-    /// Get build/commit statuses for a commit
+    /// Get build/commit statuses for a commit, following every page
     ///
     /// # Arguments
     ///
@@ -239,12 +632,10 @@ impl BitbucketClient {
             "/repositories/{}/{}/commit/{}/statuses",
             workspace, repo, commit_hash
         );
-        let response: crate::api::models::PaginatedResponse<crate::api::models::CommitStatus> =
-            self.get(&path).await?;
-        Ok(response.values)
+        self.get_all_pages(&path).await
     }
 
-    /// Get comments for a pull request
+    /// Get comments for a pull request, following every page
     ///
     /// # Arguments
     ///
@@ -261,9 +652,79 @@ impl BitbucketClient {
             "/repositories/{}/{}/pullrequests/{}/comments",
             workspace, repo, id
         );
-        let response: crate::api::models::PaginatedResponse<crate::api::models::Comment> =
-            self.get(&path).await?;
-        Ok(response.values)
+        self.get_all_pages(&path).await
+    }
+
+    /// Get a pull request's activity feed (approvals, comments, updates), following every page
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `id` - The pull request ID
+    pub async fn get_pull_request_activity(
+        &self,
+        workspace: &str,
+        repo: &str,
+        id: u32,
+    ) -> Result<Vec<crate::api::models::Activity>> {
+        let path = format!(
+            "/repositories/{}/{}/pullrequests/{}/activity",
+            workspace, repo, id
+        );
+        self.get_all_pages(&path).await
+    }
+
+    /// Get the commits of a pull request, newest first, following every page
+    pub async fn get_pull_request_commits(
+        &self,
+        workspace: &str,
+        repo: &str,
+        id: u32,
+    ) -> Result<Vec<crate::api::models::Commit>> {
+        let path = format!(
+            "/repositories/{}/{}/pullrequests/{}/commits",
+            workspace, repo, id
+        );
+        self.get_all_pages(&path).await
+    }
+
+    /// Get the per-file diffstat for a pull request, following every page
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `id` - The pull request ID
+    pub async fn get_pull_request_diffstat(
+        &self,
+        workspace: &str,
+        repo: &str,
+        id: u32,
+    ) -> Result<Vec<crate::api::models::DiffStat>> {
+        let path = format!(
+            "/repositories/{}/{}/pullrequests/{}/diffstat",
+            workspace, repo, id
+        );
+        self.get_all_pages(&path).await
+    }
+
+    /// Fetch every page of a paginated endpoint, following `next` links
+    async fn get_all_pages<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let mut path = path.to_string();
+        let mut all = Vec::new();
+
+        loop {
+            let response: crate::api::models::PaginatedResponse<T> = self.get(&path).await?;
+            all.extend(response.values);
+
+            match response.next {
+                Some(next_url) => path = next_url,
+                None => break,
+            }
+        }
+
+        Ok(all)
     }
 
     /// Find a pull request by source branch name
@@ -273,12 +734,69 @@ impl BitbucketClient {
     /// * `workspace` - The workspace ID or slug
     /// * `repo` - The repository slug
     /// * `branch_name` - The source branch name
+    /// * `source_repo` - The source repository's full name (`workspace/repo`),
+    ///   to tell apart same-named branches in different forks. It's only used
+    ///   when it names another repository. If nothing matches it, a PR from a
+    ///   branch of that name in any repository is used only when it's the
+    ///   only one, since a remote's name can differ from the repository's
+    ///   (renames, moves, SSH host aliases) but another fork's PR mustn't be
+    ///   picked up. Without it the first open PR for the branch is returned.
     pub async fn find_pull_request_by_branch(
         &self,
         workspace: &str,
         repo: &str,
         branch_name: &str,
+        source_repo: Option<&str>,
     ) -> Result<Option<crate::api::models::PullRequest>> {
+        let branch_query = format!(
+            "source.branch.name=\"{}\"",
+            escape_bbql_value(normalize_branch_name(branch_name))
+        );
+
+        let target = format!("{}/{}", workspace, repo);
+        if let Some(full_name) = source_repo.filter(|name| !name.eq_ignore_ascii_case(&target)) {
+            let fork_query = format!(
+                "{} AND source.repository.full_name=\"{}\"",
+                branch_query,
+                escape_bbql_value(full_name)
+            );
+            if let Some(pr) = self
+                .open_pull_requests(workspace, repo, &fork_query)
+                .await?
+                .into_iter()
+                .next()
+            {
+                return Ok(Some(pr));
+            }
+
+            let mut candidates = self
+                .open_pull_requests(workspace, repo, &branch_query)
+                .await?;
+            if candidates.len() > 1 {
+                crate::utils::debug::log(&format!(
+                    "{} open PRs from other repositories have branch '{}'; none from {}",
+                    candidates.len(),
+                    branch_name,
+                    full_name
+                ));
+                return Ok(None);
+            }
+            return Ok(candidates.pop());
+        }
+        Ok(self
+            .open_pull_requests(workspace, repo, &branch_query)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// The first page of open pull requests matching the BBQL `query`
+    async fn open_pull_requests(
+        &self,
+        workspace: &str,
+        repo: &str,
+        query: &str,
+    ) -> Result<Vec<crate::api::models::PullRequest>> {
         let path = format!("repositories/{}/{}/pullrequests", workspace, repo);
 
         // Ensure base URL ends with slash for join to work as expected (appending)
@@ -295,15 +813,84 @@ impl BitbucketClient {
             .join(&path)
             .context("Failed to join path")?;
 
-        let query = format!("source.branch.name=\"{}\"", branch_name);
         url.query_pairs_mut()
-            .append_pair("q", &query)
+            .append_pair("q", query)
             .append_pair("state", "OPEN");
 
         let response: crate::api::models::PaginatedResponse<crate::api::models::PullRequest> =
             self.get(url.as_str()).await?;
 
-        Ok(response.values.into_iter().next())
+        Ok(response.values)
+    }
+
+    /// Create a pull request
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `new_pr` - The pull request payload
+    pub async fn create_pull_request(
+        &self,
+        workspace: &str,
+        repo: &str,
+        new_pr: &crate::api::models::NewPullRequest,
+    ) -> Result<crate::api::models::PullRequest> {
+        let path = format!("/repositories/{}/{}/pullrequests", workspace, repo);
+
+        let request = self.build_request(Method::POST, &path).json(new_pr);
+        let response = self.send_request(request).await?;
+
+        let pr = response
+            .json::<crate::api::models::PullRequest>()
+            .await
+            .context("Failed to parse JSON response")?;
+        Ok(pr)
+    }
+
+    /// Mark a draft pull request as ready for review
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `id` - The pull request ID
+    pub async fn mark_pull_request_ready(
+        &self,
+        workspace: &str,
+        repo: &str,
+        id: u32,
+    ) -> Result<crate::api::models::PullRequest> {
+        let path = format!("/repositories/{}/{}/pullrequests/{}", workspace, repo, id);
+
+        let request = self
+            .build_request(Method::PUT, &path)
+            .json(&serde_json::json!({ "draft": false }));
+        let response = self.send_request(request).await?;
+
+        let pr = response
+            .json::<crate::api::models::PullRequest>()
+            .await
+            .context("Failed to parse JSON response")?;
+        Ok(pr)
+    }
+
+    /// Decline an open pull request
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `id` - The pull request ID
+    pub async fn decline_pull_request(&self, workspace: &str, repo: &str, id: u32) -> Result<()> {
+        let path = format!(
+            "/repositories/{}/{}/pullrequests/{}/decline",
+            workspace, repo, id
+        );
+        let request = self.build_request(Method::POST, &path);
+        self.send_request(request).await?;
+
+        Ok(())
     }
 
     /// Approve a pull request
@@ -326,6 +913,9 @@ impl BitbucketClient {
 
     /// Request changes on a pull request
     ///
+    /// Sets the caller's participant state to `changes_requested`
+    /// ([`ParticipantState::ChangesRequested`](crate::api::models::ParticipantState::ChangesRequested)).
+    ///
     /// # Arguments
     ///
     /// * `workspace` - The workspace ID or slug
@@ -342,29 +932,191 @@ impl BitbucketClient {
         Ok(())
     }
 
-    /// Post a comment on a pull request
+    /// Add a user to a pull request's participants, e.g. to follow it
     ///
     /// # Arguments
     ///
     /// * `workspace` - The workspace ID or slug
     /// * `repo` - The repository slug
     /// * `id` - The pull request ID
-    /// * `content` - The comment content
-    pub async fn post_pr_comment(
+    /// * `user_uuid` - The user's UUID, including braces
+    pub async fn add_participant(
         &self,
         workspace: &str,
         repo: &str,
         id: u32,
-        content: &str,
-    ) -> Result<crate::api::models::Comment> {
-        let path = format!(
-            "/repositories/{}/{}/pullrequests/{}/comments",
-            workspace, repo, id
+        user_uuid: &str,
+    ) -> Result<crate::api::models::Participant> {
+        let url = self.participant_url(workspace, repo, id, user_uuid)?;
+        let request = self.build_request(Method::PUT, &url);
+        let response = self.send_request(request).await?;
+
+        let participant = response
+            .json::<crate::api::models::Participant>()
+            .await
+            .context("Failed to parse JSON response")?;
+        Ok(participant)
+    }
+
+    /// Remove a user from a pull request's participants
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `id` - The pull request ID
+    /// * `user_uuid` - The user's UUID, including braces
+    pub async fn remove_participant(
+        &self,
+        workspace: &str,
+        repo: &str,
+        id: u32,
+        user_uuid: &str,
+    ) -> Result<()> {
+        let url = self.participant_url(workspace, repo, id, user_uuid)?;
+        let request = self.build_request(Method::DELETE, &url);
+        self.send_request(request).await?;
+
+        Ok(())
+    }
+
+    /// List the members of a workspace group
+    ///
+    /// Groups are only exposed by the 1.0 API, so this goes to the `1.0`
+    /// sibling of the configured base URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `group` - The group slug, without a leading `@`
+    pub async fn resolve_group_members(
+        &self,
+        workspace: &str,
+        group: &str,
+    ) -> Result<Vec<crate::api::models::User>> {
+        let url = self.group_members_url(workspace, group)?;
+        let request = self.build_request(Method::GET, &url);
+        let response = match self.send_request(request).await {
+            Err(e) if matches!(e.downcast_ref(), Some(CliError::NotFound(_))) => {
+                return Err(CliError::NotFound(format!(
+                    "Group '@{}' not found in workspace '{}'",
+                    group, workspace
+                ))
+                .into());
+            }
+            other => other?,
+        };
+
+        response
+            .json::<Vec<crate::api::models::User>>()
+            .await
+            .context("Failed to parse JSON response")
+    }
+
+    /// URL of a group's member list on the 1.0 API
+    fn group_members_url(&self, workspace: &str, group: &str) -> Result<String> {
+        let mut url = reqwest::Url::parse(&self.base_url).context("Invalid base URL")?;
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| anyhow::anyhow!("Invalid base URL"))?;
+            segments.pop_if_empty().pop();
+            segments.extend(["1.0", "groups", workspace, group, "members"]);
+        }
+        Ok(url.to_string())
+    }
+
+    /// URL of a single participant; the UUID is encoded as one path segment
+    fn participant_url(
+        &self,
+        workspace: &str,
+        repo: &str,
+        id: u32,
+        user_uuid: &str,
+    ) -> Result<String> {
+        let mut url = reqwest::Url::parse(&self.base_url).context("Invalid base URL")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid base URL"))?
+            .pop_if_empty()
+            .extend([
+                "repositories",
+                workspace,
+                repo,
+                "pullrequests",
+                &id.to_string(),
+                "participants",
+                user_uuid,
+            ]);
+        Ok(url.to_string())
+    }
+
+    /// Post a comment on a pull request
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `id` - The pull request ID
+    /// * `content` - The comment content
+    pub async fn post_pr_comment(
+        &self,
+        workspace: &str,
+        repo: &str,
+        id: u32,
+        content: &str,
+    ) -> Result<crate::api::models::Comment> {
+        let path = format!(
+            "/repositories/{}/{}/pullrequests/{}/comments",
+            workspace, repo, id
+        );
+
+        let body = serde_json::json!({
+            "content": {
+                "raw": content
+            }
+        });
+
+        let request = self.build_request(Method::POST, &path).json(&body);
+        let response = self.send_request(request).await?;
+
+        let comment = response
+            .json::<crate::api::models::Comment>()
+            .await
+            .context("Failed to parse JSON response")?;
+        Ok(comment)
+    }
+
+    /// Post an inline comment on a line of a pull request's diff
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - The workspace ID or slug
+    /// * `repo` - The repository slug
+    /// * `id` - The pull request ID
+    /// * `file` - Path of the file, as shown in the diff
+    /// * `line` - Line number in the new version of the file
+    /// * `content` - The comment content
+    pub async fn post_pr_inline_comment(
+        &self,
+        workspace: &str,
+        repo: &str,
+        id: u32,
+        file: &str,
+        line: u32,
+        content: &str,
+    ) -> Result<crate::api::models::Comment> {
+        let path = format!(
+            "/repositories/{}/{}/pullrequests/{}/comments",
+            workspace, repo, id
         );
 
         let body = serde_json::json!({
             "content": {
                 "raw": content
+            },
+            "inline": {
+                "path": file,
+                "to": line
             }
         });
 
@@ -382,17 +1134,356 @@ impl BitbucketClient {
     pub async fn get_current_user(&self) -> Result<crate::api::models::User> {
         self.get("/user").await
     }
+
+    /// Get the currently authenticated user along with the token's granted scopes
+    ///
+    /// Scopes are read from the `X-OAuth-Scopes` response header. `None` means
+    /// the server did not report them, so they could not be checked.
+    pub async fn get_current_user_with_scopes(
+        &self,
+    ) -> Result<(crate::api::models::User, Option<Vec<String>>)> {
+        let request = self.build_request(Method::GET, "/user");
+        let response = self.send_request(request).await?;
+
+        let scopes = response
+            .headers()
+            .get("x-oauth-scopes")
+            .and_then(|v| v.to_str().ok())
+            .map(parse_scopes);
+        crate::utils::debug::log(&format!("Granted scopes: {:?}", scopes));
+
+        let user = response
+            .json::<crate::api::models::User>()
+            .await
+            .context("Failed to parse JSON response")?;
+        Ok((user, scopes))
+    }
+}
+
+/// Parse a comma/space separated scope header value
+fn parse_scopes(header: &str) -> Vec<String> {
+    header
+        .split([',', ' '])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Return the required scopes that are not covered by the granted ones
+///
+/// Bitbucket scopes are hierarchical: `x:admin` implies `x:write`, which implies
+/// `x`, and `pullrequest:write` implies `repository:write`.
+pub fn missing_scopes<'a>(required: &[&'a str], granted: &[String]) -> Vec<&'a str> {
+    let implied_by = |scope: &str| -> Vec<String> {
+        let base = scope.split(':').next().unwrap_or(scope);
+        let mut implied = vec![scope.to_string()];
+        if !scope.ends_with(":admin") {
+            implied.push(format!("{}:admin", base));
+        }
+        if !scope.contains(':') {
+            implied.push(format!("{}:write", base));
+        }
+        if scope.starts_with("repository") && !scope.ends_with(":admin") {
+            implied.push("pullrequest:write".to_string());
+        }
+        implied
+    };
+
+    required
+        .iter()
+        .filter(|req| {
+            !implied_by(req)
+                .iter()
+                .any(|candidate| granted.contains(candidate))
+        })
+        .copied()
+        .collect()
+}
+
+/// Appended to access errors on requests sent without credentials
+const UNAUTHENTICATED_HINT: &str = "No credentials are configured, so only public repositories \
+     can be read. Run 'bb auth login' to authenticate";
+
+/// Map a failed HTTP status to an error, typed where it has a distinct exit code
+///
+/// Without credentials, Bitbucket answers requests for private repositories
+/// with 401/403 (or 404, hiding that they exist), so those get a login hint.
+fn error_for_status(status: reqwest::StatusCode, body: &str, authenticated: bool) -> anyhow::Error {
+    let mut message = format!("API request failed ({}) : {}", status, body);
+    let access_error = matches!(
+        status,
+        reqwest::StatusCode::NOT_FOUND
+            | reqwest::StatusCode::UNAUTHORIZED
+            | reqwest::StatusCode::FORBIDDEN
+    );
+    if access_error && !authenticated {
+        message = format!("{}\n{}", message, UNAUTHENTICATED_HINT);
+    }
+
+    match status {
+        reqwest::StatusCode::NOT_FOUND => CliError::NotFound(message).into(),
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            CliError::Auth(message).into()
+        }
+        _ => anyhow::anyhow!(message),
+    }
+}
+
+/// Render a request for `--explain`: method and URL, headers, then the body
+///
+/// Credentials are redacted; JSON bodies are pretty-printed.
+fn format_explained_request(request: &reqwest::Request) -> String {
+    let mut output = format!("{} {}\n", request.method(), request.url());
+    for (name, value) in request.headers() {
+        let value = if name == reqwest::header::AUTHORIZATION {
+            "<redacted>".to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+        output.push_str(&format!("{}: {}\n", name, value));
+    }
+
+    if let Some(bytes) = request.body().and_then(|b| b.as_bytes()) {
+        let body = serde_json::from_slice::<serde_json::Value>(bytes)
+            .ok()
+            .and_then(|json| serde_json::to_string_pretty(&json).ok())
+            .unwrap_or_else(|| String::from_utf8_lossy(bytes).into_owned());
+        output.push('\n');
+        output.push_str(&body);
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Build the pull request listing path, sending each state as its own `state` param
+fn pull_requests_path(
+    workspace: &str,
+    repo: &str,
+    states: &[String],
+    query: Option<&str>,
+    page_len: u32,
+) -> String {
+    let mut params: Vec<(&str, &str)> = states.iter().map(|s| ("state", s.as_str())).collect();
+    if let Some(query) = query {
+        params.push(("q", query));
+    }
+    let page_len = page_len.to_string();
+    params.push(("pagelen", &page_len));
+
+    format!(
+        "/repositories/{}/{}/pullrequests?{}",
+        workspace,
+        repo,
+        encode_query(&params)
+    )
+}
+
+/// URL-encode query parameters (`a=1&b=x%20y`)
+fn encode_query(params: &[(&str, &str)]) -> String {
+    // Url does the form encoding; the host is a placeholder
+    reqwest::Url::parse_with_params("http://localhost/", params)
+        .ok()
+        .and_then(|url| url.query().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// BBQL clause matching PRs whose title or description contains `text`
+pub fn pr_search_query(text: &str) -> String {
+    let value = escape_bbql_value(text);
+    format!("title ~ \"{0}\" OR description ~ \"{0}\"", value)
+}
+
+/// BBQL clause matching PRs with the user `uuid` among the reviewers
+pub fn pr_reviewer_query(uuid: &str) -> String {
+    format!("reviewers.uuid = \"{}\"", escape_bbql_value(uuid))
+}
+
+/// BBQL clause matching PRs last updated before `cutoff`
+pub fn pr_updated_before_query(cutoff: chrono::DateTime<chrono::Utc>) -> String {
+    format!(
+        "updated_on < {}",
+        cutoff.to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+    )
+}
+
+/// BBQL clause matching PRs updated at or after `since`
+pub fn pr_updated_since_query(since: chrono::DateTime<chrono::Utc>) -> String {
+    format!(
+        "updated_on >= {}",
+        since.to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+    )
+}
+
+/// BBQL `clauses` joined with AND, or `None` when there are none
+///
+/// A clause with an OR of its own is parenthesized when joined.
+pub fn bbql_and(clauses: &[String]) -> Option<String> {
+    match clauses {
+        [] => None,
+        [clause] => Some(clause.clone()),
+        _ => Some(
+            clauses
+                .iter()
+                .map(|c| {
+                    if c.contains(" OR ") {
+                        format!("({})", c)
+                    } else {
+                        c.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" AND "),
+        ),
+    }
+}
+
+/// Validate configured header names and values into a [`HeaderMap`]
+fn parse_headers(headers: &std::collections::HashMap<String, String>) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Invalid header name '{}' in profile headers", name))?;
+        if !value.is_ascii() {
+            anyhow::bail!("Header '{}' must have an ASCII value", name);
+        }
+        let header_value = HeaderValue::from_str(value).map_err(|_| {
+            anyhow::anyhow!("Invalid value for header '{}' in profile headers", name)
+        })?;
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
+
+/// Items to request per page for a listing
+///
+/// Defaults to the API maximum to minimise round-trips, never asks for more
+/// than `limit`, and clamps an explicit `page_size` to [`MAX_PAGE_LEN`].
+///
+/// [`MAX_PAGE_LEN`]: crate::constants::MAX_PAGE_LEN
+fn page_len(limit: Option<u32>, page_size: Option<u32>) -> u32 {
+    let max = crate::constants::MAX_PAGE_LEN;
+    let requested = page_size.map_or(max, |s| s.min(max));
+    limit.map_or(requested, |l| l.min(requested))
+}
+
+/// Build the repository listing path, with an optional `role` membership filter
+fn repositories_path(workspace: &str, role: Option<&str>, page_len: u32) -> String {
+    match role {
+        Some(role) => format!(
+            "/repositories/{}?role={}&pagelen={}",
+            workspace, role, page_len
+        ),
+        None => format!("/repositories/{}?pagelen={}", workspace, page_len),
+    }
+}
+
+/// Strip a `refs/heads/` prefix and surrounding whitespace from a branch name
+pub fn normalize_branch_name(branch_name: &str) -> &str {
+    let trimmed = branch_name.trim();
+    trimmed.strip_prefix("refs/heads/").unwrap_or(trimmed)
+}
+
+/// Methods that can be sent again without repeating a side effect
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE
+    )
+}
+
+/// Responses that mean "try again later" rather than "this request is wrong"
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 502 | 503 | 504)
+}
+
+/// The wait a 429 or 503 response asks for in `Retry-After`, capped at [`MAX_RETRY_AFTER`]
+///
+/// The header holds either a number of seconds or an HTTP date; a date in
+/// the past means no wait.
+fn retry_after(
+    status: reqwest::StatusCode,
+    headers: &HeaderMap,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<Duration> {
+    if !matches!(status.as_u16(), 429 | 503) {
+        return None;
+    }
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    let wait = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (at.with_timezone(&chrono::Utc) - now)
+                .to_std()
+                .unwrap_or_default()
+        }
+    };
+    Some(wait.min(MAX_RETRY_AFTER))
+}
+
+/// Whether the server advertised `Accept-Ranges: bytes`, so a body can be resumed
+fn accepts_byte_ranges(headers: &HeaderMap) -> bool {
+    headers
+        .get(reqwest::header::ACCEPT_RANGES)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|unit| unit.trim() == "bytes"))
+}
+
+/// The first byte offset of a `Content-Range: bytes <start>-<end>/<total>` header
+fn content_range_start(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Network failures worth retrying: timeouts and refused or dropped connections
+fn is_transient_error(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_timeout() || e.is_connect())
+}
+
+/// Escape a value for use inside a double-quoted Bitbucket query language (BBQL) string
+///
+/// Backslashes and double quotes are backslash-escaped so the value cannot
+/// terminate the string early and change the meaning of the query.
+pub fn escape_bbql_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '\\' || c == '"' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::fixtures::PullRequestJson;
+    use crate::api::transport::mock::{MockResponse, MockTransport};
 
     #[test]
     fn test_auth_header_presence() {
         let client = BitbucketClient::new(
             "https://api.bitbucket.org/2.0".to_string(),
             Some(("user".to_string(), "pass".to_string())),
+            ClientOptions::default(),
         )
         .unwrap();
 
@@ -412,10 +1503,309 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_error_for_status_exit_codes() {
+        use crate::error::{EXIT_AUTH, EXIT_GENERAL, EXIT_NOT_FOUND, exit_code};
+
+        let err = error_for_status(reqwest::StatusCode::NOT_FOUND, "missing", true);
+        assert_eq!(exit_code(&err), EXIT_NOT_FOUND);
+        assert!(err.to_string().contains("missing"));
+
+        let err = error_for_status(reqwest::StatusCode::UNAUTHORIZED, "", true);
+        assert_eq!(exit_code(&err), EXIT_AUTH);
+
+        let err = error_for_status(reqwest::StatusCode::FORBIDDEN, "", true);
+        assert_eq!(exit_code(&err), EXIT_AUTH);
+
+        let err = error_for_status(reqwest::StatusCode::INTERNAL_SERVER_ERROR, "", true);
+        assert_eq!(exit_code(&err), EXIT_GENERAL);
+    }
+
+    #[test]
+    fn test_error_for_status_unauthenticated_hint() {
+        use crate::error::{EXIT_AUTH, EXIT_NOT_FOUND, exit_code};
+
+        let err = error_for_status(reqwest::StatusCode::UNAUTHORIZED, "", false);
+        assert_eq!(exit_code(&err), EXIT_AUTH);
+        assert!(err.to_string().contains("bb auth login"));
+
+        let err = error_for_status(reqwest::StatusCode::NOT_FOUND, "", false);
+        assert_eq!(exit_code(&err), EXIT_NOT_FOUND);
+        assert!(err.to_string().contains("bb auth login"));
+
+        // With credentials the token itself is the problem, not a missing login
+        let err = error_for_status(reqwest::StatusCode::UNAUTHORIZED, "", true);
+        assert!(!err.to_string().contains("bb auth login"));
+
+        let err = error_for_status(reqwest::StatusCode::INTERNAL_SERVER_ERROR, "", false);
+        assert!(!err.to_string().contains("bb auth login"));
+    }
+
+    #[test]
+    fn test_pull_requests_path_states() {
+        let states = vec!["OPEN".to_string(), "MERGED".to_string()];
+        assert_eq!(
+            pull_requests_path("ws", "repo", &states, None, 50),
+            "/repositories/ws/repo/pullrequests?state=OPEN&state=MERGED&pagelen=50"
+        );
+    }
+
+    #[test]
+    fn test_pull_requests_path_search() {
+        let states = vec!["OPEN".to_string()];
+        let query = pr_search_query(r#"cache "fix" & more"#);
+        assert_eq!(
+            query,
+            r#"title ~ "cache \"fix\" & more" OR description ~ "cache \"fix\" & more""#
+        );
+
+        let path = pull_requests_path("ws", "repo", &states, Some(&query), 50);
+        let url = reqwest::Url::parse(&format!("https://api.bitbucket.org/2.0{}", path)).unwrap();
+        let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("state".to_string(), "OPEN".to_string()),
+                ("q".to_string(), query),
+                ("pagelen".to_string(), "50".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pr_updated_before_query() {
+        let cutoff = chrono::DateTime::parse_from_rfc3339("2024-03-01T12:30:00.5+02:00")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            pr_updated_before_query(cutoff),
+            "updated_on < 2024-03-01T10:30:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_page_len() {
+        assert_eq!(page_len(None, None), 100);
+        assert_eq!(page_len(Some(20), None), 20);
+        assert_eq!(page_len(Some(500), Some(25)), 25);
+        assert_eq!(page_len(Some(10), Some(25)), 10);
+        assert_eq!(page_len(None, Some(250)), 100);
+    }
+
+    #[test]
+    fn test_list_path_sends_page_size() {
+        let states = vec!["OPEN".to_string()];
+        assert!(
+            pull_requests_path("ws", "repo", &states, None, page_len(Some(50), Some(10)))
+                .ends_with("pagelen=10")
+        );
+        assert!(repositories_path("ws", None, page_len(None, Some(30))).ends_with("?pagelen=30"));
+    }
+
+    #[test]
+    fn test_repositories_path_role() {
+        assert_eq!(
+            repositories_path("ws", None, 100),
+            "/repositories/ws?pagelen=100"
+        );
+        assert_eq!(
+            repositories_path("ws", Some("member"), 20),
+            "/repositories/ws?role=member&pagelen=20"
+        );
+    }
+
+    #[test]
+    fn test_normalize_branch_name() {
+        assert_eq!(normalize_branch_name("feature/x"), "feature/x");
+        assert_eq!(normalize_branch_name("refs/heads/feature/x"), "feature/x");
+        assert_eq!(normalize_branch_name("  main\n"), "main");
+    }
+
+    #[test]
+    fn test_bbql_and() {
+        let since = pr_updated_since_query(
+            chrono::DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        );
+        assert_eq!(since, "updated_on >= 2024-03-01T00:00:00+00:00");
+
+        assert_eq!(bbql_and(&[]), None);
+        let search = pr_search_query("fix");
+        assert_eq!(
+            bbql_and(std::slice::from_ref(&search)),
+            Some(search.clone())
+        );
+        assert_eq!(
+            bbql_and(&[search, since]).unwrap(),
+            "(title ~ \"fix\" OR description ~ \"fix\") \
+             AND updated_on >= 2024-03-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_pr_reviewer_query() {
+        assert_eq!(
+            pr_reviewer_query("{1234-abcd}"),
+            r#"reviewers.uuid = "{1234-abcd}""#
+        );
+    }
+
+    #[test]
+    fn test_escape_bbql_value() {
+        assert_eq!(escape_bbql_value("feature/plain"), "feature/plain");
+        assert_eq!(escape_bbql_value(r#"say-"hi""#), r#"say-\"hi\""#);
+        assert_eq!(escape_bbql_value(r"back\slash"), r"back\\slash");
+        assert_eq!(escape_bbql_value(r#"\""#), r#"\\\""#);
+    }
+
+    #[test]
+    fn test_parse_scopes() {
+        assert_eq!(
+            parse_scopes("account, repository:write,pullrequest:write"),
+            vec!["account", "repository:write", "pullrequest:write"]
+        );
+        assert!(parse_scopes("").is_empty());
+    }
+
+    #[test]
+    fn test_missing_scopes() {
+        let required = ["account", "repository:write", "pullrequest:write"];
+
+        let granted = vec!["account".to_string(), "pullrequest:write".to_string()];
+        assert!(missing_scopes(&required, &granted).is_empty());
+
+        let granted = vec!["account:write".to_string(), "repository".to_string()];
+        assert_eq!(
+            missing_scopes(&required, &granted),
+            vec!["repository:write", "pullrequest:write"]
+        );
+
+        let granted = vec![
+            "account".to_string(),
+            "repository:admin".to_string(),
+            "pullrequest:write".to_string(),
+        ];
+        assert!(missing_scopes(&required, &granted).is_empty());
+    }
+
+    #[test]
+    fn test_extra_headers_applied() {
+        let headers = std::collections::HashMap::from([
+            ("X-Atlassian-Token".to_string(), "no-check".to_string()),
+            ("X-Gateway-Key".to_string(), "abc123".to_string()),
+        ]);
+        let client = BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            Some(("user".to_string(), "token".to_string())),
+            ClientOptions::default(),
+        )
+        .unwrap()
+        .with_headers(&headers)
+        .unwrap();
+
+        let request = client.build_request(Method::GET, "/user").build().unwrap();
+        assert_eq!(request.headers()["x-atlassian-token"], "no-check");
+        assert_eq!(request.headers()["x-gateway-key"], "abc123");
+        assert!(request.headers().contains_key("authorization"));
+    }
+
+    #[test]
+    fn test_parse_headers_rejects_malformed() {
+        let bad_name =
+            std::collections::HashMap::from([("Bad Header".to_string(), "v".to_string())]);
+        let err = parse_headers(&bad_name).unwrap_err().to_string();
+        assert!(err.contains("Bad Header"));
+
+        let bad_value =
+            std::collections::HashMap::from([("X-Key".to_string(), "línea".to_string())]);
+        assert!(parse_headers(&bad_value).is_err());
+
+        let newline = std::collections::HashMap::from([("X-Key".to_string(), "a\nb".to_string())]);
+        assert!(parse_headers(&newline).is_err());
+    }
+
+    #[test]
+    fn test_participant_url_encodes_uuid() {
+        let client = BitbucketClient::new(
+            crate::constants::DEFAULT_API_URL.to_string(),
+            None,
+            ClientOptions::default(),
+        )
+        .unwrap();
+        let url = client
+            .participant_url("ws", "repo", 42, "{1234-abcd}")
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://api.bitbucket.org/2.0/repositories/ws/repo/pullrequests/42/participants/%7B1234-abcd%7D"
+        );
+    }
+
+    #[test]
+    fn test_format_explained_request() {
+        let client = BitbucketClient::new(
+            crate::constants::DEFAULT_API_URL.to_string(),
+            Some(("me@example.com".to_string(), "secret-token".to_string())),
+            ClientOptions::default(),
+        )
+        .unwrap();
+
+        let request = client
+            .build_request(
+                Method::POST,
+                "/repositories/ws/repo/pullrequests/1/comments",
+            )
+            .json(&serde_json::json!({"content": {"raw": "hi"}}))
+            .build()
+            .unwrap();
+        let output = format_explained_request(&request);
+
+        assert!(output.starts_with(
+            "POST https://api.bitbucket.org/2.0/repositories/ws/repo/pullrequests/1/comments\n"
+        ));
+        assert!(output.contains("authorization: <redacted>"));
+        assert!(!output.contains("secret-token"));
+        assert!(output.contains("\"raw\": \"hi\""));
+    }
+
+    #[tokio::test]
+    async fn test_explain_does_not_send() {
+        // Nothing listens on this port, so a real send would fail differently
+        let client = BitbucketClient::new(
+            "http://127.0.0.1:9/2.0".to_string(),
+            None,
+            ClientOptions::default(),
+        )
+        .unwrap()
+        .with_explain(true);
+
+        let err = client.get_current_user().await.unwrap_err();
+        assert_eq!(crate::error::exit_code(&err), crate::error::EXIT_SUCCESS);
+    }
+
+    #[test]
+    fn test_group_members_url() {
+        let client = BitbucketClient::new(
+            crate::constants::DEFAULT_API_URL.to_string(),
+            None,
+            ClientOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            client.group_members_url("ws", "core devs").unwrap(),
+            "https://api.bitbucket.org/1.0/groups/ws/core%20devs/members"
+        );
+    }
+
     #[test]
     fn test_no_auth_header() {
-        let client =
-            BitbucketClient::new("https://api.bitbucket.org/2.0".to_string(), None).unwrap();
+        let client = BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            None,
+            ClientOptions::default(),
+        )
+        .unwrap();
 
         let request = client.build_request(Method::GET, "/user").build().unwrap();
 
@@ -425,4 +1815,562 @@ mod tests {
             "Authorization header should NOT be present"
         );
     }
+
+    #[test]
+    fn test_web_base_url() {
+        let web = |api: &str| {
+            BitbucketClient::new(api.to_string(), None, ClientOptions::default())
+                .unwrap()
+                .web_base_url()
+                .map(String::from)
+        };
+        assert_eq!(
+            web(crate::constants::DEFAULT_API_URL).as_deref(),
+            Some("https://bitbucket.org/")
+        );
+        assert_eq!(web("https://bitbucket.example.com/rest/api/1.0"), None);
+    }
+
+    /// A client answering from `transport` instead of the network
+    fn mock_client(transport: &Arc<MockTransport>) -> BitbucketClient {
+        BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            Some(("user".to_string(), "pass".to_string())),
+            ClientOptions::default(),
+        )
+        .unwrap()
+        .with_transport(transport.clone())
+    }
+
+    #[tokio::test]
+    async fn test_request_changes_sets_participant_state() {
+        let path = "/2.0/repositories/ws/repo/pullrequests/7/request-changes";
+        let transport = Arc::new(MockTransport::new().json(
+            Method::POST,
+            path,
+            serde_json::json!({"state": "changes_requested"}),
+        ));
+
+        mock_client(&transport)
+            .request_changes("ws", "repo", 7)
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, Method::POST);
+        assert_eq!(
+            requests[0].url,
+            format!("https://api.bitbucket.org{}", path)
+        );
+        assert!(requests[0].body.is_none(), "{:?}", requests[0].body);
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_transport() {
+        let transport = Arc::new(MockTransport::new().json(
+            Method::GET,
+            "/2.0/user",
+            serde_json::json!({"display_name": "Me", "uuid": "{me}"}),
+        ));
+
+        let user = mock_client(&transport).get_current_user().await.unwrap();
+        assert_eq!(user.uuid, "{me}");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].url, "https://api.bitbucket.org/2.0/user");
+    }
+
+    #[tokio::test]
+    async fn test_transport_error_status_is_mapped() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .route(
+                    Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests/1",
+                    403,
+                    "forbidden",
+                )
+                .route(
+                    Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests/2",
+                    404,
+                    "no such PR",
+                )
+                .route(
+                    Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests/3",
+                    500,
+                    r#"{"error": {"message": "backend on fire"}}"#,
+                ),
+        );
+        let client = mock_client(&transport);
+
+        for (id, code) in [
+            (1, crate::error::EXIT_AUTH),
+            (2, crate::error::EXIT_NOT_FOUND),
+            (3, crate::error::EXIT_GENERAL),
+        ] {
+            let err = client.get_pull_request("ws", "repo", id).await.unwrap_err();
+            assert_eq!(crate::error::exit_code(&err), code, "PR #{}", id);
+        }
+
+        let err = client.get_pull_request("ws", "repo", 3).await.unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("500"), "{}", message);
+        assert!(message.contains("backend on fire"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_unauthorized_hints_login() {
+        let transport = Arc::new(MockTransport::new().route(
+            Method::GET,
+            "/2.0/repositories/ws/private/pullrequests",
+            401,
+            "Unauthorized",
+        ));
+        let client = BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            None,
+            ClientOptions::default(),
+        )
+        .unwrap()
+        .with_transport(transport.clone());
+
+        let err = client
+            .list_pull_requests("ws", "private", &["OPEN".to_string()], None, None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(crate::error::exit_code(&err), crate::error::EXIT_AUTH);
+        let message = format!("{:#}", err);
+        assert!(message.contains("bb auth login"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_inline_comment_payload() {
+        let transport = Arc::new(
+            MockTransport::new().respond(
+                Method::POST,
+                "/2.0/repositories/ws/repo/pullrequests/5/comments",
+                MockResponse::new(
+                    201,
+                    &serde_json::json!({
+                        "id": 9,
+                        "content": {"raw": "Off by one?"},
+                        "created_on": "2024-01-01T00:00:00+00:00",
+                        "user": {"display_name": "Bob", "uuid": "{bob}"}
+                    })
+                    .to_string(),
+                ),
+            ),
+        );
+
+        let comment = mock_client(&transport)
+            .post_pr_inline_comment("ws", "repo", 5, "src/main.rs", 42, "Off by one?")
+            .await
+            .unwrap();
+        assert_eq!(comment.id, 9);
+
+        let body: serde_json::Value =
+            serde_json::from_slice(transport.requests()[0].body.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "content": {"raw": "Off by one?"},
+                "inline": {"path": "src/main.rs", "to": 42}
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transport_receives_request_body() {
+        let transport = Arc::new(MockTransport::new().json(
+            Method::PUT,
+            "/2.0/repositories/ws/repo",
+            serde_json::json!({"name": "repo", "full_name": "ws/repo", "uuid": "{r}"}),
+        ));
+
+        mock_client(&transport)
+            .set_default_branch("ws", "repo", "develop")
+            .await
+            .unwrap();
+
+        let body: serde_json::Value =
+            serde_json::from_slice(transport.requests()[0].body.as_ref().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({"mainbranch": {"name": "develop"}}));
+    }
+
+    #[tokio::test]
+    async fn test_explain_never_reaches_transport() {
+        let transport = Arc::new(MockTransport::new());
+        let err = mock_client(&transport)
+            .with_explain(true)
+            .get_current_user()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err.downcast_ref(), Some(CliError::Explained)));
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_with_credentials_keeps_explain() {
+        let transport = Arc::new(MockTransport::new());
+        let err = mock_client(&transport)
+            .with_explain(true)
+            .with_credentials(
+                crate::constants::DEFAULT_API_URL.to_string(),
+                ("other".to_string(), "token".to_string()),
+            )
+            .get_current_user()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err.downcast_ref(), Some(CliError::Explained)));
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_with_participants_requests_field() {
+        let transport = Arc::new(MockTransport::new().json(
+            Method::GET,
+            "/2.0/repositories/ws/repo/pullrequests",
+            serde_json::json!({"values": []}),
+        ));
+
+        mock_client(&transport)
+            .list_pull_requests_with_participants("ws", "repo", &[], None, Some(10), None)
+            .await
+            .unwrap();
+
+        let url = reqwest::Url::parse(&transport.requests()[0].url).unwrap();
+        assert!(
+            url.query_pairs()
+                .any(|(k, v)| k == "fields" && v == "+values.participants")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_pull_request_by_branch_source_repo() {
+        let transport = Arc::new(MockTransport::new().json(
+            Method::GET,
+            "/2.0/repositories/ws/repo/pullrequests",
+            serde_json::json!({"values": []}),
+        ));
+        let client = mock_client(&transport);
+
+        let queries = |source_repo: Option<&str>| {
+            let client = client.clone();
+            let transport = transport.clone();
+            let source_repo = source_repo.map(str::to_string);
+            async move {
+                let before = transport.requests().len();
+                client
+                    .find_pull_request_by_branch("ws", "repo", "feature/x", source_repo.as_deref())
+                    .await
+                    .unwrap();
+                transport.requests()[before..]
+                    .iter()
+                    .map(|request| {
+                        let url = reqwest::Url::parse(&request.url).unwrap();
+                        url.query_pairs()
+                            .find(|(k, _)| k == "q")
+                            .map(|(_, v)| v.into_owned())
+                            .unwrap()
+                    })
+                    .collect::<Vec<String>>()
+            }
+        };
+
+        let branch_only = r#"source.branch.name="feature/x""#;
+        // Nothing from the fork, so the lookup retries with the branch alone
+        assert_eq!(
+            queries(Some("me/repo")).await,
+            [
+                r#"source.branch.name="feature/x" AND source.repository.full_name="me/repo""#,
+                branch_only,
+            ]
+        );
+        assert_eq!(queries(None).await, [branch_only]);
+        // The target repository itself (in any case) isn't a fork to filter on
+        assert_eq!(queries(Some("WS/Repo")).await, [branch_only]);
+    }
+
+    #[tokio::test]
+    async fn test_find_pull_request_by_branch_prefers_fork_match() {
+        let transport = Arc::new(MockTransport::new().json(
+            Method::GET,
+            "/2.0/repositories/ws/repo/pullrequests",
+            serde_json::json!({"values": [PullRequestJson::new(9)
+                .source_branch("feature/x")
+                .source_repo("me/repo")
+                .json()]}),
+        ));
+
+        let pr = mock_client(&transport)
+            .find_pull_request_by_branch("ws", "repo", "feature/x", Some("me/repo"))
+            .await
+            .unwrap();
+
+        assert_eq!(pr.map(|pr| pr.id), Some(9));
+        assert_eq!(transport.requests().len(), 1, "no fallback after a match");
+    }
+
+    #[tokio::test]
+    async fn test_find_pull_request_by_branch_skips_other_forks() {
+        let from = |id: u32, full_name: &str| {
+            PullRequestJson::new(id)
+                .source_branch("feature/x")
+                .source_repo(full_name)
+                .json()
+        };
+        // Nothing from me/repo; the branch-only lookup then sees `prs`
+        let lookup = |prs: Vec<serde_json::Value>| async move {
+            let path = "/2.0/repositories/ws/repo/pullrequests";
+            let transport = Arc::new(
+                MockTransport::new()
+                    .respond(
+                        Method::GET,
+                        path,
+                        MockResponse::new(200, r#"{"values": []}"#).once(),
+                    )
+                    .json(Method::GET, path, serde_json::json!({"values": prs})),
+            );
+            mock_client(&transport)
+                .find_pull_request_by_branch("ws", "repo", "feature/x", Some("me/repo"))
+                .await
+                .unwrap()
+                .map(|pr| pr.id)
+        };
+
+        // Two forks with a feature/x branch: neither is mine
+        assert_eq!(
+            lookup(vec![from(1, "alice/repo"), from(2, "bob/repo")]).await,
+            None
+        );
+        // A single candidate is taken (the remote may name a renamed repository)
+        assert_eq!(lookup(vec![from(3, "me/renamed")]).await, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_retries_only_idempotent_requests() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .route(Method::GET, "/2.0/user", 503, "busy")
+                .route(
+                    Method::POST,
+                    "/2.0/repositories/ws/repo/pullrequests/1/approve",
+                    503,
+                    "busy",
+                ),
+        );
+        let client = BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            None,
+            ClientOptions {
+                retries: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .with_transport(transport.clone());
+
+        assert!(client.get_current_user().await.is_err());
+        assert_eq!(
+            transport.requests().len(),
+            3,
+            "first attempt and two retries"
+        );
+
+        assert!(client.approve_pr("ws", "repo", 1).await.is_err());
+        assert_eq!(transport.requests().len(), 4, "POST is never retried");
+    }
+
+    #[tokio::test]
+    async fn test_stream_diff_resumes_cut_body() {
+        let diff = "diff --git a/a.rs b/a.rs\n@@ -1 +1 @@\n-old\n+new\n".repeat(20);
+        let cut = 100;
+        let path = "/2.0/repositories/ws/repo/pullrequests/3/diff";
+        let transport = Arc::new(
+            MockTransport::new()
+                .respond(
+                    Method::GET,
+                    path,
+                    MockResponse::new(200, &diff)
+                        .header("Accept-Ranges", "bytes")
+                        .cut_after(cut)
+                        .once(),
+                )
+                .respond(
+                    Method::GET,
+                    path,
+                    MockResponse::new(206, &diff[cut..]).header(
+                        "Content-Range",
+                        &format!("bytes {}-{}/{}", cut, diff.len() - 1, diff.len()),
+                    ),
+                ),
+        );
+        let client = BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            None,
+            ClientOptions::default(),
+        )
+        .unwrap()
+        .with_transport(transport.clone());
+
+        let mut sink = Vec::new();
+        let written = client
+            .stream_pull_request_diff("ws", "repo", 3, &mut sink, |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(String::from_utf8(sink).unwrap(), diff);
+        assert_eq!(written, diff.len() as u64);
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].headers.get("Range").is_none());
+        assert_eq!(
+            requests[1].headers.get("Range").unwrap(),
+            &format!("bytes={}-", cut)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_diff_without_ranges_fails_on_cut_body() {
+        let transport = Arc::new(MockTransport::new().respond(
+            Method::GET,
+            "/2.0/repositories/ws/repo/pullrequests/3/diff",
+            MockResponse::new(200, "diff --git a/a.rs b/a.rs\n").cut_after(5),
+        ));
+        let client = BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            None,
+            ClientOptions::default(),
+        )
+        .unwrap()
+        .with_transport(transport.clone());
+
+        let mut sink = Vec::new();
+        let err = client
+            .stream_pull_request_diff("ws", "repo", 3, &mut sink, |_| {})
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("Failed to read diff stream"));
+        assert_eq!(
+            transport.requests().len(),
+            1,
+            "not resumable without Accept-Ranges"
+        );
+    }
+
+    #[test]
+    fn test_content_range_start() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(content_range_start(&headers), None);
+        headers.insert(
+            reqwest::header::CONTENT_RANGE,
+            "bytes 100-199/200".parse().unwrap(),
+        );
+        assert_eq!(content_range_start(&headers), Some(100));
+        headers.insert(
+            reqwest::header::CONTENT_RANGE,
+            "bytes */200".parse().unwrap(),
+        );
+        assert_eq!(content_range_start(&headers), None);
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(
+            reqwest::StatusCode::SERVICE_UNAVAILABLE
+        ));
+        assert!(is_retryable_status(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_status(
+            reqwest::StatusCode::INTERNAL_SERVER_ERROR
+        ));
+        assert!(!is_retryable_status(reqwest::StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        use reqwest::StatusCode;
+
+        let now = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let wait = |status: StatusCode, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                reqwest::header::RETRY_AFTER,
+                HeaderValue::from_str(value).unwrap(),
+            );
+            retry_after(status, &headers, now)
+        };
+
+        assert_eq!(
+            wait(StatusCode::TOO_MANY_REQUESTS, "3"),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            wait(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Mon, 01 Jan 2024 00:00:10 GMT"
+            ),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            wait(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Sun, 31 Dec 2023 23:00:00 GMT"
+            ),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            wait(StatusCode::TOO_MANY_REQUESTS, "3600"),
+            Some(MAX_RETRY_AFTER)
+        );
+        assert_eq!(wait(StatusCode::TOO_MANY_REQUESTS, "soon"), None);
+        assert_eq!(wait(StatusCode::BAD_GATEWAY, "3"), None, "only 429 and 503");
+        assert_eq!(
+            retry_after(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), now),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_raw_responses_keep_unmodelled_fields() {
+        let body = r#"{"id": 7, "title": "Raw", "task_count": 3, "rendered": {"x": 1}}"#;
+        let transport = Arc::new(
+            MockTransport::new()
+                .route(
+                    Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests/7",
+                    200,
+                    body,
+                )
+                .route(
+                    Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests",
+                    200,
+                    // Not in key order, which the raw list must keep
+                    r#"{"values": [{"task_count":3,"id":7}, {"id": 8}]}"#,
+                ),
+        );
+        let client = mock_client(&transport);
+
+        let raw = client
+            .get_raw("/repositories/ws/repo/pullrequests/7")
+            .await
+            .unwrap();
+        assert_eq!(raw, body);
+
+        let prs = client
+            .list_pull_requests_raw("ws", "repo", &["OPEN".to_string()], None, Some(1), None)
+            .await
+            .unwrap();
+        let prs: Vec<&str> = prs.iter().map(|pr| pr.get()).collect();
+        assert_eq!(prs, [r#"{"task_count":3,"id":7}"#]);
+    }
 }
@@ -0,0 +1,72 @@
+//! API response bodies shared by the tests
+
+use serde_json::{Value, json};
+
+/// A pull request as the API returns it, built up from PR #`id`: open, by
+/// Alice, from `feature` (at commit `abc123`) in `ws/repo` into `main`
+pub struct PullRequestJson(Value);
+
+impl PullRequestJson {
+    pub fn new(id: u32) -> Self {
+        Self(json!({
+            "id": id,
+            "title": format!("PR {}", id),
+            "state": "OPEN",
+            "created_on": "2024-01-01T00:00:00+00:00",
+            "updated_on": "2024-01-02T00:00:00+00:00",
+            "author": {"display_name": "Alice", "uuid": "{alice}"},
+            "source": {
+                "branch": {"name": "feature"},
+                "repository": {"name": "repo", "full_name": "ws/repo", "uuid": "{repo}"},
+                "commit": {"hash": "abc123"}
+            },
+            "destination": {
+                "branch": {"name": "main"},
+                "repository": {"name": "repo", "full_name": "ws/repo", "uuid": "{repo}"}
+            },
+            "links": {"html": {"href": format!("https://bitbucket.org/ws/repo/pull-requests/{}", id)}}
+        }))
+    }
+
+    pub fn state(mut self, state: &str) -> Self {
+        self.0["state"] = json!(state);
+        self
+    }
+
+    pub fn source_branch(mut self, name: &str) -> Self {
+        self.0["source"]["branch"]["name"] = json!(name);
+        self
+    }
+
+    /// Open from a fork, e.g. `me/repo`
+    pub fn source_repo(mut self, full_name: &str) -> Self {
+        self.0["source"]["repository"]["full_name"] = json!(full_name);
+        self.0["source"]["repository"]["uuid"] = json!(format!("{{{}}}", full_name));
+        self
+    }
+
+    pub fn json(self) -> Value {
+        self.0
+    }
+
+    pub fn model(self) -> super::models::PullRequest {
+        serde_json::from_value(self.0).expect("fixture matches the model")
+    }
+}
+
+/// A `pr commits` payload: two commits by Alice (one linked to her
+/// account, one only by git author line), a merge with two parents, and a
+/// commit by Bob
+pub const COMMITS_JSON: &str = r#"[
+    {"hash": "m1", "message": "Merge branch 'main' into feature",
+     "date": "2024-01-03T00:00:00+00:00",
+     "author": {"raw": "Alice Smith <alice@example.com>",
+                "user": {"display_name": "Alice Smith", "uuid": "{a}"}},
+     "parents": [{"hash": "c2"}, {"hash": "x9"}]},
+    {"hash": "c2", "message": "Fix tests", "date": "2024-01-02T00:00:00+00:00",
+     "author": {"raw": "Bob <bob@example.com>"},
+     "parents": [{"hash": "c1"}]},
+    {"hash": "c1", "message": "Add login", "date": "2024-01-01T00:00:00+00:00",
+     "author": {"raw": "Alice Smith <alice@example.com>"},
+     "parents": [{"hash": "c0"}]}
+]"#;
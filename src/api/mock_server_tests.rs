@@ -0,0 +1,257 @@
+//! End-to-end tests of [`BitbucketClient`] against a local mock Bitbucket server
+//!
+//! Unit tests of the client and commands use the in-memory
+//! [`MockTransport`](super::transport::mock::MockTransport), which answers by
+//! method and path. The tests here are for what only shows up over the wire
+//! through the real `reqwest` transport: query strings and `next` links when
+//! paging, the `Authorization` header as sent, and streamed response bodies.
+use super::client::{BitbucketClient, ClientOptions};
+use super::fixtures::PullRequestJson;
+use serde_json::{Value, json};
+use wiremock::matchers::{header, method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const PRS_PATH: &str = "/2.0/repositories/ws/repo/pullrequests";
+
+/// A client for the mock server, authenticated as `me@example.com`
+async fn client(server: &MockServer) -> BitbucketClient {
+    BitbucketClient::new(
+        format!("{}/2.0", server.uri()),
+        Some(("me@example.com".to_string(), "secret-token".to_string())),
+        ClientOptions::default(),
+    )
+    .unwrap()
+}
+
+/// A client for the mock server with no credentials, as when nobody has logged in
+fn anonymous_client(server: &MockServer) -> BitbucketClient {
+    BitbucketClient::new(
+        format!("{}/2.0", server.uri()),
+        None,
+        ClientOptions::default(),
+    )
+    .unwrap()
+}
+
+fn pr_json(id: u32) -> Value {
+    PullRequestJson::new(id)
+        .source_branch(&format!("feature/{}", id))
+        .json()
+}
+
+fn comment_json(id: u32) -> Value {
+    json!({
+        "id": id,
+        "content": {"raw": format!("comment {}", id)},
+        "created_on": "2024-01-01T00:00:00+00:00",
+        "user": {"display_name": "Bob", "uuid": "{bob}"}
+    })
+}
+
+/// One page of a paginated response, linking to `next` if given
+fn page(values: Vec<Value>, next: Option<String>) -> ResponseTemplate {
+    let mut body = json!({"values": values, "pagelen": values.len()});
+    if let Some(next) = next {
+        body["next"] = json!(next);
+    }
+    ResponseTemplate::new(200).set_body_json(body)
+}
+
+#[tokio::test]
+async fn test_list_pull_requests_follows_next() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(PRS_PATH))
+        .and(query_param_is_missing("page"))
+        .respond_with(page(
+            vec![pr_json(1), pr_json(2)],
+            Some(format!("{}{}?page=2", server.uri(), PRS_PATH)),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(PRS_PATH))
+        .and(query_param("page", "2"))
+        .respond_with(page(vec![pr_json(3)], None))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let prs = client(&server)
+        .await
+        .list_pull_requests("ws", "repo", &["OPEN".to_string()], None, None, None)
+        .await
+        .unwrap();
+
+    let ids: Vec<u32> = prs.iter().map(|pr| pr.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_list_pull_requests_limit_stops_paging() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(PRS_PATH))
+        .and(query_param("pagelen", "2"))
+        .and(query_param_is_missing("page"))
+        .respond_with(page(
+            vec![pr_json(1), pr_json(2)],
+            Some(format!("{}{}?page=2&pagelen=2", server.uri(), PRS_PATH)),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(PRS_PATH))
+        .and(query_param("page", "2"))
+        .respond_with(page(
+            vec![pr_json(3), pr_json(4)],
+            Some(format!("{}{}?page=3&pagelen=2", server.uri(), PRS_PATH)),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // Reaching the limit on page 2 means page 3 is never requested
+    Mock::given(method("GET"))
+        .and(path(PRS_PATH))
+        .and(query_param("page", "3"))
+        .respond_with(page(vec![pr_json(5)], None))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let prs = client(&server)
+        .await
+        .list_pull_requests("ws", "repo", &[], None, Some(3), Some(2))
+        .await
+        .unwrap();
+
+    let ids: Vec<u32> = prs.iter().map(|pr| pr.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_comments_follow_next() {
+    let server = MockServer::start().await;
+    let comments_path = "/2.0/repositories/ws/repo/pullrequests/7/comments";
+    Mock::given(method("GET"))
+        .and(path(comments_path))
+        .and(query_param_is_missing("page"))
+        .respond_with(page(
+            vec![comment_json(1)],
+            Some(format!("{}{}?page=2", server.uri(), comments_path)),
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(comments_path))
+        .and(query_param("page", "2"))
+        .respond_with(page(vec![comment_json(2)], None))
+        .mount(&server)
+        .await;
+
+    let comments = client(&server)
+        .await
+        .get_pull_request_comments("ws", "repo", 7)
+        .await
+        .unwrap();
+
+    let ids: Vec<u32> = comments.iter().map(|c| c.id).collect();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn test_commit_statuses_follow_next() {
+    let server = MockServer::start().await;
+    let statuses_path = "/2.0/repositories/ws/repo/commit/abc123/statuses";
+    let status = |key: &str| json!({"key": key, "state": "SUCCESSFUL", "url": "https://ci"});
+    Mock::given(method("GET"))
+        .and(path(statuses_path))
+        .and(query_param_is_missing("page"))
+        .respond_with(page(
+            vec![status("build")],
+            Some(format!("{}{}?page=2", server.uri(), statuses_path)),
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(statuses_path))
+        .and(query_param("page", "2"))
+        .respond_with(page(vec![status("lint")], None))
+        .mount(&server)
+        .await;
+
+    let statuses = client(&server)
+        .await
+        .get_commit_statuses("ws", "repo", "abc123")
+        .await
+        .unwrap();
+
+    let keys: Vec<&str> = statuses.iter().map(|s| s.key.as_str()).collect();
+    assert_eq!(keys, vec!["build", "lint"]);
+}
+
+#[tokio::test]
+async fn test_unauthenticated_list_public_repo() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(PRS_PATH))
+        .respond_with(page(vec![pr_json(1)], None))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let prs = anonymous_client(&server)
+        .list_pull_requests("ws", "repo", &["OPEN".to_string()], None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(prs.len(), 1);
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(!requests[0].headers.contains_key("authorization"));
+}
+
+#[tokio::test]
+async fn test_auth_header_sent() {
+    let server = MockServer::start().await;
+    // base64("me@example.com:secret-token")
+    Mock::given(method("GET"))
+        .and(path("/2.0/user"))
+        .and(header(
+            "authorization",
+            "Basic bWVAZXhhbXBsZS5jb206c2VjcmV0LXRva2Vu",
+        ))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"display_name": "Me", "uuid": "{me}"})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let user = client(&server).await.get_current_user().await.unwrap();
+    assert_eq!(user.uuid, "{me}");
+}
+
+#[tokio::test]
+async fn test_stream_diff_writes_body() {
+    let server = MockServer::start().await;
+    let diff = "diff --git a/a.rs b/a.rs\n@@ -1 +1 @@\n-old\n+new\n".repeat(100);
+    Mock::given(method("GET"))
+        .and(path("/2.0/repositories/ws/repo/pullrequests/3/diff"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(diff.clone()))
+        .mount(&server)
+        .await;
+
+    let mut sink = Vec::new();
+    let mut last_progress = 0;
+    let written = client(&server)
+        .await
+        .stream_pull_request_diff("ws", "repo", 3, &mut sink, |bytes| last_progress = bytes)
+        .await
+        .unwrap();
+
+    assert_eq!(String::from_utf8(sink).unwrap(), diff);
+    assert_eq!(written, diff.len() as u64);
+    assert_eq!(last_progress, written);
+}
@@ -1,2 +1,8 @@
 pub mod client;
 pub mod models;
+pub mod transport;
+
+#[cfg(test)]
+pub mod fixtures;
+#[cfg(test)]
+mod mock_server_tests;
@@ -16,7 +16,9 @@ pub struct PullRequest {
     pub title: String,
     pub description: Option<String>,
     pub state: String,
+    #[serde(deserialize_with = "crate::utils::time::deserialize_timestamp")]
     pub created_on: String,
+    #[serde(deserialize_with = "crate::utils::time::deserialize_timestamp")]
     pub updated_on: String,
     pub author: User,
     pub source: Source,
@@ -24,6 +26,120 @@ pub struct PullRequest {
     pub links: Links,
     #[serde(default)]
     pub participants: Vec<Participant>,
+    /// Included in list responses, so no per-PR request is needed
+    pub comment_count: Option<u32>,
+    pub task_count: Option<u32>,
+    pub draft: Option<bool>,
+}
+
+impl PullRequest {
+    pub fn is_draft(&self) -> bool {
+        self.draft.unwrap_or(false)
+    }
+
+    /// Merged PRs can't be reopened, so their commits and branches are final
+    pub fn is_merged(&self) -> bool {
+        self.state == "MERGED"
+    }
+
+    /// Users who have approved the PR
+    pub fn approvers(&self) -> Vec<&User> {
+        self.participants
+            .iter()
+            .filter(|p| p.review_state() == Some(ParticipantState::Approved))
+            .map(|p| &p.user)
+            .collect()
+    }
+
+    /// Whether the user with `uuid` is a requested reviewer
+    pub fn has_reviewer(&self, uuid: &str) -> bool {
+        self.participants
+            .iter()
+            .any(|p| p.role == ParticipantRole::Reviewer && p.user.matches(uuid))
+    }
+
+    /// Whether the user with `uuid` is a requested reviewer who hasn't approved (yet)
+    pub fn awaits_approval_from(&self, uuid: &str) -> bool {
+        self.pending_reviewers().iter().any(|u| u.matches(uuid))
+    }
+
+    /// Requested reviewers who haven't approved (yet)
+    pub fn pending_reviewers(&self) -> Vec<&User> {
+        self.participants
+            .iter()
+            .filter(|p| {
+                p.role == ParticipantRole::Reviewer
+                    && p.review_state() != Some(ParticipantState::Approved)
+            })
+            .map(|p| &p.user)
+            .collect()
+    }
+
+    /// Whether the PR can be merged, judged from its diffstat
+    ///
+    /// Bitbucket marks files that conflict with the destination branch in the
+    /// diffstat's `status`, so the diffstat doubles as a conflict check.
+    pub fn mergeability(&self, diffstat: &[DiffStat]) -> Mergeability {
+        if self.state != "OPEN" {
+            return Mergeability::NotOpen;
+        }
+
+        let files: Vec<String> = diffstat
+            .iter()
+            .filter(|d| d.is_conflict())
+            .filter_map(|d| d.path().map(str::to_string))
+            .collect();
+
+        if files.is_empty() {
+            Mergeability::Mergeable
+        } else {
+            Mergeability::Conflicts { files }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Mergeability {
+    Mergeable,
+    Conflicts {
+        files: Vec<String>,
+    },
+    /// Already merged, declined or superseded
+    NotOpen,
+}
+
+/// Per-file change summary from the diffstat endpoint
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DiffStat {
+    /// added, removed, modified, renamed, or a conflict state such as "merge conflict"
+    pub status: String,
+    pub lines_added: Option<u32>,
+    pub lines_removed: Option<u32>,
+    pub old: Option<DiffStatFile>,
+    pub new: Option<DiffStatFile>,
+}
+
+impl DiffStat {
+    /// Path of the file, preferring its name after the change
+    pub fn path(&self) -> Option<&str> {
+        self.new
+            .as_ref()
+            .or(self.old.as_ref())
+            .map(|f| f.path.as_str())
+    }
+
+    pub fn is_conflict(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "merge conflict" | "local deleted" | "remote deleted"
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DiffStatFile {
+    pub path: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -33,6 +149,21 @@ pub struct User {
     pub nickname: Option<String>,
 }
 
+impl User {
+    /// Whether `query` names this user: their display name or nickname
+    /// (ignoring case), or their UUID with or without braces
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim();
+        let bare_uuid = |uuid: &str| uuid.trim_matches(['{', '}']).to_ascii_lowercase();
+        self.display_name.eq_ignore_ascii_case(query)
+            || self
+                .nickname
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(query))
+            || (!bare_uuid(query).is_empty() && bare_uuid(&self.uuid) == bare_uuid(query))
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Source {
     pub branch: Branch,
@@ -45,6 +176,43 @@ pub struct Branch {
     pub name: String,
 }
 
+/// Payload for creating a pull request
+#[derive(Debug, Serialize)]
+pub struct NewPullRequest {
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub source: BranchRef,
+    /// Omitted to let Bitbucket target the repository's main branch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<BranchRef>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reviewers: Vec<UserRef>,
+}
+
+/// A user referenced by UUID in a request payload
+#[derive(Debug, Serialize, PartialEq)]
+pub struct UserRef {
+    pub uuid: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BranchRef {
+    pub branch: Branch,
+}
+
+impl BranchRef {
+    pub fn new(name: &str) -> Self {
+        Self {
+            branch: Branch {
+                name: name.to_string(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Repository {
     pub name: String,
@@ -55,20 +223,76 @@ pub struct Repository {
     pub updated_on: Option<String>,
     pub website: Option<String>,
     pub is_private: Option<bool>,
+    /// The repository's default branch
+    pub mainbranch: Option<Branch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<RepositoryLinks>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RepositoryLinks {
+    /// The repository's web page
+    pub html: Link,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Links {
     pub html: Link,
+    /// API (not web) URLs for related resources
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    pub self_link: Option<Link>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commits: Option<Link>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<Link>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diffstat: Option<Link>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Link>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activity: Option<Link>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statuses: Option<Link>,
+}
+
+impl Links {
+    /// All link relations present in the response, as (name, href) pairs
+    pub fn entries(&self) -> Vec<(&'static str, &str)> {
+        let optional = [
+            ("self", &self.self_link),
+            ("commits", &self.commits),
+            ("diff", &self.diff),
+            ("diffstat", &self.diffstat),
+            ("comments", &self.comments),
+            ("activity", &self.activity),
+            ("statuses", &self.statuses),
+        ];
+
+        std::iter::once(("html", self.html.href.as_str()))
+            .chain(
+                optional
+                    .into_iter()
+                    .filter_map(|(name, link)| link.as_ref().map(|l| (name, l.href.as_str()))),
+            )
+            .collect()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Comment {
     pub id: u32,
     pub content: Content,
+    #[serde(deserialize_with = "crate::utils::time::deserialize_timestamp")]
     pub created_on: String,
     pub user: User,
     pub inline: Option<InlineContext>,
+    /// The comment this one replies to; `None` for the start of a thread
+    pub parent: Option<CommentRef>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CommentRef {
+    pub id: u32,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -91,15 +315,89 @@ pub struct Link {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Participant {
-    pub role: String,
+    pub role: ParticipantRole,
     pub user: User,
     pub approved: bool,
-    pub state: Option<String>,
+    pub state: Option<ParticipantState>,
 }
 
+impl Participant {
+    /// Review state, treating the `approved` flag as authoritative
+    pub fn review_state(&self) -> Option<ParticipantState> {
+        if self.approved {
+            Some(ParticipantState::Approved)
+        } else {
+            self.state
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ParticipantRole {
+    Reviewer,
+    Participant,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParticipantState {
+    Approved,
+    ChangesRequested,
+    #[serde(other)]
+    Other,
+}
+
+/// A commit, either a bare `{hash}` reference or a full entry from a commit listing
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Commit {
     pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<CommitAuthor>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<Commit>,
+}
+
+impl Commit {
+    /// Whether this commit merges two or more lines of history
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+
+    /// Who wrote the commit: their Bitbucket name if the author is linked to
+    /// an account, else the name from the git author line
+    pub fn author_name(&self) -> Option<&str> {
+        let author = self.author.as_ref()?;
+        if let Some(user) = &author.user {
+            return Some(&user.display_name);
+        }
+        // `raw` is the git author line, e.g. `Alice Smith <alice@example.com>`
+        let name = author.raw.split('<').next().unwrap_or("").trim();
+        Some(if name.is_empty() {
+            author.raw.trim()
+        } else {
+            name
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CommitAuthor {
+    pub raw: String,
+    pub user: Option<User>,
+}
+
+/// Number of commits one author made in a listing (`pr commits --authors`)
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AuthorCommits {
+    pub author: String,
+    pub commits: usize,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -110,3 +408,196 @@ pub struct CommitStatus {
     pub url: String,
     pub description: Option<String>,
 }
+
+/// Overall outcome of a commit's builds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BuildState {
+    #[serde(rename = "SUCCESSFUL")]
+    Successful,
+    #[serde(rename = "FAILED")]
+    Failed,
+    #[serde(rename = "INPROGRESS")]
+    InProgress,
+}
+
+impl BuildState {
+    /// Combine build statuses; `None` when there are none
+    ///
+    /// Any failed or stopped build fails the whole; otherwise any build not
+    /// yet successful means it's still in progress.
+    pub fn aggregate(statuses: &[CommitStatus]) -> Option<Self> {
+        if statuses.is_empty() {
+            return None;
+        }
+        let states = || statuses.iter().map(|s| s.state.as_str());
+        Some(if states().any(|s| s == "FAILED" || s == "STOPPED") {
+            BuildState::Failed
+        } else if states().all(|s| s == "SUCCESSFUL") {
+            BuildState::Successful
+        } else {
+            BuildState::InProgress
+        })
+    }
+}
+
+/// One entry of a pull request's activity feed
+///
+/// The API wraps each event in an object keyed by its kind (`approval`,
+/// `comment`, `update`, ...). Kinds this CLI doesn't know are kept as raw JSON.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Activity {
+    Approval { approval: ReviewEvent },
+    ChangesRequested { changes_requested: ReviewEvent },
+    Comment { comment: Comment },
+    Update { update: UpdateEvent },
+    Other(serde_json::Value),
+}
+
+impl Activity {
+    /// When the event happened, as sent by the API
+    pub fn date(&self) -> Option<&str> {
+        match self {
+            Activity::Approval { approval: e }
+            | Activity::ChangesRequested {
+                changes_requested: e,
+            } => Some(&e.date),
+            Activity::Comment { comment } => Some(&comment.created_on),
+            Activity::Update { update } => Some(&update.date),
+            Activity::Other(_) => None,
+        }
+    }
+
+    /// Who did it
+    pub fn actor(&self) -> Option<&User> {
+        match self {
+            Activity::Approval { approval: e }
+            | Activity::ChangesRequested {
+                changes_requested: e,
+            } => Some(&e.user),
+            Activity::Comment { comment } => Some(&comment.user),
+            Activity::Update { update } => Some(&update.author),
+            Activity::Other(_) => None,
+        }
+    }
+}
+
+/// An approval or change request in the activity feed
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReviewEvent {
+    #[serde(deserialize_with = "crate::utils::time::deserialize_timestamp")]
+    pub date: String,
+    pub user: User,
+}
+
+/// The PR being opened, edited, pushed to, merged or declined
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateEvent {
+    #[serde(deserialize_with = "crate::utils::time::deserialize_timestamp")]
+    pub date: String,
+    pub author: User,
+    /// The PR's state after the update (OPEN, MERGED, DECLINED, ...)
+    pub state: String,
+    /// Fields that changed (e.g. `title`, `reviewers`), each with old and new values
+    #[serde(default)]
+    pub changes: std::collections::BTreeMap<String, serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PR with no participants, in `state`
+    fn pull_request(state: &str) -> PullRequest {
+        crate::api::fixtures::PullRequestJson::new(1)
+            .state(state)
+            .model()
+    }
+
+    #[test]
+    fn test_pull_request_is_merged() {
+        for (state, merged) in [
+            ("OPEN", false),
+            ("MERGED", true),
+            ("DECLINED", false),
+            ("SUPERSEDED", false),
+        ] {
+            assert_eq!(pull_request(state).is_merged(), merged, "{}", state);
+        }
+    }
+
+    fn user(name: &str) -> User {
+        User {
+            display_name: name.to_string(),
+            uuid: format!("{{{}}}", name),
+            nickname: None,
+        }
+    }
+
+    #[test]
+    fn test_pull_request_review_requested() {
+        let mut pr = pull_request("OPEN");
+        pr.participants = vec![
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: user("Alice"),
+                approved: true,
+                state: Some(ParticipantState::Approved),
+            },
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: user("Bob"),
+                approved: false,
+                state: Some(ParticipantState::ChangesRequested),
+            },
+            Participant {
+                role: ParticipantRole::Participant,
+                user: user("Carol"),
+                approved: false,
+                state: None,
+            },
+        ];
+
+        assert!(pr.has_reviewer("{Alice}"));
+        assert!(!pr.awaits_approval_from("{Alice}"));
+        assert!(pr.has_reviewer("Bob"));
+        assert!(pr.awaits_approval_from("{Bob}"));
+        // Commenting doesn't make someone a reviewer
+        assert!(!pr.has_reviewer("{Carol}"));
+        assert!(!pr.awaits_approval_from("{Carol}"));
+    }
+
+    #[test]
+    fn test_repository_mainbranch_deserialize() {
+        let json = r#"{"name": "repo", "full_name": "ws/repo", "uuid": "{r}",
+            "mainbranch": {"type": "branch", "name": "develop"}}"#;
+        let repo: Repository = serde_json::from_str(json).unwrap();
+        assert_eq!(repo.mainbranch.unwrap().name, "develop");
+
+        let json = r#"{"name": "empty", "full_name": "ws/empty", "uuid": "{e}"}"#;
+        let repo: Repository = serde_json::from_str(json).unwrap();
+        assert!(repo.mainbranch.is_none());
+    }
+
+    #[test]
+    fn test_commit_parents_and_authors() {
+        let commits: Vec<Commit> =
+            serde_json::from_str(crate::api::fixtures::COMMITS_JSON).unwrap();
+        let merges: Vec<&str> = commits
+            .iter()
+            .filter(|c| c.is_merge())
+            .map(|c| c.hash.as_str())
+            .collect();
+        assert_eq!(merges, vec!["m1"]);
+        assert_eq!(commits[1].author_name(), Some("Bob"));
+        assert_eq!(commits[2].author_name(), Some("Alice Smith"));
+    }
+
+    #[test]
+    fn test_bare_commit_reference_serializes_unchanged() {
+        let commit: Commit = serde_json::from_str(r#"{"hash": "abc"}"#).unwrap();
+        assert!(!commit.is_merge());
+        assert_eq!(commit.author_name(), None);
+        assert_eq!(serde_json::to_string(&commit).unwrap(), r#"{"hash":"abc"}"#);
+    }
+}
@@ -0,0 +1,218 @@
+//! The HTTP layer underneath [`BitbucketClient`](super::client::BitbucketClient)
+//!
+//! The client builds each request (URL, auth, headers, body) and hands it to
+//! an [`HttpTransport`] to be sent. [`ReqwestTransport`] is the real network
+//! implementation; tests swap in [`mock::MockTransport`] to answer requests
+//! from memory.
+use anyhow::{Context, Result};
+use reqwest::{Client, Request, Response};
+use std::future::Future;
+use std::pin::Pin;
+
+/// The future returned by [`HttpTransport::execute`]
+pub type ResponseFuture<'a> = Pin<Box<dyn Future<Output = Result<Response>> + Send + 'a>>;
+
+/// Sends a fully built request and returns the raw response
+///
+/// Implementations only move bytes: status checks, error mapping and
+/// `--explain` are handled by the client before and after.
+pub trait HttpTransport: Send + Sync {
+    fn execute(&self, request: Request) -> ResponseFuture<'_>;
+}
+
+/// Sends requests over the network with a pooled [`reqwest::Client`]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn execute(&self, request: Request) -> ResponseFuture<'_> {
+        Box::pin(async move {
+            self.client
+                .execute(request)
+                .await
+                .context("Failed to send request")
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    //! An in-memory [`HttpTransport`] for tests that shouldn't touch the network
+    //!
+    //! The default for unit tests. Routes match on method and path only, so
+    //! tests of query strings, pagination links or headers on the wire belong
+    //! in the local-server suite in `api/mock_server_tests.rs`.
+    use super::*;
+    use reqwest::Method;
+    use std::sync::Mutex;
+
+    /// A request as the mock received it
+    #[derive(Debug, Clone)]
+    pub struct RecordedRequest {
+        pub method: Method,
+        pub url: String,
+        pub headers: reqwest::header::HeaderMap,
+        pub body: Option<Vec<u8>>,
+    }
+
+    /// A canned response, registered with [`MockTransport::respond`]
+    #[derive(Clone)]
+    pub struct MockResponse {
+        status: u16,
+        body: String,
+        headers: Vec<(String, String)>,
+        cut_after: Option<usize>,
+        once: bool,
+    }
+
+    impl MockResponse {
+        pub fn new(status: u16, body: &str) -> Self {
+            Self {
+                status,
+                body: body.to_string(),
+                headers: Vec::new(),
+                cut_after: None,
+                once: false,
+            }
+        }
+
+        pub fn header(mut self, name: &str, value: &str) -> Self {
+            self.headers.push((name.to_string(), value.to_string()));
+            self
+        }
+
+        /// Fail the body with a connection error after its first `bytes` bytes
+        pub fn cut_after(mut self, bytes: usize) -> Self {
+            self.cut_after = Some(bytes);
+            self
+        }
+
+        /// Answer a single request; later ones go to the next matching route
+        pub fn once(mut self) -> Self {
+            self.once = true;
+            self
+        }
+    }
+
+    /// A canned response for requests with a given method and URL path
+    struct Route {
+        method: Method,
+        path: String,
+        response: MockResponse,
+        used: bool,
+    }
+
+    /// Answers requests from registered routes and records every request
+    ///
+    /// Routes match on method and URL path (the query string is ignored), in
+    /// the order they were added; unmatched requests get a 404.
+    #[derive(Default)]
+    pub struct MockTransport {
+        routes: Mutex<Vec<Route>>,
+        requests: Mutex<Vec<RecordedRequest>>,
+    }
+
+    impl MockTransport {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Respond to `method path` with `response`
+        pub fn respond(self, method: Method, path: &str, response: MockResponse) -> Self {
+            self.routes.lock().unwrap().push(Route {
+                method,
+                path: path.to_string(),
+                response,
+                used: false,
+            });
+            self
+        }
+
+        /// Respond to `method path` with `status` and `body`
+        pub fn route(self, method: Method, path: &str, status: u16, body: &str) -> Self {
+            self.respond(method, path, MockResponse::new(status, body))
+        }
+
+        /// Respond to `method path` with `200 OK` and `body` as JSON
+        pub fn json(self, method: Method, path: &str, body: serde_json::Value) -> Self {
+            self.route(method, path, 200, &body.to_string())
+        }
+
+        /// Every request received so far, in order
+        pub fn requests(&self) -> Vec<RecordedRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    /// A body that sends some bytes and then fails, like a dropped connection
+    struct CutBody {
+        data: Option<bytes::Bytes>,
+    }
+
+    impl http_body::Body for CutBody {
+        type Data = bytes::Bytes;
+        type Error = std::io::Error;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+            std::task::Poll::Ready(Some(match self.data.take() {
+                Some(data) => Ok(http_body::Frame::data(data)),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionReset,
+                    "connection reset by mock",
+                )),
+            }))
+        }
+    }
+
+    impl HttpTransport for MockTransport {
+        fn execute(&self, request: Request) -> ResponseFuture<'_> {
+            let recorded = RecordedRequest {
+                method: request.method().clone(),
+                url: request.url().to_string(),
+                headers: request.headers().clone(),
+                body: request
+                    .body()
+                    .and_then(|b| b.as_bytes())
+                    .map(<[u8]>::to_vec),
+            };
+            let response = {
+                let mut routes = self.routes.lock().unwrap();
+                match routes.iter_mut().find(|r| {
+                    !r.used && r.method == recorded.method && r.path == request.url().path()
+                }) {
+                    Some(route) => {
+                        route.used = route.response.once;
+                        route.response.clone()
+                    }
+                    None => MockResponse::new(404, "no mock route"),
+                }
+            };
+            self.requests.lock().unwrap().push(recorded);
+
+            Box::pin(async move {
+                let mut builder = http::Response::builder().status(response.status);
+                for (name, value) in &response.headers {
+                    builder = builder.header(name, value);
+                }
+                let body = match response.cut_after {
+                    Some(bytes) => reqwest::Body::wrap(CutBody {
+                        data: Some(bytes::Bytes::from(response.body[..bytes].to_string())),
+                    }),
+                    None => reqwest::Body::from(response.body),
+                };
+                let response = builder.body(body).context("Invalid mock response")?;
+                Ok(Response::from(response))
+            })
+        }
+    }
+}
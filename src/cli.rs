@@ -30,6 +30,42 @@ pub struct Cli {
     /// Output as JSON
     #[arg(long, global = true)]
     pub json: bool,
+
+    /// Max concurrent API requests for multi-request operations (1 = fully serial)
+    #[arg(long, global = true, default_value = "8", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_concurrency: u32,
+
+    /// Output as JSON wrapped in a versioned {schema_version, data} envelope
+    #[arg(long, global = true)]
+    pub json_envelope: bool,
+
+    /// Indent JSON output even when it's too large to be indented by default
+    #[arg(long, global = true)]
+    pub pretty: bool,
+
+    /// Ignore the global config and any local .bb-cli (flags, environment and git remote still apply)
+    #[arg(long, global = true)]
+    pub no_config: bool,
+
+    /// Seconds to wait for a connection, or for more data from a response, before giving up
+    #[arg(long, global = true, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: Option<u64>,
+
+    /// Retry requests that time out or hit a temporary server error this many times
+    #[arg(long, global = true, value_name = "N")]
+    pub retries: Option<u32>,
+
+    /// Print the first API request the command would send (method, URL, headers, body) and exit
+    #[arg(long, global = true)]
+    pub explain: bool,
+
+    /// Print URLs as plain text instead of clickable terminal hyperlinks
+    #[arg(long, global = true)]
+    pub no_hyperlinks: bool,
+
+    /// Disable colored output (like setting NO_COLOR)
+    #[arg(long, global = true)]
+    pub no_color: bool,
 }
 
 #[derive(Subcommand)]
@@ -2,9 +2,13 @@ use anyhow::{Context, Result, anyhow};
 use clap::{Args, Subcommand};
 use std::io::{self, Write};
 
+use super::examples;
 use crate::api::models::User;
 use crate::config::manager::Profile;
 use crate::display::ui;
+use crate::error::CliError;
+use crate::utils::auth::EnteredToken;
+use crate::utils::formatting::sanitize_for_terminal;
 
 #[derive(Args)]
 pub struct AuthArgs {
@@ -17,13 +21,29 @@ pub enum AuthCommands {
     /// Login to Bitbucket
     Login,
     /// Logout
-    Logout,
+    Logout {
+        /// Log out every configured profile
+        #[arg(long)]
+        all: bool,
+        /// Skip the confirmation prompt for --all
+        #[arg(short, long, requires = "all")]
+        yes: bool,
+    },
     /// Check authentication status
     Status,
+    /// List configured profiles and whether each has a stored token
+    List,
+    /// Print the active user (no network unless --verify)
+    #[command(after_help = examples::AUTH_WHOAMI)]
+    Whoami {
+        /// Confirm the user against the API
+        #[arg(long)]
+        verify: bool,
+    },
 }
 
 /// Check if user is authenticated by verifying credentials and API access
-async fn get_authenticated_user(profile: Option<&Profile>) -> Result<User> {
+async fn get_authenticated_user(ctx: &AppContext, profile: Option<&Profile>) -> Result<User> {
     let username = profile
         .and_then(|p| p.user.as_ref())
         .ok_or_else(|| anyhow!("No user configured in active profile"))?;
@@ -34,8 +54,11 @@ async fn get_authenticated_user(profile: Option<&Profile>) -> Result<User> {
     let base_url = crate::constants::DEFAULT_API_URL.to_string();
 
     // Verify credentials against API
-    let client =
-        crate::api::client::BitbucketClient::new(base_url, Some((username.clone(), api_token)))?;
+    let client = with_profile_headers(
+        ctx.client
+            .with_credentials(base_url, (username.clone(), api_token)),
+        profile,
+    )?;
     client
         .get_current_user()
         .await
@@ -43,23 +66,53 @@ async fn get_authenticated_user(profile: Option<&Profile>) -> Result<User> {
 }
 
 /// Attempt to log in with provided credentials
-async fn check_login(username: &str, api_token: &str) -> Result<User> {
+///
+/// Returns the user and the token's granted scopes (if reported by the server).
+async fn check_login(
+    ctx: &AppContext,
+    username: &str,
+    api_token: &str,
+) -> Result<(User, Option<Vec<String>>)> {
     let base_url = crate::constants::DEFAULT_API_URL.to_string();
 
     // Verify credentials work with API first
-    let client = crate::api::client::BitbucketClient::new(
-        base_url,
-        Some((username.to_string(), api_token.to_string())),
+    let config = crate::config::manager::ProfileConfig::load().unwrap_or_default();
+    let client = with_profile_headers(
+        ctx.client
+            .with_credentials(base_url, (username.to_string(), api_token.to_string())),
+        config.get_profile(ctx.profile.as_deref()),
     )?;
-    let user = client
-        .get_current_user()
+    let (user, scopes) = client
+        .get_current_user_with_scopes()
         .await
         .context("Authentication failed - check username and password")?;
 
     // Save to keyring after verification
     crate::utils::auth::save_credentials(username, api_token)?;
 
-    Ok(user)
+    let cached = crate::utils::auth::CachedUser {
+        username: username.to_string(),
+        display_name: user.display_name.clone(),
+        uuid: user.uuid.clone(),
+        nickname: user.nickname.clone(),
+        scopes: scopes.clone(),
+    };
+    if let Err(e) = crate::utils::auth::save_cached_user(&cached) {
+        crate::utils::debug::log(&format!("Failed to cache user details: {}", e));
+    }
+
+    Ok((user, scopes))
+}
+
+/// Apply the profile's custom `headers` table, if any, to a client
+fn with_profile_headers(
+    client: crate::api::client::BitbucketClient,
+    profile: Option<&Profile>,
+) -> Result<crate::api::client::BitbucketClient> {
+    match profile.and_then(|p| p.headers.as_ref()) {
+        Some(headers) => client.with_headers(headers),
+        None => Ok(client),
+    }
 }
 
 /// Delete credentials from keyring
@@ -69,13 +122,32 @@ fn check_logout(username: &str) -> Result<()> {
     Ok(())
 }
 
+/// Ask before wiping every profile's credentials
+fn confirm_logout_all(users: &[&str]) -> Result<bool> {
+    use std::io::IsTerminal;
+
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("Refusing to log out every profile without confirmation; pass --yes");
+    }
+
+    let prompt = format!(
+        "Log out {} account(s) and delete their stored tokens ({})?",
+        users.len(),
+        users.join(", ")
+    );
+    Ok(dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?)
+}
+
 mod messages;
 use messages::auth as msg;
 
 // TODO: Improve view layer of this command.
 use crate::context::AppContext;
 
-pub async fn handle(_ctx: &AppContext, args: AuthArgs) -> Result<()> {
+pub async fn handle(ctx: &AppContext, args: AuthArgs) -> Result<()> {
     match args.command {
         AuthCommands::Login => {
             print!("Email: ");
@@ -91,38 +163,91 @@ pub async fn handle(_ctx: &AppContext, args: AuthArgs) -> Result<()> {
 
             print!("API Token: ");
             io::stdout().flush()?;
-            let mut api_token = String::new();
-            io::stdin().read_line(&mut api_token)?;
-            let api_token = api_token.trim();
-
-            if api_token.is_empty() {
+            let api_token = crate::utils::auth::read_entered_token()?;
+            if api_token.trim().is_empty() {
                 ui::error(msg::EMPTY_API_TOKEN);
                 return Ok(());
             }
 
+            let entered = crate::utils::auth::clean_entered_token(&api_token)?;
+            match &entered {
+                EnteredToken::Clean(_) => {}
+                EnteredToken::Rejoined(_) => ui::warning(msg::TOKEN_REJOINED),
+                EnteredToken::HasSpaces(_) => ui::warning(msg::TOKEN_HAS_SPACES),
+            }
+            let api_token = entered.token();
+
             ui::info(msg::VERIFYING_CREDENTIALS);
 
-            match check_login(username, api_token).await {
-                Ok(user) => {
+            match check_login(ctx, username, api_token).await {
+                Ok((user, scopes)) => {
                     ui::success(msg::AUTH_SUCCESS);
                     ui::info(&msg::CREDENTIALS_SAVED.replace("{}", username));
 
-                    let mut user_info =
-                        vec![("Display Name", user.display_name), ("UUID", user.uuid)];
+                    match scopes {
+                        Some(granted) => {
+                            let missing = crate::api::client::missing_scopes(
+                                crate::constants::REQUIRED_SCOPES,
+                                &granted,
+                            );
+                            if !missing.is_empty() {
+                                ui::warning(
+                                    &msg::MISSING_SCOPES.replace("{}", &missing.join(", ")),
+                                );
+                            }
+                        }
+                        None => crate::utils::debug::log(msg::SCOPES_UNKNOWN),
+                    }
+
+                    let mut user_info = vec![
+                        ("Display Name", sanitize_for_terminal(&user.display_name)),
+                        ("UUID", user.uuid),
+                    ];
                     if let Some(nickname) = user.nickname {
-                        user_info.push(("Nickname", nickname));
+                        user_info.push(("Nickname", sanitize_for_terminal(&nickname)));
                     }
 
                     crate::utils::formatting::print_key_value_table(user_info);
                 }
+                Err(e) if crate::error::is_explained(&e) => return Err(e),
                 Err(e) => {
-                    ui::error(&format!("Login failed: {:#}", e));
+                    return Err(CliError::Auth(format!("Login failed: {:#}", e)).into());
+                }
+            }
+        }
+        AuthCommands::Logout { all: true, yes } => {
+            let config = crate::config::manager::ProfileConfig::load()?;
+            let users = config.profile_users();
+
+            if users.is_empty() {
+                ui::info(msg::NO_PROFILES);
+                return Ok(());
+            }
+            if !yes && !confirm_logout_all(&users)? {
+                ui::info(msg::LOGOUT_CANCELLED);
+                return Ok(());
+            }
+
+            let results = crate::utils::auth::delete_all_credentials(&users)?;
+            let mut failed = 0;
+            for (user, result) in users.iter().zip(&results) {
+                match result {
+                    Ok(()) => ui::success(&msg::LOGGED_OUT.replace("{}", user)),
+                    Err(e) => {
+                        failed += 1;
+                        ui::error(&format!("Logout failed for {}: {:#}", user, e));
+                    }
                 }
             }
+            if failed > 0 {
+                return Err(anyhow!("{} of {} logouts failed", failed, users.len()));
+            }
         }
-        AuthCommands::Logout => {
+        AuthCommands::Logout { all: false, .. } => {
             let config = crate::config::manager::ProfileConfig::load().ok();
-            let default_user = config.as_ref().and_then(|c| c.get_default_user());
+            let default_user = config
+                .as_ref()
+                .and_then(|c| c.get_profile_user(ctx.profile.as_deref()));
 
             let username = if let Some(user) = default_user.as_ref() {
                 ui::info(&msg::LOGOUT_USER.replace("{}", user));
@@ -150,23 +275,137 @@ pub async fn handle(_ctx: &AppContext, args: AuthArgs) -> Result<()> {
             ui::info(msg::CHECKING_STATUS);
 
             let config = crate::config::manager::ProfileConfig::load()?;
-            let profile = config.get_active_profile();
+            let profile = config.get_profile(ctx.profile.as_deref());
 
-            match get_authenticated_user(profile).await {
+            match get_authenticated_user(ctx, profile).await {
                 Ok(user) => {
                     ui::success(msg::AUTHENTICATED);
-                    let mut user_info =
-                        vec![("Display Name", user.display_name), ("UUID", user.uuid)];
+                    let mut user_info = vec![
+                        ("Display Name", sanitize_for_terminal(&user.display_name)),
+                        ("UUID", user.uuid),
+                    ];
                     if let Some(nickname) = user.nickname {
-                        user_info.push(("Nickname", nickname));
+                        user_info.push(("Nickname", sanitize_for_terminal(&nickname)));
+                    }
+                    if let Some(scopes) = profile
+                        .and_then(|p| p.user.as_deref())
+                        .and_then(crate::utils::auth::load_cached_user)
+                        .and_then(|cached| cached.scopes)
+                    {
+                        user_info.push(("Scopes", scopes.join(", ")));
                     }
 
                     crate::utils::formatting::print_key_value_table(user_info);
                 }
+                Err(e) if crate::error::is_explained(&e) => return Err(e),
                 Err(e) => {
-                    ui::error(&format!("{}: {:#}", msg::NOT_AUTHENTICATED, e));
                     ui::info(msg::LOGIN_REQUIRED);
+                    return Err(
+                        CliError::Auth(format!("{}: {:#}", msg::NOT_AUTHENTICATED, e)).into(),
+                    );
+                }
+            }
+        }
+        AuthCommands::List => {
+            let config = crate::config::manager::ProfileConfig::load()?;
+            let active = config.profile_name(ctx.profile.as_deref());
+
+            let mut profiles: Vec<_> = config.profiles.iter().flatten().collect();
+            profiles.sort_by(|a, b| a.0.cmp(b.0));
+
+            if profiles.is_empty() {
+                ui::info(msg::NO_PROFILES);
+                return Ok(());
+            }
+
+            // The token itself is never read out, only whether one exists
+            let rows: Vec<(&String, Option<&String>, Result<bool>)> = profiles
+                .into_iter()
+                .map(|(name, profile)| {
+                    let stored = match &profile.user {
+                        Some(email) => crate::utils::auth::has_credentials(email),
+                        None => Ok(false),
+                    };
+                    (name, profile.user.as_ref(), stored)
+                })
+                .collect();
+
+            if ctx.json {
+                let output: Vec<_> = rows
+                    .iter()
+                    .map(|(name, email, stored)| {
+                        serde_json::json!({
+                            "profile": name,
+                            "email": email,
+                            "active": name.as_str() == active,
+                            "credential": stored.as_ref().ok(),
+                            "error": stored.as_ref().err().map(|e| format!("{:#}", e)),
+                        })
+                    })
+                    .collect();
+                ui::print_json(&output)?;
+                return Ok(());
+            }
+
+            let table_rows = rows
+                .iter()
+                .map(|(name, email, stored)| {
+                    let profile = if name.as_str() == active {
+                        format!("{} (active)", name)
+                    } else {
+                        name.to_string()
+                    };
+                    let (credential, color) = match stored {
+                        Ok(true) => ("yes".to_string(), comfy_table::Color::Green),
+                        Ok(false) => ("no".to_string(), comfy_table::Color::Red),
+                        Err(e) => (format!("error: {:#}", e), comfy_table::Color::Yellow),
+                    };
+                    vec![
+                        comfy_table::Cell::new(sanitize_for_terminal(&profile)),
+                        comfy_table::Cell::new(sanitize_for_terminal(
+                            email.map(String::as_str).unwrap_or("-"),
+                        )),
+                        comfy_table::Cell::new(credential).fg(color),
+                    ]
+                })
+                .collect();
+
+            println!(
+                "{}",
+                crate::utils::formatting::format_table(
+                    vec!["Profile", "Email", "Credential"],
+                    table_rows
+                )
+            );
+        }
+        AuthCommands::Whoami { verify } => {
+            let config = crate::config::manager::ProfileConfig::load()?;
+            let email = config
+                .get_profile_user(ctx.profile.as_deref())
+                .ok_or_else(|| {
+                    CliError::Auth(format!("{}. {}", msg::NO_ACTIVE_USER, msg::LOGIN_REQUIRED))
+                })?;
+
+            let (display_name, uuid) = if verify {
+                let user =
+                    get_authenticated_user(ctx, config.get_profile(ctx.profile.as_deref())).await?;
+                (Some(user.display_name), Some(user.uuid))
+            } else {
+                match crate::utils::auth::load_cached_user(&email) {
+                    Some(cached) => (Some(cached.display_name), Some(cached.uuid)),
+                    None => (None, None),
                 }
+            };
+
+            if ctx.json {
+                let output = serde_json::json!({
+                    "email": email,
+                    "display_name": display_name,
+                    "uuid": uuid,
+                });
+                ui::print_json(&output)?;
+            } else {
+                println!("{}", email);
             }
         }
     }
@@ -2,15 +2,24 @@
 pub mod auth {
     pub const EMPTY_EMAIL: &str = "Email cannot be empty";
     pub const EMPTY_API_TOKEN: &str = "API Token cannot be empty";
+    pub const TOKEN_REJOINED: &str = "Removed whitespace from the pasted API token";
+    pub const TOKEN_HAS_SPACES: &str =
+        "API token contains spaces; if it was pasted, check that it was copied whole";
 
     pub const LOGIN_REQUIRED: &str = "Run 'bb auth login' to authenticate";
     pub const VERIFYING_CREDENTIALS: &str = "Verifying credentials...";
     pub const AUTH_SUCCESS: &str = "Authentication successful!";
     pub const CREDENTIALS_SAVED: &str = "Credentials saved for user '{}'";
+    pub const MISSING_SCOPES: &str =
+        "API token is missing scopes: {}. Reviews, comments, and merges may fail";
+    pub const SCOPES_UNKNOWN: &str = "Server did not report token scopes; skipping scope check";
     pub const LOGOUT_USER: &str = "Logging out user: {}";
     pub const LOGGED_OUT: &str = "Logged out {}";
     pub const NO_USERNAME: &str = "No username provided";
+    pub const LOGOUT_CANCELLED: &str = "Logout cancelled";
     pub const CHECKING_STATUS: &str = "Checking authentication status...";
     pub const AUTHENTICATED: &str = "Authenticated";
     pub const NOT_AUTHENTICATED: &str = "Not authenticated";
+    pub const NO_ACTIVE_USER: &str = "No user configured in active profile";
+    pub const NO_PROFILES: &str = "No profiles configured. Run 'bb auth login' to add one";
 }
@@ -1,6 +1,8 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
 
+use super::examples;
+use crate::config::keys;
 use crate::display::ui;
 
 #[derive(Args)]
@@ -12,23 +14,85 @@ pub struct ConfigArgs {
 #[derive(Subcommand)]
 pub enum ConfigCommands {
     /// Initialize configuration
-    Init,
+    Init {
+        /// Overwrite an existing local .bb-cli instead of merging into it
+        #[arg(long)]
+        force: bool,
+    },
     /// List configuration
-    List,
+    #[command(after_help = examples::CONFIG_LIST)]
+    List {
+        /// Show the effective values and the layer each comes from (flag, local, git remote, global)
+        #[arg(long)]
+        sources: bool,
+    },
     /// Set configuration value
-    Set { key: String, value: String },
+    #[command(after_help = examples::CONFIG_SET)]
+    Set {
+        key: String,
+        value: String,
+        /// Write to the project's .bb-cli file instead of the global config
+        #[arg(long)]
+        local: bool,
+        /// Set keys or values this version doesn't recognize
+        #[arg(long)]
+        force: bool,
+    },
     /// Get configuration value (or entire config if no key specified)
-    Get { key: Option<String> },
+    ///
+    /// Accepts short keys (user, workspace, repository, remote) or any dotted
+    /// key such as profile.work.user.
+    #[command(after_help = examples::CONFIG_GET)]
+    Get {
+        key: Option<String>,
+        /// Show the workspace, repository or remote a command would use here
+        /// (environment, .bb-cli, git remote, profile) instead of the stored value
+        #[arg(long, requires = "key")]
+        effective: bool,
+    },
+    /// Upgrade an older global config file to the current layout
+    ///
+    /// The original is backed up to config.toml.bak before any change.
+    #[command(after_help = examples::CONFIG_MIGRATE)]
+    Migrate {
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check that credentials can be stored, read back and deleted
+    ///
+    /// Uses a throwaway entry; existing credentials are not touched.
+    Doctor,
 }
 
-use crate::context::AppContext;
+use crate::context::{AppContext, Coordinates, Source, Sourced};
 
 pub async fn handle(ctx: &AppContext, args: ConfigArgs) -> Result<()> {
     match args.command {
-        ConfigCommands::Init => {
-            crate::config::setup::interactive_init()?;
+        ConfigCommands::Init { force } => {
+            crate::config::setup::interactive_init(force)?;
         }
-        ConfigCommands::List => {
+        ConfigCommands::List { sources: true } => {
+            let values = effective_values(ctx, &ctx.global_config()?);
+
+            if ctx.json {
+                let map: serde_json::Map<String, serde_json::Value> = values
+                    .into_iter()
+                    .map(|(key, value, source)| {
+                        (
+                            key.to_string(),
+                            serde_json::json!({"value": value, "source": source.to_string()}),
+                        )
+                    })
+                    .collect();
+                ui::print_json(&map)?;
+            } else {
+                for (key, value, source) in values {
+                    println!("{}={} ({})", key, value, source);
+                }
+            }
+        }
+        ConfigCommands::List { sources: false } => {
             let config = crate::config::manager::ProfileConfig::load_global().unwrap_or_default();
             let repo_root = crate::git::get_repo_root().ok();
             let local_config =
@@ -83,12 +147,54 @@ pub async fn handle(ctx: &AppContext, args: ConfigArgs) -> Result<()> {
                 }
             }
         }
-        ConfigCommands::Set { key, value } => {
+        ConfigCommands::Set {
+            key,
+            value,
+            local: true,
+            force,
+        } => {
+            // Short keys live in the [project] table of the local file
+            let real_key = match key.as_str() {
+                "workspace" | "repository" | "remote" => format!("project.{}", key),
+                "user" => {
+                    anyhow::bail!("'user' selects the global profile and can't be set locally")
+                }
+                _ => key,
+            };
+            if !force {
+                keys::validate(&real_key, &value, keys::Scope::Local)?;
+            }
+
+            let repo_root = crate::git::get_repo_root()
+                .context("--local requires running inside a git repository")?;
+            crate::config::manager::set_local_config_value(&repo_root, &real_key, &value)?;
+            ui::success(&format!(
+                "Set {} = {} in {}",
+                real_key,
+                value,
+                crate::constants::LOCAL_CONFIG_FILE_NAME
+            ));
+        }
+        ConfigCommands::Set {
+            key,
+            value,
+            local: false,
+            force,
+        } => {
             // Context-aware setting
             // If key is "user", set global user.
             // If key is "workspace", "repository", "remote", set it for the ACTIVE profile.
             // Otherwise, set as provided (full key).
 
+            // Profiles don't hold a repository or remote; only project files do
+            if !force && ["repository", "remote"].contains(&key.as_str()) {
+                anyhow::bail!(
+                    "'{}' is only read from a project's {} file; pass --local to set it there",
+                    key,
+                    crate::constants::LOCAL_CONFIG_FILE_NAME
+                );
+            }
+
             let real_key = if key == "user" {
                 key
             } else if ["workspace", "repository", "remote"].contains(&key.as_str()) {
@@ -101,48 +207,218 @@ pub async fn handle(ctx: &AppContext, args: ConfigArgs) -> Result<()> {
                 key
             };
 
+            if !force {
+                keys::validate(&real_key, &value, keys::Scope::Global)?;
+            }
+
             crate::config::manager::set_config_value(&real_key, &value)?;
             ui::success(&format!("Set {} = {}", real_key, value));
         }
-        ConfigCommands::Get { key } => {
-            let config = crate::config::manager::ProfileConfig::load()?;
-
+        ConfigCommands::Get { key, effective } => {
             // If no key provided, show full config
-            if key.is_none() || key.as_ref().is_none_or(|s| s.is_empty()) {
-                println!("{:#?}", config);
+            let key = match key.filter(|k| !k.is_empty()) {
+                Some(k) => k,
+                None => {
+                    let config = crate::config::manager::ProfileConfig::load()?;
+                    println!("{:#?}", config);
+                    return Ok(());
+                }
+            };
+
+            let repo_root = crate::git::get_repo_root().ok();
+            let resolved = if effective {
+                let coordinates = effective_coordinates(ctx, repo_root.as_deref())?;
+                effective_value(&key, coordinates)?
+            } else {
+                crate::config::manager::get_config_value(&key, repo_root.as_deref())?
+            };
+
+            if ctx.json {
+                let output = serde_json::json!({
+                    "key": key,
+                    "value": resolved.as_ref().map(|(v, _)| v),
+                    "source": resolved.as_ref().map(|(_, s)| s.to_string()),
+                });
+                ui::print_json(&output)?;
                 return Ok(());
             }
-            let p = config.get_active_profile();
-
-            match key {
-                Some(key) => match key.as_str() {
-                    "user" => println!("{}", config.user.as_deref().unwrap_or("Not set")),
-                    "workspace" => {
-                        println!(
-                            "{}",
-                            p.and_then(|prof| prof.workspace.as_deref())
-                                .unwrap_or("Not set")
-                        )
-                    }
-                    _ => {
-                        ui::error(&format!("Unknown key: '{}'", key));
-                        ui::info("Valid keys: user, workspace");
-                    }
-                },
+
+            match resolved {
+                Some((value, source)) => println!("{} ({})", value, source),
+                None => println!("Not set"),
+            }
+        }
+        ConfigCommands::Migrate { dry_run } => {
+            let changes = match crate::config::manager::migrate_global_config(dry_run)? {
+                Some(changes) => changes,
                 None => {
-                    println!("Current Profile Settings:");
-                    println!("  User: {}", config.user.as_deref().unwrap_or("Not set"));
-                    if let Some(profile) = p {
-                        println!(
-                            "  Workspace: {}",
-                            profile.workspace.as_deref().unwrap_or("Not set")
-                        );
-                    } else {
-                        ui::warning("No active profile found.");
+                    ui::info("No global config file found; nothing to migrate");
+                    return Ok(());
+                }
+            };
+
+            if ctx.json {
+                ui::print_json(&serde_json::json!({
+                    "dry_run": dry_run,
+                    "changes": changes,
+                }))?;
+                return Ok(());
+            }
+
+            if changes.is_empty() {
+                ui::success("Configuration is already up to date");
+                return Ok(());
+            }
+
+            for change in &changes {
+                println!("  {}", change);
+            }
+            if dry_run {
+                ui::info(&format!("{} change(s) would be applied", changes.len()));
+            } else {
+                ui::success(&format!(
+                    "Applied {} change(s); previous config saved as config.toml.bak",
+                    changes.len()
+                ));
+            }
+        }
+        ConfigCommands::Doctor => {
+            let diagnosis = crate::utils::auth::diagnose_store();
+
+            if ctx.json {
+                ui::print_json(&serde_json::json!({
+                    "credential_store": diagnosis.kind,
+                    "backend": diagnosis.backend,
+                    "ok": diagnosis.result.is_ok(),
+                    "error": diagnosis.result.as_ref().err().map(|e| format!("{:#}", e)),
+                    "remediation": diagnosis.remediation,
+                }))?;
+            } else {
+                println!(
+                    "Credential store: {} ({})",
+                    diagnosis.kind, diagnosis.backend
+                );
+                match &diagnosis.result {
+                    Ok(()) => ui::success("Write/read/delete round-trip passed"),
+                    Err(_) => {
+                        if let Some(remediation) = &diagnosis.remediation {
+                            ui::info(remediation);
+                        }
                     }
                 }
             }
+
+            diagnosis.result.context("Credential store check failed")?;
         }
     }
     Ok(())
 }
+
+/// Coordinates as a command run in `repo_root` would resolve them, minus
+/// -R/--remote, and without the config files under `--no-config`
+fn effective_coordinates(
+    ctx: &AppContext,
+    repo_root: Option<&std::path::Path>,
+) -> Result<Coordinates> {
+    let local_config = ctx.local_config(repo_root)?;
+    Ok(crate::context::resolve_coordinates(
+        repo_root,
+        &ctx.global_config()?,
+        local_config.as_ref().and_then(|c| c.project.as_ref()),
+        crate::context::CoordinateOverrides {
+            profile: ctx.profile.as_deref(),
+            ..Default::default()
+        },
+        crate::context::CoordinateEnv::from_env(),
+    ))
+}
+
+/// The resolved value of a short key for `config get --effective`
+fn effective_value(key: &str, coordinates: Coordinates) -> Result<Option<Sourced>> {
+    match key {
+        "workspace" => Ok(coordinates.workspace),
+        "repository" => Ok(coordinates.repo),
+        "remote" => Ok(coordinates.remote),
+        _ => anyhow::bail!(
+            "--effective resolves workspace, repository and remote; '{}' is only read \
+             from the config files, so 'bb config get {}' shows what commands use",
+            key,
+            key
+        ),
+    }
+}
+
+/// Values in effect for this invocation with the layer each came from, as
+/// resolved by [`AppContext::new`]
+fn effective_values(
+    ctx: &AppContext,
+    config: &crate::config::manager::ProfileConfig,
+) -> Vec<(&'static str, String, Source)> {
+    let profile = config.profile_name(ctx.profile.as_deref());
+    let mut values = vec![("profile", profile.to_string(), ctx.sources.profile.clone())];
+
+    if let Some(user) = config.get_profile_user(ctx.profile.as_deref()) {
+        values.push(("user", user, Source::Profile(profile.to_string())));
+    }
+    let resolved = [
+        ("workspace", &ctx.workspace, &ctx.sources.workspace),
+        ("repository", &ctx.repo, &ctx.sources.repo),
+    ];
+    for (key, value, source) in resolved {
+        if let (Some(value), Some(source)) = (value, source) {
+            values.push((key, value.clone(), source.clone()));
+        }
+    }
+    if let Some(source) = &ctx.sources.remote {
+        let remote = ctx.remote.as_deref().unwrap_or("origin");
+        values.push(("remote", remote.to_string(), source.clone()));
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(no_config: bool) -> AppContext {
+        let client = crate::api::client::BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            None,
+            Default::default(),
+        )
+        .unwrap();
+        AppContext {
+            no_config,
+            ..AppContext::for_test(client)
+        }
+    }
+
+    #[test]
+    fn test_list_sources_without_config_has_no_user() {
+        let ctx = context(true);
+        let values = effective_values(&ctx, &ctx.global_config().unwrap());
+
+        assert!(values.iter().all(|(key, _, _)| *key != "user"));
+        assert_eq!(values[0].0, "profile");
+        assert_eq!(values[0].2, Source::Default);
+    }
+
+    #[test]
+    fn test_get_effective_without_config_ignores_local_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(crate::constants::LOCAL_CONFIG_FILE_NAME),
+            "[project]\nworkspace = \"stray-ws\"\nrepository = \"stray-repo\"\n",
+        )
+        .unwrap();
+
+        let coordinates = effective_coordinates(&context(true), Some(dir.path())).unwrap();
+        assert_eq!(coordinates.workspace, None);
+        assert_eq!(coordinates.repo, None);
+
+        // The file is read when config isn't disabled
+        let project = context(false).local_config(Some(dir.path())).unwrap();
+        let project = project.and_then(|c| c.project).unwrap();
+        assert_eq!(project.workspace.as_deref(), Some("stray-ws"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Usage examples shown at the end of each command's `--help`
+
+pub const PR_LIST: &str = "\
+Examples:
+  bb pr list
+  bb pr list --state MERGED --state DECLINED --limit 20
+  bb pr list --all-states --count
+  bb pr list --columns id,title,destination
+  bb pr list --search 'cache fix' --all-states
+  bb pr list --state MERGED --since 2024-03-01 --markdown
+  bb pr list --compact
+  bb pr list --review-requested  # waiting on you highlighted
+  bb pr list --watch --interval 60
+  bb pr list -R my-workspace/other-repo --json
+  bb pr list --raw-json    # every field the API returns";
+
+pub const PR_VIEW: &str = "\
+Examples:
+  bb pr view              # PR for the current branch
+  bb pr 42                # same as bb pr view 42
+  bb pr view 42 --comments
+  bb pr view 42 --comments-first
+  bb pr view 42 --comments-only
+  bb pr view 42 --web
+  bb pr view 42 --web --file src/main.rs
+  bb pr view 42 --print-links
+  bb pr view 42 --no-statuses --no-comments --json
+  bb pr view 42 --refresh  # skip the cache for a merged PR
+  bb pr view 42 --raw-json  # the API response, unchanged
+  bb pr view --branch feature/login";
+
+pub const PR_DIFF: &str = "\
+Examples:
+  bb pr diff 42
+  bb pr diff 42 'src/**/*.rs'
+  bb pr diff 42 --pattern-file review-paths.txt
+  bb pr diff --name-only
+  bb pr diff 42 --name-status
+  bb pr diff 42 --stat
+  bb pr diff 42 --max-diff-size 500
+  bb pr diff 42 --inline-comments
+  bb pr diff 42 --wrap
+  bb pr diff 42 --refresh
+  bb pr diff --compare feature/login..main";
+
+pub const PR_COMMENTS: &str = "\
+Examples:
+  bb pr comments
+  bb pr comments 42 --sort newest
+  bb pr comments 42 --since 2024-06-01
+  bb pr comments 42 --new
+  bb pr comments 42 --thread 1187      # one discussion with its replies
+  bb pr comments 42 --author 'Jane Doe' --sort newest
+  bb pr comments 42 --json";
+
+pub const PR_CREATE: &str = "\
+Examples:
+  bb pr create --fill
+  bb pr create --fill --draft
+  bb pr create --title-from-branch -b 'Closes #12'
+  bb pr create --fill --push               # push the branch first if needed
+  bb pr create --web -B develop            # finish in the browser
+  bb pr create --fill -r @backend-team -r '{8e6c5bd0-0a4e-4e51-9ce4-5f1c2a3b4c5d}'
+  bb pr create -t 'Add login page' -b 'Closes #12' -B develop";
+
+pub const PR_APPROVALS: &str = "\
+Examples:
+  bb pr approvals 42
+  bb pr approvals --json
+  bb pr approvals --branch feature/login --json";
+
+pub const PR_ACTIVITY: &str = "\
+Examples:
+  bb pr activity 42
+  bb pr activity --branch feature/login --json";
+
+pub const PR_COMMITS: &str = "\
+Examples:
+  bb pr commits 42
+  bb pr commits 42 --no-merges
+  bb pr commits --authors --no-merges";
+
+pub const PR_DECLINE: &str = "\
+Examples:
+  bb pr decline 42
+  bb pr decline 42 43 --yes
+  bb pr decline --older-than 90d
+  bb pr decline --older-than 12w --limit 20 --yes --json";
+
+pub const PR_CHECK_APPROVALS: &str = "\
+Examples:
+  bb pr check-approvals 42 --min 2
+  bb pr check-approvals --min 2 --json";
+
+pub const PR_STATUS: &str = "\
+Examples:
+  bb pr status 42
+  bb pr status --exit-code   # 0 passed, 1 failed, 2 still running
+  bb pr status 42 --json";
+
+pub const PR_REVIEW: &str = "\
+Examples:
+  bb pr review 42 --approve
+  bb pr review 42 --request-changes
+  bb pr review 42 --comment --body 'Looks good, one nit inline'
+  bb pr review 42 --from-file review.json";
+
+pub const AUTH_WHOAMI: &str = "\
+Examples:
+  bb auth whoami
+  bb auth whoami --verify --json";
+
+pub const CONFIG_GET: &str = "\
+Examples:
+  bb config get workspace
+  bb config get workspace --effective  # what commands here would use
+  bb config get profile.default.user --json";
+
+pub const CONFIG_LIST: &str = "\
+Examples:
+  bb config list
+  bb config list --sources             # e.g. workspace=acme (local .bb-cli)
+  bb config list --sources -R acme/web";
+
+pub const CONFIG_SET: &str = "\
+Examples:
+  bb config set user work
+  bb config set profile.work.workspace acme
+  bb config set credential_store file
+  bb config set pr.default_limit 200
+  bb config set --local workspace acme
+  bb config set --force some.future_key value";
+
+pub const CONFIG_MIGRATE: &str = "\
+Examples:
+  bb config migrate --dry-run
+  bb config migrate";
+
+pub const REPO_LIST: &str = "\
+Examples:
+  bb repo list
+  bb repo list --workspace acme --limit 20
+  bb repo list --mine --count
+  bb repo list --role admin --json
+  bb repo list --refresh";
+
+pub const REPO_DEFAULT_BRANCH: &str = "\
+Examples:
+  bb repo default-branch
+  bb repo default-branch develop
+  bb repo default-branch release/2.0 --yes";
+
+pub const REPO_BROWSE: &str = "\
+Examples:
+  bb repo browse              # repository for the current directory
+  bb repo browse acme/widgets
+  bb repo browse --pipelines
+  bb repo browse widgets --settings";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+
+    const ALL: [&str; 20] = [
+        CONFIG_LIST,
+        PR_LIST,
+        PR_VIEW,
+        PR_DIFF,
+        PR_COMMENTS,
+        PR_CREATE,
+        PR_REVIEW,
+        PR_APPROVALS,
+        PR_CHECK_APPROVALS,
+        PR_STATUS,
+        PR_DECLINE,
+        PR_ACTIVITY,
+        PR_COMMITS,
+        AUTH_WHOAMI,
+        CONFIG_GET,
+        CONFIG_SET,
+        CONFIG_MIGRATE,
+        REPO_LIST,
+        REPO_DEFAULT_BRANCH,
+        REPO_BROWSE,
+    ];
+
+    /// Split an example line like a shell would, for the simple quoting used here
+    fn split_example(line: &str) -> Vec<String> {
+        let line = line.split("  #").next().unwrap_or(line);
+        let mut args = Vec::new();
+        let mut current = String::new();
+        let mut quoted = false;
+        for c in line.trim().chars() {
+            match c {
+                '\'' => quoted = !quoted,
+                ' ' if !quoted => {
+                    if !current.is_empty() {
+                        args.push(std::mem::take(&mut current));
+                    }
+                }
+                _ => current.push(c),
+            }
+        }
+        if !current.is_empty() {
+            args.push(current);
+        }
+        args
+    }
+
+    #[test]
+    fn test_examples_parse() {
+        for example in ALL {
+            for line in example
+                .lines()
+                .map(str::trim)
+                .filter(|l| l.starts_with("bb "))
+            {
+                let args = split_example(line);
+                assert!(
+                    Cli::try_parse_from(&args).is_ok(),
+                    "example does not parse: {}",
+                    line
+                );
+            }
+        }
+    }
+}
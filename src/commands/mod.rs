@@ -1,4 +1,5 @@
 pub mod auth;
 pub mod config;
+mod examples;
 pub mod pr;
 pub mod repo;
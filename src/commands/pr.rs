@@ -1,40 +1,156 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
 
+pub mod create;
+pub mod decline;
 pub mod review;
+pub mod watch;
 
+use super::examples;
 use crate::display::{pr as pr_display, ui};
+use crate::error::CliError;
+use crate::utils::concurrency;
 
-#[derive(Args)]
+/// `bb pr <COMMAND>`, or `bb pr <ID>` as a shorthand for `bb pr view <ID>`
+#[derive(Args, Default)]
+#[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
 pub struct PrArgs {
     #[command(subcommand)]
-    pub command: PrCommands,
+    pub command: Option<PrCommands>,
+    /// View this pull request (same as `bb pr view ID`)
+    #[arg(value_name = "ID")]
+    pub id: Option<String>,
+}
+
+impl PrArgs {
+    /// The subcommand to run, with a bare ID standing for `view`
+    ///
+    /// The ID is parsed as `bb pr view <ID>`, so `view`'s own defaults apply.
+    /// Any other word was most likely a mistyped subcommand, and parsing it as
+    /// one gets clap's "similar subcommand" tip. Resolved in `main` before
+    /// anything is set up, so the error exits like any other usage error.
+    pub fn into_command(self) -> Result<PrCommands, clap::Error> {
+        use clap::FromArgMatches;
+
+        if let Some(command) = self.command {
+            return Ok(command);
+        }
+        let argv = match &self.id {
+            Some(id) if id.parse::<u32>().is_ok() => vec!["pr", "view", id],
+            Some(word) => vec!["pr", word],
+            None => vec!["pr", "view"],
+        };
+        let matches = PrCommands::augment_subcommands(clap::Command::new("pr").bin_name("bb pr"))
+            .try_get_matches_from(argv)?;
+        PrCommands::from_arg_matches(&matches)
+    }
 }
 
 #[derive(Subcommand)]
 pub enum PrCommands {
     /// List pull requests
+    #[command(after_help = examples::PR_LIST)]
     List {
-        /// Filter by state
-        #[arg(long, default_value = "OPEN")]
-        state: String,
+        /// Filter by state (repeatable, case-insensitive, e.g. --state open --state merged)
+        #[arg(long, default_value = "OPEN", value_parser = parse_pr_state)]
+        state: Vec<String>,
+
+        /// Include PRs in every state (OPEN, MERGED, DECLINED, SUPERSEDED)
+        #[arg(long, conflicts_with = "state")]
+        all_states: bool,
+
+        /// Max number of PRs to fetch in total [default: pr.default_limit, else 50]
+        #[arg(long)]
+        limit: Option<u32>,
 
-        /// Max number of PRs to fetch
-        #[arg(long, default_value = "50")]
-        limit: u32,
+        /// PRs requested per API call (max 100; fewer round-trips when larger)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        page_size: Option<u32>,
+
+        /// Print only the number of matching PRs
+        #[arg(long)]
+        count: bool,
+
+        /// Don't cap the table at the terminal width (full titles)
+        #[arg(long)]
+        no_trunc: bool,
+        /// Only PRs whose title or description contains this text
+        #[arg(long)]
+        search: Option<String>,
+        /// Only PRs updated since a date (YYYY-MM-DD), timestamp, or age ago (e.g. 30d)
+        #[arg(long, value_name = "WHEN", value_parser = crate::utils::time::parse_since)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Comma-separated table columns [default: all]
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<pr_display::PrColumn>,
+        /// Print a Markdown list of linked PRs, e.g. for release notes
+        #[arg(long, conflicts_with_all = ["count", "columns", "no_trunc"])]
+        markdown: bool,
+        /// One line per PR instead of a table: `#42  [OPEN]  Title  (author → main)`
+        #[arg(long, conflicts_with_all = ["count", "columns", "no_trunc", "markdown"])]
+        compact: bool,
+        /// Keep the table on screen, refreshing it and highlighting changed PRs
+        ///
+        /// Not with `--explain`, which would print the same request every poll.
+        /// Titles are cut to fit the screen, so not with `--no-trunc` either.
+        #[arg(long, conflicts_with_all = ["count", "markdown", "compact", "no_trunc"])]
+        watch: bool,
+        /// Seconds between refreshes with --watch
+        #[arg(long, value_name = "SECS", default_value_t = 30, requires = "watch",
+              value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Only PRs where you're a reviewer; those you haven't approved are highlighted
+        ///
+        /// With `--watch`, rows are highlighted when they change instead.
+        #[arg(long, alias = "target-me")]
+        review_requested: bool,
+        /// Print each PR as the API returned it, with fields `--json` leaves out
+        ///
+        /// Like `--json`, no matches print `[]` and exit 0 rather than failing.
+        #[arg(long, conflicts_with_all = ["count", "columns", "no_trunc", "markdown", "compact", "watch"])]
+        raw_json: bool,
     },
     /// View a pull request
+    #[command(after_help = examples::PR_VIEW)]
     View {
         /// PR ID (optional, infers from branch if missing)
         id: Option<u32>,
         /// Open in browser
         #[arg(long)]
         web: bool,
+        /// With --web, open the diff scrolled to this file
+        #[arg(long, value_name = "PATH", requires = "web")]
+        file: Option<String>,
         /// Show comments
         #[arg(long)]
         comments: bool,
+        /// Show comments above the PR details
+        #[arg(long, conflicts_with = "comments_only")]
+        comments_first: bool,
+        /// Show only the comments, without the PR details
+        #[arg(long)]
+        comments_only: bool,
+        /// Don't fetch comments, even for --json (which then omits the comments field)
+        #[arg(long, conflicts_with_all = ["comments", "comments_first", "comments_only"])]
+        no_comments: bool,
+        /// Don't fetch build statuses for the source commit
+        #[arg(long)]
+        no_statuses: bool,
+        /// List the PR's related URLs (web page, API diff, commits, ...)
+        #[arg(long, conflicts_with = "web")]
+        print_links: bool,
+        /// Find the PR for this branch instead of the current one
+        #[arg(long)]
+        branch: Option<String>,
+        /// Fetch a merged PR from the API instead of the cache
+        #[arg(long)]
+        refresh: bool,
+        /// Print the API's response body unchanged, with fields `--json` leaves out
+        #[arg(long, conflicts_with_all = ["web", "print_links", "comments", "comments_first", "comments_only"])]
+        raw_json: bool,
     },
     /// Show diff
+    #[command(after_help = examples::PR_DIFF)]
     Diff {
         /// PR ID (optional, infers from branch if missing) or file patterns
         #[arg(trailing_var_arg = true)]
@@ -42,96 +158,692 @@ pub enum PrCommands {
         /// Display only names of changed files
         #[arg(long)]
         name_only: bool,
+        /// Display changed files with a status letter: A(dded), D(eleted), M(odified), R(enamed)
+        #[arg(
+            long,
+            conflicts_with_all = ["name_only", "web", "inline_comments", "wrap", "stat", "max_diff_size"]
+        )]
+        name_status: bool,
         /// Open the pull request diff in the browser
         #[arg(long, short = 'w')]
         web: bool,
         /// Skip files larger than this number of lines
         #[arg(long)]
         max_diff_size: Option<usize>,
+        /// Compare two branches instead of a PR (format: source..destination)
+        #[arg(long, value_name = "A..B", conflicts_with = "web")]
+        compare: Option<String>,
+        /// Find the PR for this branch instead of the current one
+        #[arg(long, conflicts_with = "compare")]
+        branch: Option<String>,
+        /// Show existing inline comments beneath the lines they refer to
+        #[arg(long, conflicts_with_all = ["compare", "name_only", "web"])]
+        inline_comments: bool,
+        /// Soft-wrap long lines to the terminal width
+        #[arg(long, conflicts_with_all = ["name_only", "web"])]
+        wrap: bool,
+        /// Summarize added/removed lines per file without downloading the patch
+        #[arg(
+            long,
+            conflicts_with_all = ["compare", "name_only", "web", "inline_comments", "wrap", "max_diff_size"]
+        )]
+        stat: bool,
+        /// Download the patch again instead of reusing the cached copy
+        #[arg(long, conflicts_with_all = ["compare", "web", "stat"])]
+        refresh: bool,
+        /// Also filter by the patterns in this file, one per line (`#` starts a comment)
+        #[arg(
+            long,
+            value_name = "PATH",
+            alias = "pattern-from-file",
+            conflicts_with = "web"
+        )]
+        pattern_file: Option<std::path::PathBuf>,
     },
     /// Show comments
+    #[command(after_help = examples::PR_COMMENTS)]
     Comments {
         /// PR ID (optional, infers from branch if missing)
         id: Option<u32>,
+        /// Order comments by creation time
+        #[arg(long, value_enum, default_value_t = CommentSort::Oldest)]
+        sort: CommentSort,
+        /// Find the PR for this branch instead of the current one
+        #[arg(long)]
+        branch: Option<String>,
+        /// Only comments after this comment ID or time (RFC 3339 or YYYY-MM-DD)
+        #[arg(long, value_parser = parse_comment_marker)]
+        since: Option<CommentMarker>,
+        /// Only comments not seen by a previous --new run, then mark them seen
+        #[arg(long, conflicts_with = "since")]
+        new: bool,
+        /// Show only the thread started by this comment, with its replies indented
+        #[arg(long, value_name = "COMMENT_ID", conflicts_with_all = ["since", "new"])]
+        thread: Option<u32>,
+        /// Only comments by this user (display name, nickname or UUID)
+        ///
+        /// Not with --new, which marks every fetched comment seen, including
+        /// ones by other authors that this filter would hide.
+        #[arg(long, value_name = "NAME_OR_UUID", conflicts_with = "new")]
+        author: Option<String>,
+    },
+    /// Show a pull request's timeline of approvals, comments and updates
+    #[command(after_help = examples::PR_ACTIVITY)]
+    Activity {
+        /// PR ID (optional, infers from branch if missing)
+        id: Option<u32>,
+        /// Find the PR for this branch instead of the current one
+        #[arg(long)]
+        branch: Option<String>,
     },
+    /// List the commits of a pull request
+    #[command(after_help = examples::PR_COMMITS)]
+    Commits {
+        /// PR ID (optional, infers from branch if missing)
+        id: Option<u32>,
+        /// Find the PR for this branch instead of the current one
+        #[arg(long)]
+        branch: Option<String>,
+        /// Leave out merge commits (commits with more than one parent)
+        #[arg(long)]
+        no_merges: bool,
+        /// Show how many commits each author made instead of listing them
+        #[arg(long)]
+        authors: bool,
+    },
+    /// Create a pull request from the current branch
+    #[command(after_help = examples::PR_CREATE)]
+    Create(create::CreateArgs),
     /// Review a pull request
+    #[command(after_help = examples::PR_REVIEW)]
     Review(review::ReviewArgs),
+    /// Decline pull requests by ID, or every open one that's gone stale
+    #[command(after_help = examples::PR_DECLINE)]
+    Decline(decline::DeclineArgs),
+    /// List the users who have approved a pull request
+    ///
+    /// Exits with status 3 when there are no approvals.
+    #[command(after_help = examples::PR_APPROVALS)]
+    Approvals {
+        /// PR ID (optional, infers from branch if missing)
+        id: Option<u32>,
+        /// Find the PR for this branch instead of the current one
+        #[arg(long)]
+        branch: Option<String>,
+    },
+    /// Check that a pull request has at least --min approvals, e.g. as a CI gate
+    ///
+    /// Exits with status 3 when it has fewer.
+    #[command(after_help = examples::PR_CHECK_APPROVALS)]
+    CheckApprovals {
+        /// PR ID (optional, infers from branch if missing)
+        id: Option<u32>,
+        /// Approvals required to pass
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        min: u32,
+        /// Find the PR for this branch instead of the current one
+        #[arg(long)]
+        branch: Option<String>,
+    },
+    /// Show the build status of a pull request's latest commit
+    ///
+    /// With --exit-code, exits 0 when every build passed, 1 when any failed or
+    /// was stopped, and 2 while any is still running or none were reported.
+    #[command(after_help = examples::PR_STATUS)]
+    Status {
+        /// PR ID (optional, infers from branch if missing)
+        id: Option<u32>,
+        /// Find the PR for this branch instead of the current one
+        #[arg(long)]
+        branch: Option<String>,
+        /// Set the exit status from the overall build result
+        #[arg(long)]
+        exit_code: bool,
+    },
+    /// Mark a draft pull request as ready for review
+    Ready {
+        /// PR ID (optional, infers from branch if missing)
+        id: Option<u32>,
+    },
+    /// Follow a pull request by adding yourself as a participant
+    Watch {
+        /// PR ID (optional, infers from branch if missing)
+        id: Option<u32>,
+    },
+    /// Stop following a pull request
+    Unwatch {
+        /// PR ID (optional, infers from branch if missing)
+        id: Option<u32>,
+    },
 }
 
 use crate::api::client::BitbucketClient;
 
 use crate::context::AppContext;
 
-pub async fn handle(ctx: &AppContext, args: PrArgs) -> Result<()> {
-    match args.command {
-        PrCommands::List { state, limit } => {
-            let workspace = ctx
-                .workspace
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("No workspace found"))?;
-            let repo = ctx
-                .repo
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("No repository found"))?;
-
-            let prs = ctx
-                .client
-                .list_pull_requests(workspace, repo, &state, Some(limit))
-                .await?;
+/// Ordering for `pr comments --sort`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommentSort {
+    Oldest,
+    Newest,
+}
+
+/// Where `pr comments --since` starts: after a comment or a point in time
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommentMarker {
+    Id(u32),
+    Time(chrono::DateTime<chrono::Utc>),
+}
+
+impl std::fmt::Display for CommentMarker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommentMarker::Id(id) => write!(f, "comment #{}", id),
+            CommentMarker::Time(time) => write!(f, "{}", time.to_rfc3339()),
+        }
+    }
+}
+
+/// Parse a `--since` value: a comment ID, an RFC 3339 timestamp, or a date
+fn parse_comment_marker(value: &str) -> std::result::Result<CommentMarker, String> {
+    let value = value.trim();
+    if let Ok(id) = value.parse::<u32>() {
+        return Ok(CommentMarker::Id(id));
+    }
+    if let Some(time) = crate::utils::time::parse_timestamp(value) {
+        return Ok(CommentMarker::Time(time));
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|midnight| CommentMarker::Time(midnight.and_utc()))
+        .ok_or_else(|| {
+            format!(
+                "'{}' is not a comment ID, RFC 3339 timestamp, or YYYY-MM-DD date",
+                value
+            )
+        })
+}
+
+/// Cache namespace holding the newest comment ID seen per PR (`pr comments --new`)
+const SEEN_COMMENTS_NAMESPACE: &str = "comments-seen";
+
+/// Commits per author, most prolific first (ties by name)
+///
+/// Commits without author information are counted under `(unknown)`.
+fn count_commits_by_author(
+    commits: &[crate::api::models::Commit],
+) -> Vec<crate::api::models::AuthorCommits> {
+    let mut counts: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+    for commit in commits {
+        *counts
+            .entry(commit.author_name().unwrap_or("(unknown)"))
+            .or_default() += 1;
+    }
+
+    let mut counts: Vec<crate::api::models::AuthorCommits> = counts
+        .into_iter()
+        .map(|(author, commits)| crate::api::models::AuthorCommits {
+            author: author.to_string(),
+            commits,
+        })
+        .collect();
+    // Stable sort keeps the BTreeMap's name order among equal counts
+    counts.sort_by_key(|c| std::cmp::Reverse(c.commits));
+    counts
+}
+
+/// One line summing up a PR's builds, e.g. `1 of 3 builds failed for PR #42`
+fn build_summary(
+    pr_id: u32,
+    statuses: &[crate::api::models::CommitStatus],
+    state: Option<crate::api::models::BuildState>,
+) -> String {
+    use crate::api::models::BuildState;
+
+    let count = |states: &[&str]| {
+        statuses
+            .iter()
+            .filter(|s| states.contains(&s.state.as_str()))
+            .count()
+    };
+    let total = statuses.len();
+    match state {
+        None => format!("No builds reported for PR #{}", pr_id),
+        Some(BuildState::Successful) => format!("All {} builds passed for PR #{}", total, pr_id),
+        Some(BuildState::Failed) => format!(
+            "{} of {} builds failed for PR #{}",
+            count(&["FAILED", "STOPPED"]),
+            total,
+            pr_id
+        ),
+        Some(BuildState::InProgress) => format!(
+            "{} of {} builds still running for PR #{}",
+            total - count(&["SUCCESSFUL"]),
+            total,
+            pr_id
+        ),
+    }
+}
+
+/// `--name-status`: each changed file matching `patterns` with its status letter
+fn print_name_status(ctx: &AppContext, diff: &str, patterns: &[String]) -> Result<()> {
+    use crate::display::diff;
+
+    let statuses = diff::filter_file_statuses(diff::file_statuses(diff), patterns);
+    if ctx.json {
+        ui::print_json_list(&statuses)?;
+    } else if !statuses.is_empty() {
+        println!("{}", diff::format_file_statuses(&statuses));
+    }
+    diff::warn_if_truncated(diff);
+    Ok(())
+}
+
+/// Web URL of a PR's diff tab, optionally scrolled to one file
+///
+/// The API's `diff` link is the raw patch; the web diff tab lives under the
+/// HTML page, and each file in it is anchored as `#chg-<path>`.
+fn pr_diff_web_url(html_url: &str, file: Option<&str>) -> String {
+    let diff_url = format!("{}/diff", html_url.trim_end_matches('/'));
+    let Some(file) = file else {
+        return diff_url;
+    };
+    let path = file.trim_start_matches("./").trim_start_matches('/');
+
+    match reqwest::Url::parse(&diff_url) {
+        Ok(mut url) => {
+            // Percent-encodes characters (spaces, quotes, ...) not allowed in a fragment
+            url.set_fragment(Some(&format!("chg-{}", path)));
+            url.to_string()
+        }
+        Err(_) => diff_url,
+    }
+}
+
+/// Cache namespace holding merged PRs (`pr view`)
+const PR_CACHE_NAMESPACE: &str = "prs";
+
+/// Cache namespace holding each PR's last downloaded patch (`pr diff`)
+const DIFF_CACHE_NAMESPACE: &str = "diffs";
+
+/// Diff cache key prefix shared by every patch of one PR
+fn diff_cache_prefix(workspace: &str, repo: &str, pr_id: u32) -> String {
+    format!("{}/{}/{}/", workspace, repo, pr_id)
+}
+
+/// Diff cache key for a PR's patch between its current source and destination commits
+///
+/// `None` without a source commit, since nothing would tell a stale patch apart.
+fn diff_cache_key(prefix: &str, pr: &crate::api::models::PullRequest) -> Option<String> {
+    let source = &pr.source.commit.as_ref()?.hash;
+    let destination = pr
+        .destination
+        .commit
+        .as_ref()
+        .map_or("", |c| c.hash.as_str());
+    Some(format!("{}{}..{}", prefix, source, destination))
+}
+
+/// Every pull request state Bitbucket reports
+const ALL_PR_STATES: [&str; 4] = ["OPEN", "MERGED", "DECLINED", "SUPERSEDED"];
+
+/// Validate a `--state` value case-insensitively, returning the uppercase form the API expects
+fn parse_pr_state(value: &str) -> std::result::Result<String, String> {
+    let upper = value.trim().to_ascii_uppercase();
+    if ALL_PR_STATES.contains(&upper.as_str()) {
+        Ok(upper)
+    } else {
+        Err(format!(
+            "unknown state '{}' (expected one of: {})",
+            value,
+            ALL_PR_STATES.join(", ")
+        ))
+    }
+}
+
+pub async fn handle(ctx: &AppContext, command: PrCommands) -> Result<()> {
+    match command {
+        PrCommands::List {
+            state,
+            all_states,
+            limit,
+            page_size,
+            count,
+            no_trunc,
+            columns,
+            search,
+            since,
+            markdown,
+            compact,
+            watch,
+            interval,
+            review_requested,
+            raw_json,
+        } => {
+            let (workspace, repo) = ctx.require_repo()?;
+            let reviewer_uuid = if review_requested {
+                Some(current_user_uuid(ctx, "--review-requested").await?)
+            } else {
+                None
+            };
+            let clauses: Vec<String> = [
+                search.as_deref().map(crate::api::client::pr_search_query),
+                reviewer_uuid
+                    .as_deref()
+                    .map(crate::api::client::pr_reviewer_query),
+                since.map(crate::api::client::pr_updated_since_query),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            let query = crate::api::client::bbql_and(&clauses);
+
+            let states = if all_states {
+                ALL_PR_STATES.iter().map(|s| s.to_string()).collect()
+            } else {
+                state
+            };
+
+            if count {
+                let total = ctx
+                    .client
+                    .count_pull_requests(workspace, repo, &states, query.as_deref())
+                    .await?;
+                println!("{}", total);
+                return Ok(());
+            }
+
+            if let Some(size) = page_size.filter(|s| *s > crate::constants::MAX_PAGE_LEN) {
+                ui::warning(&format!(
+                    "--page-size {} exceeds Bitbucket's maximum; using {}",
+                    size,
+                    crate::constants::MAX_PAGE_LEN
+                ));
+            }
+
+            let limit = limit
+                .or(ctx.pr_default_limit)
+                .unwrap_or(crate::constants::DEFAULT_PR_LIST_LIMIT);
+
+            if watch {
+                if ctx.json {
+                    anyhow::bail!("--watch can't be combined with --json");
+                }
+                // --explain is global, so clap can't hold it against --watch
+                if ctx.client.explains() {
+                    anyhow::bail!("--watch can't be combined with --explain");
+                }
+                let title = format!(
+                    "{} pull requests {}in {}/{}",
+                    states.join(", "),
+                    if review_requested {
+                        "requesting your review "
+                    } else {
+                        ""
+                    },
+                    workspace,
+                    repo
+                );
+                let (client, states, query, reviewer_uuid) = (
+                    &ctx.client,
+                    &states,
+                    query.as_deref(),
+                    reviewer_uuid.as_deref(),
+                );
+                return watch::watch_pr_list(
+                    &title,
+                    move || async move {
+                        let mut prs = client
+                            .list_pull_requests_with_participants(
+                                workspace,
+                                repo,
+                                states,
+                                query,
+                                Some(limit),
+                                page_size,
+                            )
+                            .await?;
+                        // As below, for servers that ignore the reviewers query
+                        if let Some(uuid) = reviewer_uuid {
+                            prs.retain(|pr| pr.has_reviewer(uuid));
+                        }
+                        Ok(prs)
+                    },
+                    &columns,
+                    std::time::Duration::from_secs(interval),
+                )
+                .await;
+            }
+
+            if raw_json {
+                let prs = ctx
+                    .client
+                    .list_pull_requests_raw(
+                        workspace,
+                        repo,
+                        &states,
+                        query.as_deref(),
+                        Some(limit),
+                        page_size,
+                    )
+                    .await?;
+                // As for `pr view --raw-json`: the API's own text, with no
+                // envelope and no re-serializing that would reorder keys
+                let prs: Vec<&str> = prs.iter().map(|pr| pr.get()).collect();
+                println!("[{}]", prs.join(","));
+                return Ok(());
+            }
+
+            let prs = match &reviewer_uuid {
+                // Participants tell whether you've approved yet; the filter
+                // also holds where the reviewers query isn't supported
+                Some(uuid) => {
+                    let mut prs = ctx
+                        .client
+                        .list_pull_requests_with_participants(
+                            workspace,
+                            repo,
+                            &states,
+                            query.as_deref(),
+                            Some(limit),
+                            page_size,
+                        )
+                        .await?;
+                    prs.retain(|pr| pr.has_reviewer(uuid));
+                    prs
+                }
+                None => {
+                    ctx.client
+                        .list_pull_requests(
+                            workspace,
+                            repo,
+                            &states,
+                            query.as_deref(),
+                            Some(limit),
+                            page_size,
+                        )
+                        .await?
+                }
+            };
 
             if ctx.json {
-                ui::print_json(&prs)?;
+                ui::print_json_list(&prs)?;
                 return Ok(());
             }
 
             if prs.is_empty() {
-                ui::info(&format!(
-                    "No pull requests found in {}/{} with state {}",
-                    workspace, repo, state
-                ));
+                return Err(CliError::NotFound(format!(
+                    "No pull requests {}found in {}/{} with state {}",
+                    if review_requested {
+                        "requesting your review "
+                    } else {
+                        ""
+                    },
+                    workspace,
+                    repo,
+                    states.join(", ")
+                ))
+                .into());
+            }
+
+            if markdown {
+                println!("{}", pr_display::format_pr_list_markdown(&prs));
+                return Ok(());
+            }
+
+            if compact {
+                println!("{}", pr_display::format_pr_list_compact(&prs));
                 return Ok(());
             }
 
-            let table = pr_display::format_pr_list(&prs);
+            let awaiting_me: std::collections::HashSet<u32> = match &reviewer_uuid {
+                Some(uuid) => prs
+                    .iter()
+                    .filter(|pr| pr.awaits_approval_from(uuid))
+                    .map(|pr| pr.id)
+                    .collect(),
+                None => Default::default(),
+            };
+            let table =
+                pr_display::format_pr_list_highlighted(&prs, &columns, no_trunc, &awaiting_me);
             if ui::should_use_pager() {
                 ui::display_in_pager(&table)?;
             } else {
                 println!("{}", table);
             }
         }
-        PrCommands::View { id, web, comments } => {
-            let workspace = ctx
-                .workspace
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("No workspace found"))?;
-            let repo = ctx
-                .repo
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("No repository found"))?;
+        PrCommands::View {
+            id,
+            web,
+            file,
+            comments,
+            comments_first,
+            comments_only,
+            no_comments,
+            no_statuses,
+            print_links,
+            branch,
+            refresh,
+            raw_json,
+        } => {
+            let (workspace, repo) = ctx.require_repo()?;
 
-            let pr_id = resolve_pr_id(id, &ctx.client, workspace, repo).await?;
-            let pr = ctx.client.get_pull_request(workspace, repo, pr_id).await?;
+            let pr_id = resolve_pr_id(id, branch.as_deref(), &ctx.client, workspace, repo).await?;
+
+            // Straight from the API: no cache, model or envelope in between
+            if raw_json {
+                let body = ctx
+                    .client
+                    .get_raw(&format!(
+                        "/repositories/{}/{}/pullrequests/{}",
+                        workspace, repo, pr_id
+                    ))
+                    .await?;
+                println!("{}", body.trim_end());
+                return Ok(());
+            }
+
+            let pr = fetch_pr(ctx, workspace, repo, pr_id, refresh).await?;
 
             if web {
-                open::that(pr.links.html.href)?;
-                ui::success(&format!("Opened PR #{} in browser", pr.id));
+                if let Some(file) = file {
+                    let url = pr_diff_web_url(&pr.links.html.href, Some(&file));
+                    if crate::utils::browser::open_url(&url) {
+                        ui::success(&format!("Opened {} in PR #{} diff in browser", file, pr.id));
+                    }
+                } else if crate::utils::browser::open_url(&pr.links.html.href) {
+                    ui::success(&format!("Opened PR #{} in browser", pr.id));
+                }
                 return Ok(());
             }
 
-            let pr_comments = if comments || ctx.json {
-                Some(
+            if print_links {
+                if ctx.json {
+                    ui::print_json(&pr.links)?;
+                } else {
+                    crate::utils::formatting::print_key_value_table(
+                        pr.links
+                            .entries()
+                            .into_iter()
+                            .map(|(name, href)| (name, href.to_string()))
+                            .collect(),
+                    );
+                }
+                return Ok(());
+            }
+
+            let layout = if comments_only {
+                pr_display::ViewLayout::CommentsOnly
+            } else if comments_first {
+                pr_display::ViewLayout::CommentsFirst
+            } else {
+                pr_display::ViewLayout::DetailsFirst
+            };
+            let comments = comments || comments_first || comments_only;
+            // Details-only requests aren't needed when the details aren't shown
+            let show_details = layout != pr_display::ViewLayout::CommentsOnly;
+
+            let fetch_comments = async {
+                if !no_comments && (comments || ctx.json) {
                     ctx.client
                         .get_pull_request_comments(workspace, repo, pr_id)
-                        .await?,
-                )
-            } else {
-                None
+                        .await
+                        .map(Some)
+                } else {
+                    Ok(None)
+                }
+            };
+
+            // Build statuses are only shown in the table view
+            let fetch_statuses = async {
+                match &pr.source.commit {
+                    Some(commit) if !ctx.json && !no_statuses && show_details => {
+                        ctx.client
+                            .get_commit_statuses(workspace, repo, &commit.hash)
+                            .await
+                    }
+                    _ => Ok(Vec::new()),
+                }
+            };
+
+            // Conflicts are reported per file in the diffstat; a failure here
+            // shouldn't hide the rest of the PR
+            let fetch_mergeability = async {
+                if ctx.json || !show_details {
+                    return Ok(None);
+                }
+                if pr.state != "OPEN" {
+                    return Ok(Some(pr.mergeability(&[])));
+                }
+                match ctx
+                    .client
+                    .get_pull_request_diffstat(workspace, repo, pr_id)
+                    .await
+                {
+                    Ok(diffstat) => Ok(Some(pr.mergeability(&diffstat))),
+                    Err(e) => {
+                        crate::utils::debug::log(&format!("Failed to fetch diffstat: {:#}", e));
+                        Ok::<_, anyhow::Error>(None)
+                    }
+                }
             };
 
+            let (pr_comments, statuses, mergeability) = tokio::try_join!(
+                concurrency::bounded(&ctx.limiter, fetch_comments),
+                concurrency::bounded(&ctx.limiter, fetch_statuses),
+                concurrency::bounded(&ctx.limiter, fetch_mergeability)
+            )?;
+
+            if ctx.json && !show_details {
+                ui::print_json_list(&pr_comments.unwrap_or_default())?;
+                return Ok(());
+            }
             if ctx.json {
                 #[derive(serde::Serialize)]
                 struct JsonOutput {
                     pr: crate::api::models::PullRequest,
+                    #[serde(skip_serializing_if = "Option::is_none")]
                     comments: Option<Vec<crate::api::models::Comment>>,
                 }
 
@@ -144,92 +856,433 @@ pub async fn handle(ctx: &AppContext, args: PrArgs) -> Result<()> {
                 return Ok(());
             }
 
-            // Fetch build statuses
-            let statuses = if let Some(commit) = &pr.source.commit {
-                ctx.client
-                    .get_commit_statuses(workspace, repo, &commit.hash)
-                    .await?
+            let details = if show_details {
+                pr_display::format_pr_details(
+                    &pr,
+                    &statuses,
+                    mergeability.as_ref(),
+                    ctx.jira_url.as_deref(),
+                )
             } else {
-                Vec::new()
+                String::new()
             };
+            let comments = pr_comments
+                .as_deref()
+                .map(pr_display::format_comments)
+                .unwrap_or_default();
 
-            pr_display::print_pr_details(&pr, &statuses);
-
-            // Display Comments
-            if let Some(comments_list) = pr_comments {
-                pr_display::print_comments(&comments_list);
+            if !show_details && comments.is_empty() {
+                ui::info(&format!("No comments on PR #{}", pr.id));
+                return Ok(());
             }
+            print!(
+                "{}",
+                pr_display::arrange_pr_view(&details, &comments, layout)
+            );
         }
         PrCommands::Diff {
             args,
             name_only,
+            name_status,
             web,
             max_diff_size,
+            compare,
+            branch,
+            inline_comments,
+            wrap,
+            stat,
+            refresh,
+            pattern_file,
         } => {
-            let workspace = ctx
-                .workspace
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("No workspace found"))?;
-            let repo = ctx
-                .repo
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("No repository found"))?;
+            let (workspace, repo) = ctx.require_repo()?;
+            // A file matches if it matches any pattern, from the file or the command line
+            let file_patterns = match &pattern_file {
+                Some(path) => crate::display::diff::read_pattern_file(path)?,
+                None => Vec::new(),
+            };
+
+            if let Some(spec) = compare {
+                parse_compare_spec(&spec)?;
+                let diff = ctx.client.get_branch_diff(workspace, repo, &spec).await?;
+                let patterns = &[args, file_patterns].concat();
+
+                if name_status {
+                    print_name_status(ctx, &diff, patterns)?;
+                } else if name_only {
+                    crate::display::diff::print_filenames_only(&diff, patterns);
+                } else {
+                    crate::display::diff::print_diff(&diff, patterns, max_diff_size, &[], wrap)?;
+                }
+                return Ok(());
+            }
 
             let (id_opt, patterns) = parse_args_with_id(&args);
-            let pr_id = resolve_pr_id(id_opt, &ctx.client, workspace, repo).await?;
+            let patterns = &[patterns, &file_patterns].concat();
+            let pr_id =
+                resolve_pr_id(id_opt, branch.as_deref(), &ctx.client, workspace, repo).await?;
 
             // Handle --web flag (open in browser)
             if web {
                 let pr = ctx.client.get_pull_request(workspace, repo, pr_id).await?;
-                let diff_url = format!("{}/diff", pr.links.html.href);
-                open::that(diff_url)?;
-                ui::success(&format!("Opened PR #{} diff in browser", pr_id));
+                let diff_url = pr_diff_web_url(&pr.links.html.href, None);
+                if crate::utils::browser::open_url(&diff_url) {
+                    ui::success(&format!("Opened PR #{} diff in browser", pr_id));
+                }
                 return Ok(());
             }
 
-            let diff = ctx
-                .client
-                .get_pull_request_diff(workspace, repo, pr_id)
-                .await?;
+            if stat {
+                let diffstat = ctx
+                    .client
+                    .get_pull_request_diffstat(workspace, repo, pr_id)
+                    .await?;
+                if ctx.json {
+                    let filtered = crate::display::diff::filter_diffstat(&diffstat, patterns);
+                    ui::print_json_list(&filtered)?;
+                } else {
+                    crate::display::diff::print_diffstat(&diffstat, patterns);
+                }
+                return Ok(());
+            }
+
+            let diff = read_diff(fetch_pr_diff(ctx, workspace, repo, pr_id, refresh).await?)?;
 
-            // Handle --name-only flag
-            if name_only {
+            if name_status {
+                print_name_status(ctx, &diff, patterns)?;
+            } else if name_only {
                 crate::display::diff::print_filenames_only(&diff, patterns);
             } else {
-                crate::display::diff::print_diff(&diff, patterns, max_diff_size)?;
+                let comments = if inline_comments {
+                    ctx.client
+                        .get_pull_request_comments(workspace, repo, pr_id)
+                        .await?
+                } else {
+                    Vec::new()
+                };
+                crate::display::diff::print_diff(&diff, patterns, max_diff_size, &comments, wrap)?;
             }
         }
-        PrCommands::Comments { id } => {
-            let workspace = ctx
-                .workspace
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("No workspace found"))?;
-            let repo = ctx
-                .repo
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("No repository found"))?;
+        PrCommands::Comments {
+            id,
+            sort,
+            branch,
+            since,
+            new,
+            thread,
+            author,
+        } => {
+            let (workspace, repo) = ctx.require_repo()?;
 
-            let pr_id = resolve_pr_id(id, &ctx.client, workspace, repo).await?;
+            let pr_id = resolve_pr_id(id, branch.as_deref(), &ctx.client, workspace, repo).await?;
 
-            let comments = ctx
+            let mut comments = ctx
                 .client
                 .get_pull_request_comments(workspace, repo, pr_id)
                 .await?;
 
+            if let Some(root) = thread {
+                sort_comments(&mut comments, CommentSort::Oldest);
+                let thread = comment_thread(&comments, root).map_err(|e| match e {
+                    ThreadError::NotFound => {
+                        CliError::NotFound(format!("Comment #{} not found on PR #{}", root, pr_id))
+                            .into()
+                    }
+                    ThreadError::Reply { root: thread_root } => anyhow::anyhow!(
+                        "Comment #{} is a reply, not the start of a thread; \
+                         use --thread {} to see the whole discussion",
+                        root,
+                        thread_root
+                    ),
+                })?;
+                let thread = match &author {
+                    Some(author) => thread_by_author(thread, author),
+                    None => thread,
+                };
+                if let Some(author) = &author
+                    && thread.is_empty()
+                {
+                    ui::info(&format!("No comments by {} in thread #{}", author, root));
+                    return Ok(());
+                }
+
+                if ctx.json {
+                    let thread: Vec<_> = thread.iter().map(|(_, c)| c).collect();
+                    ui::print_json_list(&thread)?;
+                } else {
+                    print!("{}", pr_display::format_comment_thread(&thread));
+                }
+                return Ok(());
+            }
+
+            let seen_key = format!("{}/{}/{}", workspace, repo, pr_id);
+            let since = if new {
+                crate::utils::cache::read::<u32>(
+                    SEEN_COMMENTS_NAMESPACE,
+                    &seen_key,
+                    std::time::Duration::MAX,
+                )
+                .map(|seen| CommentMarker::Id(seen.data))
+            } else {
+                since
+            };
+
+            // Advance the marker before filtering so it covers everything fetched
+            if new
+                && let Some(newest) = comments.iter().map(|c| c.id).max()
+                && let Err(e) =
+                    crate::utils::cache::write(SEEN_COMMENTS_NAMESPACE, &seen_key, &newest)
+            {
+                crate::utils::debug::log(&format!("Failed to record seen comments: {}", e));
+            }
+
+            if let Some(marker) = &since {
+                retain_comments_after(&mut comments, marker);
+                if comments.is_empty() {
+                    ui::info(&format!(
+                        "No new comments on PR #{} since {}",
+                        pr_id, marker
+                    ));
+                    return Ok(());
+                }
+            }
+
+            if let Some(author) = &author {
+                comments.retain(|c| c.user.matches(author));
+                if comments.is_empty() {
+                    ui::info(&format!("No comments by {} on PR #{}", author, pr_id));
+                    return Ok(());
+                }
+            }
+
             if comments.is_empty() {
                 ui::info(&format!("No comments found for PR #{}", pr_id));
                 return Ok(());
             }
 
+            sort_comments(&mut comments, sort);
+
             if ctx.json {
-                ui::print_json(&comments)?;
+                ui::print_json_list(&comments)?;
             } else {
                 pr_display::print_comments(&comments);
             }
         }
+        PrCommands::Activity { id, branch } => {
+            let (workspace, repo) = ctx.require_repo()?;
+
+            let pr_id = resolve_pr_id(id, branch.as_deref(), &ctx.client, workspace, repo).await?;
+            let mut activity = ctx
+                .client
+                .get_pull_request_activity(workspace, repo, pr_id)
+                .await?;
+
+            // The API lists newest first; a timeline reads oldest first
+            activity
+                .sort_by_key(|entry| entry.date().and_then(crate::utils::time::parse_timestamp));
+
+            if ctx.json {
+                ui::print_json_list(&activity)?;
+            } else if activity.is_empty() {
+                ui::info(&format!("No activity found for PR #{}", pr_id));
+            } else {
+                print!("{}", pr_display::format_activity(&activity));
+            }
+        }
+        PrCommands::Commits {
+            id,
+            branch,
+            no_merges,
+            authors,
+        } => {
+            let (workspace, repo) = ctx.require_repo()?;
+
+            let pr_id = resolve_pr_id(id, branch.as_deref(), &ctx.client, workspace, repo).await?;
+            let mut commits = ctx
+                .client
+                .get_pull_request_commits(workspace, repo, pr_id)
+                .await?;
+            if no_merges {
+                commits.retain(|commit| !commit.is_merge());
+            }
+
+            if authors {
+                let counts = count_commits_by_author(&commits);
+                if ctx.json {
+                    ui::print_json(&counts)?;
+                } else if counts.is_empty() {
+                    ui::info(&format!("No commits found for PR #{}", pr_id));
+                } else {
+                    println!("{}", pr_display::format_author_counts(&counts));
+                }
+            } else if ctx.json {
+                ui::print_json_list(&commits)?;
+            } else if commits.is_empty() {
+                ui::info(&format!("No commits found for PR #{}", pr_id));
+            } else {
+                println!("{}", pr_display::format_commit_list(&commits));
+            }
+        }
+        PrCommands::Create(args) => {
+            create::pr_create(ctx, &args).await?;
+        }
         PrCommands::Review(args) => {
             review::pr_review(ctx, &args).await?;
         }
+        PrCommands::Decline(args) => {
+            decline::pr_decline(ctx, &args).await?;
+        }
+        PrCommands::Approvals { id, branch } => {
+            let (workspace, repo) = ctx.require_repo()?;
+
+            let pr_id = resolve_pr_id(id, branch.as_deref(), &ctx.client, workspace, repo).await?;
+            let pr = ctx.client.get_pull_request(workspace, repo, pr_id).await?;
+            let approvers = pr.approvers();
+
+            if ctx.json {
+                let output: Vec<ReviewerJson> = approvers.iter().map(ReviewerJson::from).collect();
+                ui::print_json(&output)?;
+            } else if !approvers.is_empty() {
+                println!("{}", pr_display::format_approvers(&approvers));
+            }
+
+            ApprovalGate::new(pr_id, approvers.len(), 1).check()?;
+        }
+        PrCommands::CheckApprovals { id, min, branch } => {
+            let (workspace, repo) = ctx.require_repo()?;
+
+            let pr_id = resolve_pr_id(id, branch.as_deref(), &ctx.client, workspace, repo).await?;
+            let pr = ctx.client.get_pull_request(workspace, repo, pr_id).await?;
+            let gate = ApprovalGate::new(pr_id, pr.approvers().len(), min);
+            let pending = pr.pending_reviewers();
+
+            if ctx.json {
+                #[derive(serde::Serialize)]
+                struct ApprovalCheck<'a> {
+                    approved: u32,
+                    required: u32,
+                    pending: Vec<ReviewerJson<'a>>,
+                }
+
+                ui::print_json(&ApprovalCheck {
+                    approved: gate.approved,
+                    required: gate.required,
+                    pending: pending.iter().map(ReviewerJson::from).collect(),
+                })?;
+            } else {
+                print!("{}", pr_display::format_reviewer_status(&pr.participants));
+            }
+
+            gate.check()?;
+            if !ctx.json {
+                ui::success(&gate.summary());
+            }
+        }
+        PrCommands::Status {
+            id,
+            branch,
+            exit_code,
+        } => {
+            use crate::api::models::BuildState;
+
+            let (workspace, repo) = ctx.require_repo()?;
+
+            let pr_id = resolve_pr_id(id, branch.as_deref(), &ctx.client, workspace, repo).await?;
+            let pr = ctx.client.get_pull_request(workspace, repo, pr_id).await?;
+            let statuses = match &pr.source.commit {
+                Some(commit) => {
+                    ctx.client
+                        .get_commit_statuses(workspace, repo, &commit.hash)
+                        .await?
+                }
+                None => Vec::new(),
+            };
+            let state = BuildState::aggregate(&statuses);
+
+            if ctx.json {
+                #[derive(serde::Serialize)]
+                struct BuildStatus<'a> {
+                    id: u32,
+                    state: Option<BuildState>,
+                    statuses: &'a [crate::api::models::CommitStatus],
+                }
+
+                ui::print_json(&BuildStatus {
+                    id: pr_id,
+                    state,
+                    statuses: &statuses,
+                })?;
+            } else {
+                print!(
+                    "{}",
+                    pr_display::format_build_statuses(&statuses).trim_start_matches('\n')
+                );
+            }
+
+            let summary = build_summary(pr_id, &statuses, state);
+            if exit_code {
+                match state {
+                    Some(BuildState::Successful) => {}
+                    Some(BuildState::Failed) => return Err(CliError::BuildFailed(summary).into()),
+                    Some(BuildState::InProgress) => {
+                        return Err(CliError::BuildPending(summary).into());
+                    }
+                    None => return Err(CliError::NotFound(summary).into()),
+                }
+            }
+            if !ctx.json {
+                match state {
+                    Some(BuildState::Successful) => ui::success(&summary),
+                    Some(BuildState::Failed) => ui::warning(&summary),
+                    _ => ui::info(&summary),
+                }
+            }
+        }
+        PrCommands::Ready { id } => {
+            let (workspace, repo) = ctx.require_repo()?;
+
+            let pr_id = resolve_pr_id(id, None, &ctx.client, workspace, repo).await?;
+            let pr = ctx
+                .client
+                .mark_pull_request_ready(workspace, repo, pr_id)
+                .await?;
+
+            if ctx.json {
+                ui::print_json(&pr)?;
+            } else {
+                ui::success(&format!("Marked PR #{} as ready for review", pr.id));
+            }
+        }
+        PrCommands::Watch { id } => {
+            let (workspace, repo) = ctx.require_repo()?;
+
+            let pr_id = resolve_pr_id(id, None, &ctx.client, workspace, repo).await?;
+            let uuid = current_user_uuid(ctx, "pr watch").await?;
+            let participant = ctx
+                .client
+                .add_participant(workspace, repo, pr_id, &uuid)
+                .await?;
+
+            if ctx.json {
+                ui::print_json(&participant)?;
+            } else {
+                ui::success(&format!(
+                    "Watching PR #{} (role: {})",
+                    pr_id,
+                    format!("{:?}", participant.role).to_lowercase()
+                ));
+            }
+        }
+        PrCommands::Unwatch { id } => {
+            let (workspace, repo) = ctx.require_repo()?;
+
+            let pr_id = resolve_pr_id(id, None, &ctx.client, workspace, repo).await?;
+            let uuid = current_user_uuid(ctx, "pr unwatch").await?;
+            ctx.client
+                .remove_participant(workspace, repo, pr_id, &uuid)
+                .await?;
+
+            ui::success(&format!("Stopped watching PR #{}", pr_id));
+        }
     }
     Ok(())
 }
@@ -239,25 +1292,303 @@ pub async fn handle(ctx: &AppContext, args: PrArgs) -> Result<()> {
 /// # Arguments
 ///
 /// * `id` - Optional explicit PR ID
+/// * `branch` - Branch to look up instead of the current one (ignored when `id` is given)
 /// * `client` - Bitbucket API client
 /// * `workspace` - Workspace ID/slug
 /// * `repo` - Repository slug
 async fn resolve_pr_id(
     id: Option<u32>,
+    branch: Option<&str>,
     client: &BitbucketClient,
     workspace: &str,
     repo: &str,
 ) -> Result<u32> {
     if let Some(i) = id {
+        if let Some(b) = branch {
+            ui::warning(&format!("Using PR #{}; ignoring --branch '{}'", i, b));
+        }
         return Ok(i);
     }
-    let branch = crate::git::get_current_branch()?;
+    let branch = match branch {
+        Some(b) => b.to_string(),
+        None => crate::git::get_current_branch()?,
+    };
+    // In a fork-based workflow the branch lives in the fork, so match on
+    // where it's pushed when we can tell
+    let source_repo = crate::git::get_branch_source_repo(&branch);
     let pr = client
-        .find_pull_request_by_branch(workspace, repo, &branch)
+        .find_pull_request_by_branch(workspace, repo, &branch, source_repo.as_deref())
         .await?;
     match pr {
-        Some(p) => Ok(p.id),
-        None => Err(anyhow::anyhow!("No open PR found for branch '{}'", branch)),
+        Some(p) => {
+            let target = format!("{}/{}", workspace, repo);
+            let matched = &p.source.repository.full_name;
+            // Found by branch name alone, so say where it's from
+            if let Some(pushed_to) = source_repo
+                .filter(|r| !r.eq_ignore_ascii_case(&target) && !r.eq_ignore_ascii_case(matched))
+            {
+                ui::warning(&format!(
+                    "No open PR from {} for branch '{}'; using PR #{} from {}",
+                    pushed_to, branch, p.id, matched
+                ));
+            }
+            Ok(p.id)
+        }
+        None => Err(CliError::NotFound(format!("No open PR found for branch '{}'", branch)).into()),
+    }
+}
+
+/// A PR, served from the cache for a while once it's merged
+///
+/// Open and declined PRs are always fetched live (a declined PR can be
+/// reopened). A merged one still gains comments and participants, so the
+/// cached copy expires after [`PR_CACHE_TTL_SECS`](crate::constants::PR_CACHE_TTL_SECS).
+/// `refresh` always fetches (and re-caches).
+async fn fetch_pr(
+    ctx: &AppContext,
+    workspace: &str,
+    repo: &str,
+    pr_id: u32,
+    refresh: bool,
+) -> Result<crate::api::models::PullRequest> {
+    let cache_path = crate::utils::cache::cache_path(
+        PR_CACHE_NAMESPACE,
+        &format!("{}/{}/{}/{}", ctx.profile_name, workspace, repo, pr_id),
+    );
+    fetch_pr_cached(ctx, workspace, repo, pr_id, refresh, cache_path.as_deref()).await
+}
+
+/// [`fetch_pr`] with the cache entry at `cache_path`
+async fn fetch_pr_cached(
+    ctx: &AppContext,
+    workspace: &str,
+    repo: &str,
+    pr_id: u32,
+    refresh: bool,
+    cache_path: Option<&std::path::Path>,
+) -> Result<crate::api::models::PullRequest> {
+    use crate::utils::cache;
+
+    let ttl = std::time::Duration::from_secs(crate::constants::PR_CACHE_TTL_SECS);
+    if !refresh
+        && let Some(path) = cache_path
+        && let Some(hit) = cache::read_from::<crate::api::models::PullRequest>(path, ttl)
+        && hit.data.is_merged()
+    {
+        crate::utils::debug::log(&format!(
+            "Using cached PR #{} ({}, cached {} ago, --refresh to update)",
+            pr_id,
+            hit.data.state,
+            cache::format_age(hit.age)
+        ));
+        return Ok(hit.data);
+    }
+
+    let pr = ctx.client.get_pull_request(workspace, repo, pr_id).await?;
+    if pr.is_merged()
+        && let Some(path) = cache_path
+        && let Err(e) = cache::write_to(path, &pr)
+    {
+        crate::utils::debug::log(&format!("Failed to cache PR: {}", e));
+    }
+    Ok(pr)
+}
+
+/// A PR's patch, reusing the cached copy while its commits are unchanged
+///
+/// The patch only changes when new commits are pushed to the source branch or
+/// the destination branch moves, so cached patches are keyed by both commit
+/// hashes. Those come from the PR itself: it's fetched first only when a patch
+/// for this PR is cached, and otherwise alongside the download. `refresh`
+/// always downloads. Returns the patch file, positioned at the start.
+async fn fetch_pr_diff(
+    ctx: &AppContext,
+    workspace: &str,
+    repo: &str,
+    pr_id: u32,
+    refresh: bool,
+) -> Result<std::fs::File> {
+    use crate::constants::{DIFF_CACHE_MAX_BYTES, DIFF_CACHE_MAX_ENTRY_BYTES};
+
+    let Some(store) = crate::utils::cache::FileStore::open(
+        DIFF_CACHE_NAMESPACE,
+        DIFF_CACHE_MAX_ENTRY_BYTES,
+        DIFF_CACHE_MAX_BYTES,
+    ) else {
+        let file = download_pr_diff(ctx, workspace, repo, pr_id, diff_temp_file(None)?).await?;
+        return Ok(file.into_file());
+    };
+    let prefix = diff_cache_prefix(workspace, repo, pr_id);
+
+    let (pr, file) = if !refresh && store.has_prefix(&prefix) {
+        let pr = ctx.client.get_pull_request(workspace, repo, pr_id).await?;
+        if let Some(key) = diff_cache_key(&prefix, &pr)
+            && let Some(file) = store.get(&key)
+        {
+            crate::utils::debug::log(&format!(
+                "Using cached diff for PR #{} ({}, --refresh to update)",
+                pr_id, key
+            ));
+            return Ok(file);
+        }
+        let file =
+            download_pr_diff(ctx, workspace, repo, pr_id, diff_temp_file(Some(&store))?).await?;
+        (pr, file)
+    } else {
+        tokio::try_join!(
+            ctx.client.get_pull_request(workspace, repo, pr_id),
+            download_pr_diff(ctx, workspace, repo, pr_id, diff_temp_file(Some(&store))?)
+        )?
+    };
+
+    // A cut-short patch would be served from the cache until the PR changes
+    if diff_file_truncated(file.as_file()) {
+        crate::utils::debug::log(&format!("Not caching truncated diff for PR #{}", pr_id));
+        return Ok(file.into_file());
+    }
+
+    Ok(match diff_cache_key(&prefix, &pr) {
+        Some(key) => store.write(&key, &prefix, file),
+        None => file.into_file(),
+    })
+}
+
+/// A file to download a patch into: in the diff cache when there is one, so
+/// caching it is a rename, and in the system temp directory otherwise
+fn diff_temp_file(
+    store: Option<&crate::utils::cache::FileStore>,
+) -> Result<tempfile::NamedTempFile> {
+    if let Some(store) = store {
+        match store.temp_file() {
+            Ok(file) => return Ok(file),
+            Err(e) => crate::utils::debug::log(&format!("Diff cache unavailable: {:#}", e)),
+        }
+    }
+    tempfile::NamedTempFile::new().context("Failed to create a file for the diff")
+}
+
+/// Download a PR's patch into `file`, showing progress on stderr
+///
+/// Returns the file positioned at the start.
+async fn download_pr_diff(
+    ctx: &AppContext,
+    workspace: &str,
+    repo: &str,
+    pr_id: u32,
+    mut file: tempfile::NamedTempFile,
+) -> Result<tempfile::NamedTempFile> {
+    use std::io::Seek;
+
+    let mut progress = ui::DownloadProgress::new("Downloading diff...");
+    ctx.client
+        .stream_pull_request_diff(workspace, repo, pr_id, &mut file, |n| progress.update(n))
+        .await?;
+    progress.finish();
+    file.rewind()
+        .context("Failed to read the downloaded diff")?;
+    Ok(file)
+}
+
+/// Whether a downloaded patch ends partway through a hunk; an unreadable one
+/// counts as cut short
+fn diff_file_truncated(file: &std::fs::File) -> bool {
+    use std::io::{BufRead, Seek};
+
+    let mut reader = std::io::BufReader::new(file);
+    let mut unreadable = reader.rewind().is_err();
+    let lines = (&mut reader).split(b'\n').map_while(|line| match line {
+        Ok(line) => Some(String::from_utf8_lossy(&line).into_owned()),
+        Err(_) => {
+            unreadable = true;
+            None
+        }
+    });
+    let truncated = crate::display::diff::truncation_notice_lines(lines).is_some();
+    // The check read to the end; put the file back for the caller
+    truncated || unreadable || reader.rewind().is_err()
+}
+
+/// The text of a patch file, replacing any invalid UTF-8
+fn read_diff(mut file: std::fs::File) -> Result<String> {
+    let mut bytes = Vec::new();
+    std::io::Read::read_to_end(&mut file, &mut bytes).context("Failed to read the diff")?;
+    // Reuse the buffer as the string; only a patch with invalid UTF-8 is copied
+    Ok(String::from_utf8(bytes)
+        .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned()))
+}
+
+/// A reviewer as the approval commands print them with `--json`
+#[derive(serde::Serialize)]
+struct ReviewerJson<'a> {
+    display_name: &'a str,
+    uuid: &'a str,
+}
+
+impl<'a> From<&&'a crate::api::models::User> for ReviewerJson<'a> {
+    fn from(user: &&'a crate::api::models::User) -> Self {
+        Self {
+            display_name: &user.display_name,
+            uuid: &user.uuid,
+        }
+    }
+}
+
+/// How a PR's approvals measure up to what a gate requires
+#[derive(Debug, PartialEq)]
+struct ApprovalGate {
+    pr_id: u32,
+    approved: u32,
+    required: u32,
+}
+
+impl ApprovalGate {
+    fn new(pr_id: u32, approved: usize, required: u32) -> Self {
+        Self {
+            pr_id,
+            approved: approved as u32,
+            required,
+        }
+    }
+
+    fn summary(&self) -> String {
+        if self.approved == 0 && self.required == 1 {
+            format!("PR #{} has no approvals", self.pr_id)
+        } else {
+            format!(
+                "PR #{} has {} of {} required approvals",
+                self.pr_id, self.approved, self.required
+            )
+        }
+    }
+
+    /// Fails with [`CliError::ApprovalsMissing`] when there are too few
+    fn check(&self) -> Result<()> {
+        if self.approved < self.required {
+            return Err(CliError::ApprovalsMissing(self.summary()).into());
+        }
+        Ok(())
+    }
+}
+
+/// UUID of the active user, from the login cache when available
+///
+/// With `--no-config` there's no profile, so no user to ask about; `purpose`
+/// names what needed one in the error.
+async fn current_user_uuid(ctx: &AppContext, purpose: &str) -> Result<String> {
+    if ctx.no_config {
+        anyhow::bail!(
+            "{} needs a profile; not available with --no-config",
+            purpose
+        );
+    }
+    let cached = crate::config::manager::ProfileConfig::load_global()
+        .ok()
+        .and_then(|c| c.get_profile_user(ctx.profile.as_deref()))
+        .and_then(|u| crate::utils::auth::load_cached_user(&u));
+
+    match cached {
+        Some(user) => Ok(user.uuid),
+        None => Ok(ctx.client.get_current_user().await?.uuid),
     }
 }
 
@@ -282,9 +1613,127 @@ fn parse_args_with_id(args: &[String]) -> (Option<u32>, &[String]) {
     }
 }
 
+/// Validate a `source..destination` comparison spec
+///
+/// # Returns
+///
+/// The `(source, destination)` pair, or an error if the spec is malformed.
+fn parse_compare_spec(spec: &str) -> Result<(&str, &str)> {
+    match spec.split_once("..") {
+        Some((a, b)) if !a.is_empty() && !b.is_empty() && !b.starts_with('.') => Ok((a, b)),
+        _ => Err(anyhow::anyhow!(
+            "Invalid compare spec '{}'. Expected <source>..<destination>, e.g. feature..main",
+            spec
+        )),
+    }
+}
+
+/// Drop comments at or before `marker`
+///
+/// Comment IDs only grow, so an ID marker compares IDs. Comments whose
+/// timestamp can't be parsed are dropped by a time marker.
+fn retain_comments_after(comments: &mut Vec<crate::api::models::Comment>, marker: &CommentMarker) {
+    use crate::utils::time::parse_timestamp;
+
+    match marker {
+        CommentMarker::Id(id) => comments.retain(|c| c.id > *id),
+        CommentMarker::Time(time) => {
+            comments.retain(|c| parse_timestamp(&c.created_on).is_some_and(|t| t > *time))
+        }
+    }
+}
+
+/// Why [`comment_thread`] couldn't select a thread
+#[derive(Debug, PartialEq)]
+enum ThreadError {
+    NotFound,
+    /// The comment replies to another; `root` starts its thread
+    Reply {
+        root: u32,
+    },
+}
+
+/// The thread started by comment `root`: the comment and every reply below
+/// it, depth first, each with its depth (0 for `root`)
+///
+/// Replies to the same comment keep their order in `comments`.
+fn comment_thread(
+    comments: &[crate::api::models::Comment],
+    root: u32,
+) -> std::result::Result<Vec<(usize, &crate::api::models::Comment)>, ThreadError> {
+    let by_id: std::collections::HashMap<u32, &crate::api::models::Comment> =
+        comments.iter().map(|c| (c.id, c)).collect();
+    let start = by_id.get(&root).ok_or(ThreadError::NotFound)?;
+
+    if start.parent.is_some() {
+        // Walk up to the top, stopping at parents we weren't given (or a cycle)
+        let mut top = *start;
+        let mut visited = std::collections::HashSet::from([top.id]);
+        while let Some(parent) = top.parent.as_ref().and_then(|p| by_id.get(&p.id))
+            && visited.insert(parent.id)
+        {
+            top = parent;
+        }
+        return Err(ThreadError::Reply { root: top.id });
+    }
+
+    let mut thread = Vec::new();
+    let mut stack = vec![(0, *start)];
+    while let Some((depth, comment)) = stack.pop() {
+        thread.push((depth, comment));
+        // Reversed so the earliest reply is popped first
+        stack.extend(
+            comments
+                .iter()
+                .filter(|c| c.parent.as_ref().is_some_and(|p| p.id == comment.id))
+                .rev()
+                .map(|c| (depth + 1, c)),
+        );
+    }
+    Ok(thread)
+}
+
+/// `author`'s comments in a thread from [`comment_thread`], all at depth 0
+///
+/// The comments they answered are left out, so indenting their replies would
+/// suggest they answered each other.
+fn thread_by_author<'a>(
+    thread: Vec<(usize, &'a crate::api::models::Comment)>,
+    author: &str,
+) -> Vec<(usize, &'a crate::api::models::Comment)> {
+    thread
+        .into_iter()
+        .filter(|(_, c)| c.user.matches(author))
+        .map(|(_, c)| (0, c))
+        .collect()
+}
+
+/// Sort comments by `created_on`, keeping API order for ties
+///
+/// Comments whose timestamp can't be parsed go last in either order.
+fn sort_comments(comments: &mut [crate::api::models::Comment], order: CommentSort) {
+    use crate::utils::time::parse_timestamp;
+
+    comments.sort_by(|a, b| {
+        match (
+            parse_timestamp(&a.created_on),
+            parse_timestamp(&b.created_on),
+        ) {
+            (Some(a), Some(b)) => match order {
+                CommentSort::Oldest => a.cmp(&b),
+                CommentSort::Newest => b.cmp(&a),
+            },
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::fixtures::PullRequestJson;
 
     fn create_test_context(
         config_workspace: Option<String>,
@@ -294,17 +1743,325 @@ mod tests {
         let client = crate::api::client::BitbucketClient::new(
             "https://api.bitbucket.org/2.0".to_string(),
             None,
+            Default::default(),
         )
         .unwrap();
 
         AppContext {
-            client,
-            json: false,
             workspace: config_workspace,
             repo: config_repo,
+            ..AppContext::for_test(client)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_current_user_uuid_refuses_without_profile() {
+        use crate::api::transport::mock::MockTransport;
+
+        let transport = std::sync::Arc::new(MockTransport::new());
+        let mut ctx = create_test_context(None, None);
+        ctx.client = ctx.client.with_transport(transport.clone());
+        ctx.no_config = true;
+
+        let err = current_user_uuid(&ctx, "--review-requested")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--review-requested needs a profile; not available with --no-config"
+        );
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_pr_caches_only_merged_prs() {
+        use crate::api::transport::mock::MockTransport;
+
+        let dir = tempfile::tempdir().unwrap();
+        let transport = std::sync::Arc::new(
+            MockTransport::new()
+                .json(
+                    reqwest::Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests/1",
+                    PullRequestJson::new(1).state("MERGED").json(),
+                )
+                .json(
+                    reqwest::Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests/2",
+                    PullRequestJson::new(2).state("DECLINED").json(),
+                ),
+        );
+        let mut ctx = create_test_context(Some("ws".to_string()), Some("repo".to_string()));
+        ctx.client = ctx.client.with_transport(transport.clone());
+
+        let fetch = |id: u32, refresh: bool| {
+            let path = dir.path().join(format!("{}.json", id));
+            let ctx = &ctx;
+            async move {
+                fetch_pr_cached(ctx, "ws", "repo", id, refresh, Some(&path))
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // A merged PR is fetched once, then served from the cache until --refresh
+        assert_eq!(fetch(1, false).await.state, "MERGED");
+        assert_eq!(fetch(1, false).await.state, "MERGED");
+        assert_eq!(transport.requests().len(), 1);
+        fetch(1, true).await;
+        assert_eq!(transport.requests().len(), 2);
+
+        // A declined PR can be reopened, so it's always fetched live
+        fetch(2, false).await;
+        fetch(2, false).await;
+        assert_eq!(transport.requests().len(), 4);
+        assert!(!dir.path().join("2.json").exists());
+    }
+
+    /// Run `pr view` for an open PR against `transport`, returning the paths requested
+    async fn view_requests(json: bool, no_comments: bool, no_statuses: bool) -> Vec<String> {
+        use crate::api::transport::mock::MockTransport;
+
+        let transport = std::sync::Arc::new(
+            MockTransport::new()
+                .json(
+                    reqwest::Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests/7",
+                    PullRequestJson::new(7).json(),
+                )
+                .json(
+                    reqwest::Method::GET,
+                    "/2.0/repositories/ws/repo/pullrequests/7/comments",
+                    serde_json::json!({"values": []}),
+                )
+                .json(
+                    reqwest::Method::GET,
+                    "/2.0/repositories/ws/repo/commit/abc123/statuses",
+                    serde_json::json!({"values": []}),
+                ),
+        );
+        let mut ctx = create_test_context(Some("ws".to_string()), Some("repo".to_string()));
+        ctx.client = ctx.client.with_transport(transport.clone());
+        ctx.json = json;
+
+        let command = PrCommands::View {
+            id: Some(7),
+            web: false,
+            file: None,
+            comments: false,
+            comments_first: false,
+            comments_only: false,
+            no_comments,
+            no_statuses,
+            print_links: false,
+            branch: None,
+            refresh: false,
+            raw_json: false,
+        };
+        handle(&ctx, command).await.unwrap();
+
+        transport
+            .requests()
+            .into_iter()
+            .map(|r| reqwest::Url::parse(&r.url).unwrap().path().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_view_no_comments_skips_comments_request() {
+        let comments_path = "/2.0/repositories/ws/repo/pullrequests/7/comments";
+        assert!(
+            view_requests(true, false, false)
+                .await
+                .iter()
+                .any(|p| p == comments_path)
+        );
+        assert!(
+            !view_requests(true, true, false)
+                .await
+                .iter()
+                .any(|p| p == comments_path)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_view_no_statuses_skips_statuses_request() {
+        let statuses_path = "/2.0/repositories/ws/repo/commit/abc123/statuses";
+        assert!(
+            view_requests(false, false, false)
+                .await
+                .iter()
+                .any(|p| p == statuses_path)
+        );
+
+        let requests = view_requests(false, true, true).await;
+        assert!(!requests.iter().any(|p| p == statuses_path));
+        assert!(!requests.iter().any(|p| p.ends_with("/comments")));
+    }
+
+    #[test]
+    fn test_build_summary_and_state() {
+        use crate::api::models::{BuildState, CommitStatus};
+
+        let status = |state: &str| CommitStatus {
+            key: state.to_lowercase(),
+            state: state.to_string(),
+            name: None,
+            url: "https://ci.example.com".to_string(),
+            description: None,
+        };
+        let summary = |statuses: &[CommitStatus]| {
+            let state = BuildState::aggregate(statuses);
+            (state, build_summary(42, statuses, state))
+        };
+
+        assert_eq!(
+            summary(&[status("SUCCESSFUL"), status("SUCCESSFUL")]),
+            (
+                Some(BuildState::Successful),
+                "All 2 builds passed for PR #42".to_string()
+            )
+        );
+        assert_eq!(
+            summary(&[status("SUCCESSFUL"), status("INPROGRESS"), status("FAILED")]),
+            (
+                Some(BuildState::Failed),
+                "1 of 3 builds failed for PR #42".to_string()
+            )
+        );
+        assert_eq!(
+            summary(&[status("SUCCESSFUL"), status("INPROGRESS")]),
+            (
+                Some(BuildState::InProgress),
+                "1 of 2 builds still running for PR #42".to_string()
+            )
+        );
+        assert_eq!(summary(&[status("STOPPED")]).0, Some(BuildState::Failed));
+        assert_eq!(
+            summary(&[]),
+            (None, "No builds reported for PR #42".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bare_id_views_pr() {
+        use crate::cli::{Cli, Commands};
+        use clap::Parser;
+
+        let pr_command = |argv: &[&str]| match Cli::try_parse_from(argv).map(|cli| cli.command) {
+            Ok(Commands::Pr(args)) => args.into_command().map_err(|e| e.kind()),
+            Ok(_) => panic!("not a pr command"),
+            Err(e) => Err(e.kind()),
+        };
+
+        assert!(matches!(
+            pr_command(&["bb", "pr", "42"]),
+            Ok(PrCommands::View {
+                id: Some(42),
+                web: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            pr_command(&["bb", "pr", "view", "42", "--web"]),
+            Ok(PrCommands::View {
+                id: Some(42),
+                web: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            pr_command(&["bb", "pr", "list"]),
+            Ok(PrCommands::List { .. })
+        ));
+        // Options belong to subcommands, and anything but a number isn't an ID
+        assert!(pr_command(&["bb", "pr", "42", "--web"]).is_err());
+        assert!(matches!(
+            pr_command(&["bb", "pr", "lsit"]),
+            Err(clap::error::ErrorKind::InvalidSubcommand)
+        ));
+    }
+
+    #[test]
+    fn test_comments_author_conflicts_with_new() {
+        use crate::cli::Cli;
+        use clap::Parser;
+
+        let err = Cli::try_parse_from(["bb", "pr", "comments", "42", "--new", "--author", "jane"])
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        for argv in [
+            &["bb", "pr", "comments", "42", "--new"][..],
+            &["bb", "pr", "comments", "42", "--author", "jane"],
+        ] {
+            assert!(Cli::try_parse_from(argv).is_ok(), "{:?}", argv);
         }
     }
 
+    #[test]
+    fn test_approval_gate() {
+        use crate::error::{EXIT_APPROVALS, exit_code};
+
+        let err = ApprovalGate::new(7, 0, 1).check().unwrap_err();
+        assert_eq!(err.to_string(), "PR #7 has no approvals");
+        assert_eq!(exit_code(&err), EXIT_APPROVALS);
+
+        let err = ApprovalGate::new(7, 1, 2).check().unwrap_err();
+        assert_eq!(err.to_string(), "PR #7 has 1 of 2 required approvals");
+        assert_eq!(exit_code(&err), EXIT_APPROVALS);
+
+        let gate = ApprovalGate::new(7, 2, 2);
+        assert!(gate.check().is_ok());
+        assert_eq!(gate.summary(), "PR #7 has 2 of 2 required approvals");
+    }
+
+    #[test]
+    fn test_list_watch_with_review_requested() {
+        use crate::cli::Cli;
+        use clap::Parser;
+
+        assert!(Cli::try_parse_from(["bb", "pr", "list", "--watch", "--review-requested"]).is_ok());
+    }
+
+    #[test]
+    fn test_list_watch_conflicts_with_no_trunc() {
+        use crate::cli::Cli;
+        use clap::Parser;
+
+        let err = Cli::try_parse_from(["bb", "pr", "list", "--watch", "--no-trunc"])
+            .err()
+            .expect("--watch --no-trunc should be rejected");
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
+    #[tokio::test]
+    async fn test_list_watch_refuses_explain() {
+        use crate::api::transport::mock::MockTransport;
+        use crate::cli::{Cli, Commands};
+        use clap::Parser;
+
+        let Commands::Pr(args) = Cli::try_parse_from(["bb", "--explain", "pr", "list", "--watch"])
+            .unwrap()
+            .command
+        else {
+            panic!("expected a pr command");
+        };
+        let transport = std::sync::Arc::new(MockTransport::new());
+        let mut ctx = create_test_context(Some("ws".to_string()), Some("repo".to_string()));
+        ctx.client = ctx
+            .client
+            .with_transport(transport.clone())
+            .with_explain(true);
+
+        let err = handle(&ctx, args.into_command().unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "--watch can't be combined with --explain");
+        assert!(transport.requests().is_empty());
+    }
+
     #[test]
     fn test_context_resolution_mock() {
         // Since resolution logic moved to main.rs, we can just verify AppContext holds values
@@ -313,6 +2070,18 @@ mod tests {
         assert_eq!(ctx.repo.as_deref(), Some("repo"));
     }
 
+    #[test]
+    fn test_parse_compare_spec() {
+        assert_eq!(
+            parse_compare_spec("feature/x..main").unwrap(),
+            ("feature/x", "main")
+        );
+        assert!(parse_compare_spec("feature").is_err());
+        assert!(parse_compare_spec("..main").is_err());
+        assert!(parse_compare_spec("feature..").is_err());
+        assert!(parse_compare_spec("feature...main").is_err());
+    }
+
     #[test]
     fn test_parse_args_with_id() {
         // Case 1: ID and patterns
@@ -339,4 +2108,240 @@ mod tests {
         assert_eq!(id, None);
         assert!(patterns.is_empty());
     }
+
+    fn reply(id: u32, parent: u32) -> crate::api::models::Comment {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "content": {"raw": "text"},
+            "created_on": "2024-01-01T00:00:00+00:00",
+            "user": {"display_name": "User", "uuid": "{u}"},
+            "parent": {"id": parent},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_comment_thread() {
+        let comments = vec![
+            comment(1, "2024-01-01T00:00:00+00:00"),
+            reply(2, 1),
+            comment(3, "2024-01-01T00:00:00+00:00"),
+            reply(4, 2),
+            reply(5, 1),
+            reply(6, 3),
+        ];
+
+        let thread: Vec<(usize, u32)> = comment_thread(&comments, 1)
+            .unwrap()
+            .into_iter()
+            .map(|(depth, c)| (depth, c.id))
+            .collect();
+        assert_eq!(thread, vec![(0, 1), (1, 2), (2, 4), (1, 5)]);
+
+        assert_eq!(
+            comment_thread(&comments, 4).unwrap_err(),
+            ThreadError::Reply { root: 1 }
+        );
+        assert_eq!(
+            comment_thread(&comments, 99).unwrap_err(),
+            ThreadError::NotFound
+        );
+    }
+
+    #[test]
+    fn test_thread_by_author_flattens_replies() {
+        let by = |id: u32, parent: Option<u32>, name: &str| -> crate::api::models::Comment {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "content": {"raw": "text"},
+                "created_on": "2024-01-01T00:00:00+00:00",
+                "user": {"display_name": name, "uuid": format!("{{{}}}", name)},
+                "parent": parent.map(|id| serde_json::json!({"id": id})),
+            }))
+            .unwrap()
+        };
+        let comments = vec![
+            by(1, None, "Alice"),
+            by(2, Some(1), "Bob"),
+            by(3, Some(2), "Alice"),
+            by(4, Some(3), "Bob"),
+        ];
+
+        let thread = thread_by_author(comment_thread(&comments, 1).unwrap(), "Bob");
+        let thread: Vec<(usize, u32)> = thread.into_iter().map(|(d, c)| (d, c.id)).collect();
+        assert_eq!(thread, vec![(0, 2), (0, 4)]);
+    }
+
+    fn comment(id: u32, created_on: &str) -> crate::api::models::Comment {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "content": {"raw": "text"},
+            "created_on": created_on,
+            "user": {"display_name": "User", "uuid": "{u}"},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_filter_comments_by_author() {
+        let by = |id: u32, name: &str, uuid: &str| -> crate::api::models::Comment {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "content": {"raw": "text"},
+                "created_on": "2024-01-01T00:00:00+00:00",
+                "user": {"display_name": name, "uuid": uuid},
+            }))
+            .unwrap()
+        };
+        let comments = [
+            by(1, "Jane Doe", "{1b2c}"),
+            by(2, "Bob", "{9f8e}"),
+            by(3, "Jane Doe", "{1b2c}"),
+        ];
+        let ids = |author: &str| -> Vec<u32> {
+            comments
+                .iter()
+                .filter(|c| c.user.matches(author))
+                .map(|c| c.id)
+                .collect()
+        };
+
+        assert_eq!(ids("jane doe"), vec![1, 3]);
+        assert_eq!(ids("{1B2C}"), vec![1, 3]);
+        assert_eq!(ids("9f8e"), vec![2]);
+        assert_eq!(ids("Jane"), Vec::<u32>::new());
+        assert_eq!(ids("{}"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_parse_pr_state() {
+        assert_eq!(parse_pr_state("open").unwrap(), "OPEN");
+        assert_eq!(parse_pr_state("Merged").unwrap(), "MERGED");
+        assert_eq!(parse_pr_state(" declined ").unwrap(), "DECLINED");
+        assert_eq!(parse_pr_state("SUPERSEDED").unwrap(), "SUPERSEDED");
+
+        let err = parse_pr_state("closed").unwrap_err();
+        assert!(err.contains("closed"));
+        assert!(err.contains("OPEN, MERGED, DECLINED, SUPERSEDED"));
+    }
+
+    #[test]
+    fn test_sort_comments() {
+        let mut comments = vec![
+            comment(1, "2024-01-02T00:00:00+00:00"),
+            comment(2, "not a date"),
+            comment(3, "2024-01-01T00:00:00+00:00"),
+            comment(4, ""),
+            comment(5, "2024-01-03T00:00:00+00:00"),
+        ];
+
+        sort_comments(&mut comments, CommentSort::Oldest);
+        let ids: Vec<u32> = comments.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![3, 1, 5, 2, 4]);
+
+        sort_comments(&mut comments, CommentSort::Newest);
+        let ids: Vec<u32> = comments.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![5, 1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn test_parse_comment_marker() {
+        assert_eq!(parse_comment_marker("42"), Ok(CommentMarker::Id(42)));
+        assert_eq!(
+            parse_comment_marker("2024-01-02"),
+            parse_comment_marker("2024-01-02T00:00:00Z")
+        );
+        assert!(matches!(
+            parse_comment_marker("2024-01-02T10:00:00+02:00"),
+            Ok(CommentMarker::Time(_))
+        ));
+        assert!(parse_comment_marker("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_retain_comments_after() {
+        let all = || {
+            vec![
+                comment(10, "2024-01-01T00:00:00+00:00"),
+                comment(11, "2024-01-02T12:00:00+00:00"),
+                comment(12, "not a date"),
+                comment(13, "2024-01-03T00:00:00+00:00"),
+            ]
+        };
+
+        let mut comments = all();
+        retain_comments_after(&mut comments, &CommentMarker::Id(11));
+        let ids: Vec<u32> = comments.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![12, 13]);
+
+        let mut comments = all();
+        retain_comments_after(&mut comments, &parse_comment_marker("2024-01-02").unwrap());
+        let ids: Vec<u32> = comments.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![11, 13]);
+
+        let mut comments = all();
+        retain_comments_after(&mut comments, &CommentMarker::Id(13));
+        assert!(comments.is_empty());
+    }
+
+    #[test]
+    fn test_pr_diff_web_url() {
+        let html = "https://bitbucket.org/ws/repo/pull-requests/42";
+        assert_eq!(
+            pr_diff_web_url(html, None),
+            "https://bitbucket.org/ws/repo/pull-requests/42/diff"
+        );
+        assert_eq!(
+            pr_diff_web_url(html, Some("src/main.rs")),
+            "https://bitbucket.org/ws/repo/pull-requests/42/diff#chg-src/main.rs"
+        );
+        assert_eq!(
+            pr_diff_web_url(html, Some("./docs/my notes \"v2\".md")),
+            "https://bitbucket.org/ws/repo/pull-requests/42/diff#chg-docs/my%20notes%20%22v2%22.md"
+        );
+    }
+
+    #[test]
+    fn test_diff_file_truncated_rewinds() {
+        use std::io::Write;
+
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"@@ -1,2 +1,2 @@\n-old\n+new\n context\n")
+            .unwrap();
+        assert!(!diff_file_truncated(&file));
+        assert_eq!(
+            read_diff(file).unwrap(),
+            "@@ -1,2 +1,2 @@\n-old\n+new\n context\n"
+        );
+
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"@@ -1,2 +1,2 @@\n-old\n").unwrap();
+        assert!(diff_file_truncated(&file));
+    }
+
+    #[test]
+    fn test_count_commits_by_author() {
+        let mut commits: Vec<crate::api::models::Commit> =
+            serde_json::from_str(crate::api::fixtures::COMMITS_JSON).unwrap();
+        assert_eq!(
+            count_commits_by_author(&commits),
+            vec![
+                crate::api::models::AuthorCommits {
+                    author: "Alice Smith".to_string(),
+                    commits: 2
+                },
+                crate::api::models::AuthorCommits {
+                    author: "Bob".to_string(),
+                    commits: 1
+                },
+            ]
+        );
+
+        commits.retain(|c| !c.is_merge());
+        let counts = count_commits_by_author(&commits);
+        // A tie is broken by name
+        assert_eq!(counts[0].author, "Alice Smith");
+        assert_eq!(counts[1].author, "Bob");
+        assert!(counts.iter().all(|c| c.commits == 1));
+    }
 }
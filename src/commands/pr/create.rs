@@ -0,0 +1,512 @@
+use crate::api::models::{BranchRef, NewPullRequest, User, UserRef};
+use crate::context::AppContext;
+use crate::display::ui;
+use anyhow::{Context, Result};
+use clap::Args;
+use dialoguer::Input;
+
+#[derive(Args, Debug)]
+pub struct CreateArgs {
+    /// Title of the pull request
+    #[arg(short, long)]
+    pub title: Option<String>,
+
+    /// Description of the pull request
+    #[arg(short, long)]
+    pub body: Option<String>,
+
+    /// Destination branch (defaults to the tracked upstream branch, then the repository's main branch)
+    #[arg(short = 'B', long)]
+    pub base: Option<String>,
+
+    /// Use commit subjects/bodies for the title and description, skipping prompts
+    #[arg(short, long)]
+    pub fill: bool,
+
+    /// Use a title derived from the branch name (e.g. `feature/PROJ-1-add-cache` → `PROJ-1 Add Cache`) without asking
+    #[arg(long, conflicts_with_all = ["title", "fill"])]
+    pub title_from_branch: bool,
+
+    /// Open the pull request as a draft
+    #[arg(short, long)]
+    pub draft: bool,
+
+    /// Reviewer UUID, or @group to add every member of a workspace group (repeatable)
+    #[arg(short, long = "reviewer")]
+    pub reviewers: Vec<String>,
+
+    /// Push the branch without asking if it isn't on the remote yet
+    #[arg(long)]
+    pub push: bool,
+
+    /// Open Bitbucket's create page for the branch in the browser instead of creating it here
+    #[arg(long, conflicts_with_all = ["title", "body", "fill", "title_from_branch", "draft", "reviewers"])]
+    pub web: bool,
+}
+
+pub async fn pr_create(ctx: &AppContext, args: &CreateArgs) -> Result<()> {
+    let (workspace, repo) = ctx.require_repo()?;
+
+    let source_branch = crate::git::get_current_branch()?;
+    let web_base = args
+        .web
+        .then(|| {
+            ctx.client.web_base_url().context(
+                "--web only knows Bitbucket Cloud's create page; \
+                 open the pull request from your server's web UI instead",
+            )
+        })
+        .transpose()?;
+    let remote = ctx.remote.as_deref().unwrap_or("origin");
+    // --explain only prints the API request, so it mustn't push anything
+    if !ctx.client.explains() {
+        ensure_branch_pushed(remote, &source_branch, args.push)?;
+    }
+
+    // Prefer the tracked branch (e.g. origin/develop) over the repository's
+    // main branch, unless it's just the source branch's own remote copy
+    let upstream = crate::git::get_upstream_branch().filter(|u| *u != source_branch);
+    let destination = args.base.clone().or_else(|| upstream.clone());
+    if let Some(dest) = &destination {
+        crate::utils::debug::log(&format!("Destination branch: {}", dest));
+    }
+
+    if let Some(web_base) = web_base {
+        let url = pr_create_web_url(
+            &web_base,
+            workspace,
+            repo,
+            &source_branch,
+            destination.as_deref(),
+        )?;
+        // Like the API requests it stands in for, the page is shown, not opened
+        if ctx.client.explains() {
+            println!("{}", url);
+            return Ok(());
+        }
+        if crate::utils::browser::open_url(&url) {
+            ui::success(&format!(
+                "Opened the pull request page for '{}' in browser",
+                source_branch
+            ));
+        }
+        return Ok(());
+    }
+
+    let (title, description) = if args.fill {
+        // Compare against the explicit base, the tracked ref, or the remote's default branch
+        let log_base = match (&args.base, &upstream) {
+            (Some(base), _) => base.clone(),
+            (None, Some(_)) => "@{u}".to_string(),
+            (None, None) => remote_default_branch(ctx, workspace, repo, remote).await?,
+        };
+        let commits = crate::git::log_between(&log_base, "HEAD")?;
+        let (fill_title, fill_body) = fill_from_commits(&commits)
+            .with_context(|| format!("No commits found between '{}' and HEAD", log_base))?;
+
+        (
+            args.title.clone().unwrap_or(fill_title),
+            args.body.clone().or(Some(fill_body)),
+        )
+    } else {
+        let branch_title = title_from_branch(&source_branch);
+        let title = match &args.title {
+            Some(t) => t.clone(),
+            None if args.title_from_branch => branch_title,
+            None => Input::new()
+                .with_prompt("Title")
+                .with_initial_text(branch_title)
+                .interact_text()?,
+        };
+        let body = match &args.body {
+            Some(b) => b.clone(),
+            None => Input::new()
+                .with_prompt("Description (optional)")
+                .allow_empty(true)
+                .interact_text()?,
+        };
+        (title, Some(body))
+    };
+
+    let reviewers = resolve_reviewers(ctx, workspace, &args.reviewers).await?;
+
+    let new_pr = NewPullRequest {
+        title,
+        description: description.filter(|d| !d.is_empty()),
+        source: BranchRef::new(&source_branch),
+        destination: destination.as_deref().map(BranchRef::new),
+        draft: args.draft,
+        reviewers,
+    };
+
+    let pr = ctx
+        .client
+        .create_pull_request(workspace, repo, &new_pr)
+        .await?;
+
+    if ctx.json {
+        ui::print_json(&pr)?;
+    } else {
+        let kind = if pr.is_draft() {
+            "draft pull request"
+        } else {
+            "pull request"
+        };
+        ui::success(&format!(
+            "Created {} #{}: {}",
+            kind, pr.id, pr.links.html.href
+        ));
+    }
+
+    Ok(())
+}
+
+/// The remote's copy of the default branch, as a ref to log against
+///
+/// `<remote>/HEAD` when git knows it; otherwise the repository's main branch
+/// as Bitbucket reports it, under the same remote.
+async fn remote_default_branch(
+    ctx: &AppContext,
+    workspace: &str,
+    repo: &str,
+    remote: &str,
+) -> Result<String> {
+    let remote_head = format!("{}/HEAD", remote);
+    if crate::git::ref_exists(&remote_head) {
+        return Ok(remote_head);
+    }
+
+    crate::utils::debug::log(&format!(
+        "{} isn't set; asking Bitbucket for the main branch",
+        remote_head
+    ));
+    let main_branch = ctx
+        .client
+        .get_repository(workspace, repo)
+        .await?
+        .mainbranch
+        .with_context(|| {
+            format!(
+                "{}/{} has no main branch to compare against; pass --base",
+                workspace, repo
+            )
+        })?;
+    Ok(format!("{}/{}", remote, main_branch.name))
+}
+
+/// Bitbucket's page under `web_base` for opening a PR from `source`, with the
+/// destination preselected when given (otherwise Bitbucket picks the main branch)
+fn pr_create_web_url(
+    web_base: &reqwest::Url,
+    workspace: &str,
+    repo: &str,
+    source: &str,
+    destination: Option<&str>,
+) -> Result<String> {
+    let mut url = web_base.clone();
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("Invalid base URL"))?
+        .extend([workspace, repo, "pull-requests", "new"]);
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("source", source);
+        if let Some(dest) = destination {
+            query.append_pair("dest", dest);
+        }
+    }
+    Ok(url.into())
+}
+
+/// Make sure `branch` is on `remote`, pushing it if needed
+///
+/// Bitbucket can only open a PR from a branch it has. An unpushed branch is
+/// pushed with `--push`, or after confirmation when run interactively.
+fn ensure_branch_pushed(remote: &str, branch: &str, push: bool) -> Result<()> {
+    use std::io::IsTerminal;
+
+    if crate::git::branch_exists_on_remote(remote, branch)? {
+        return Ok(());
+    }
+
+    let push = push
+        || (std::io::stdin().is_terminal()
+            && dialoguer::Confirm::new()
+                .with_prompt(format!(
+                    "Branch '{}' isn't on remote '{}'. Push it now?",
+                    branch, remote
+                ))
+                .default(true)
+                .interact()?);
+    if !push {
+        anyhow::bail!(
+            "Branch '{0}' isn't on remote '{1}'; push your branch first \
+             (git push -u {1} {0}) or pass --push",
+            branch,
+            remote
+        );
+    }
+
+    crate::git::push_branch(remote, branch)?;
+    ui::success(&format!("Pushed '{}' to '{}'", branch, remote));
+    Ok(())
+}
+
+/// Turn `--reviewer` values into reviewer refs, expanding `@group` entries
+///
+/// Group lookups fail loudly if the group doesn't exist. The author can't
+/// review their own PR, so they're dropped from expanded groups.
+async fn resolve_reviewers(
+    ctx: &AppContext,
+    workspace: &str,
+    values: &[String],
+) -> Result<Vec<UserRef>> {
+    let mut explicit = Vec::new();
+    let mut members = Vec::new();
+    for value in values {
+        match value.strip_prefix('@') {
+            Some(group) => {
+                let group_members = ctx.client.resolve_group_members(workspace, group).await?;
+                if group_members.is_empty() {
+                    ui::warning(&format!("Group '@{}' has no members", group));
+                }
+                members.extend(group_members);
+            }
+            None => explicit.push(value.as_str()),
+        }
+    }
+
+    let author = if members.is_empty() {
+        None
+    } else {
+        Some(super::current_user_uuid(ctx, "Adding @group reviewers").await?)
+    };
+
+    Ok(merge_reviewers(&explicit, &members, author.as_deref()))
+}
+
+/// Combine explicit reviewer UUIDs with expanded group members, without duplicates
+///
+/// UUIDs may be given with or without braces. `exclude` (the author) is only
+/// dropped from group members; naming yourself explicitly is left for the API to reject.
+fn merge_reviewers(explicit: &[&str], members: &[User], exclude: Option<&str>) -> Vec<UserRef> {
+    let mut reviewers: Vec<UserRef> = Vec::new();
+    let mut push = |uuid: String| {
+        if !reviewers.iter().any(|r| r.uuid == uuid) {
+            reviewers.push(UserRef { uuid });
+        }
+    };
+
+    for value in explicit {
+        push(braced_uuid(value));
+    }
+    for member in members {
+        let uuid = braced_uuid(&member.uuid);
+        if exclude.map(braced_uuid).as_deref() != Some(uuid.as_str()) {
+            push(uuid);
+        }
+    }
+    reviewers
+}
+
+fn braced_uuid(uuid: &str) -> String {
+    format!(
+        "{{{}}}",
+        uuid.trim().trim_start_matches('{').trim_end_matches('}')
+    )
+}
+
+/// Branch prefixes that say what kind of change it is rather than what it does
+const BRANCH_KIND_PREFIXES: [&str; 5] = ["feature/", "feat/", "bugfix/", "fix/", "hotfix/"];
+
+/// A readable title from a branch name: `feature/PROJ-123-add-cache` → `PROJ-123 Add Cache`
+///
+/// A leading kind prefix is dropped and the rest is split on `-`, `_` and `/`
+/// with each word capitalized. Jira issue keys stay whole.
+fn title_from_branch(branch: &str) -> String {
+    let name = BRANCH_KIND_PREFIXES
+        .iter()
+        .find_map(|prefix| {
+            branch
+                .get(..prefix.len())
+                .filter(|p| p.eq_ignore_ascii_case(prefix))
+                .map(|_| &branch[prefix.len()..])
+        })
+        .unwrap_or(branch);
+
+    let mut words: Vec<String> = Vec::new();
+    for word in name.split(['-', '_', '/']).filter(|w| !w.is_empty()) {
+        // `PROJ`, `123` came from an issue key split at its dash
+        let after_project_key = words.last().is_some_and(|previous| {
+            previous.len() >= 2
+                && previous.starts_with(|c: char| c.is_ascii_uppercase())
+                && previous
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        });
+        if after_project_key && word.chars().all(|c| c.is_ascii_digit()) {
+            let key = format!("{}-{}", words.pop().unwrap_or_default(), word);
+            words.push(key);
+            continue;
+        }
+
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            words.push(first.to_uppercase().chain(chars).collect());
+        }
+    }
+
+    if words.is_empty() {
+        branch.to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+/// Derive a title and description from commits (newest first, as returned by `git log`)
+///
+/// A single commit supplies its own subject and body. With several commits the
+/// latest subject becomes the title and every subject is listed, oldest first,
+/// as a checklist in the description.
+fn fill_from_commits(commits: &[(String, String)]) -> Option<(String, String)> {
+    let (latest_subject, latest_body) = commits.first()?;
+
+    if commits.len() == 1 {
+        return Some((latest_subject.clone(), latest_body.clone()));
+    }
+
+    let body = commits
+        .iter()
+        .rev()
+        .map(|(subject, _)| format!("- [ ] {}", subject))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some((latest_subject.clone(), body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pr_create_web_url() {
+        let web = reqwest::Url::parse("https://bitbucket.org/").unwrap();
+        assert_eq!(
+            pr_create_web_url(&web, "ws", "repo", "feature/login", Some("develop")).unwrap(),
+            "https://bitbucket.org/ws/repo/pull-requests/new?source=feature%2Flogin&dest=develop"
+        );
+        assert_eq!(
+            pr_create_web_url(&web, "ws", "repo", "fix/a&b #1", None).unwrap(),
+            "https://bitbucket.org/ws/repo/pull-requests/new?source=fix%2Fa%26b+%231"
+        );
+    }
+
+    #[test]
+    fn test_title_from_branch() {
+        assert_eq!(
+            title_from_branch("feature/PROJ-123-add-cache"),
+            "PROJ-123 Add Cache"
+        );
+        assert_eq!(title_from_branch("bugfix/login_timeout"), "Login Timeout");
+        assert_eq!(title_from_branch("Hotfix/null-check"), "Null Check");
+        assert_eq!(title_from_branch("add-http2-support"), "Add Http2 Support");
+        assert_eq!(title_from_branch("alice/wip/retry"), "Alice Wip Retry");
+        assert_eq!(title_from_branch("PROJ-9"), "PROJ-9");
+        assert_eq!(
+            title_from_branch("fix-PROJ-9-and-OPS-12"),
+            "Fix PROJ-9 And OPS-12"
+        );
+        assert_eq!(title_from_branch("feature/"), "feature/");
+    }
+
+    #[test]
+    fn test_fill_from_single_commit() {
+        let commits = vec![("Add cache".to_string(), "Details here".to_string())];
+        let (title, body) = fill_from_commits(&commits).unwrap();
+        assert_eq!(title, "Add cache");
+        assert_eq!(body, "Details here");
+    }
+
+    #[test]
+    fn test_fill_from_multiple_commits() {
+        let commits = vec![
+            ("Fix tests".to_string(), String::new()),
+            ("Add cache".to_string(), "Details".to_string()),
+        ];
+        let (title, body) = fill_from_commits(&commits).unwrap();
+        assert_eq!(title, "Fix tests");
+        assert_eq!(body, "- [ ] Add cache\n- [ ] Fix tests");
+    }
+
+    fn user(uuid: &str) -> User {
+        User {
+            display_name: uuid.to_string(),
+            uuid: uuid.to_string(),
+            nickname: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_reviewers_dedupes_and_skips_author() {
+        let members = vec![user("{b}"), user("{me}"), user("{c}"), user("{b}")];
+        let reviewers = merge_reviewers(&["a", "{b}"], &members, Some("{me}"));
+
+        let uuids: Vec<&str> = reviewers.iter().map(|r| r.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["{a}", "{b}", "{c}"]);
+    }
+
+    #[test]
+    fn test_new_pull_request_reviewers_payload() {
+        let mut pr = NewPullRequest {
+            title: "t".to_string(),
+            description: None,
+            source: BranchRef::new("feature"),
+            destination: None,
+            draft: false,
+            reviewers: vec![],
+        };
+        let json = serde_json::to_value(&pr).unwrap();
+        assert!(json.get("reviewers").is_none());
+
+        pr.reviewers = merge_reviewers(&["{a}"], &[], None);
+        let json = serde_json::to_value(&pr).unwrap();
+        assert_eq!(json["reviewers"], serde_json::json!([{"uuid": "{a}"}]));
+    }
+
+    #[test]
+    fn test_fill_from_no_commits() {
+        assert!(fill_from_commits(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remote_default_branch_falls_back_to_main_branch() {
+        use crate::api::transport::mock::MockTransport;
+
+        let transport = std::sync::Arc::new(MockTransport::new().json(
+            reqwest::Method::GET,
+            "/2.0/repositories/ws/repo",
+            serde_json::json!({
+                "name": "repo", "full_name": "ws/repo", "uuid": "{repo}",
+                "mainbranch": {"type": "branch", "name": "develop"}
+            }),
+        ));
+        let client = crate::api::client::BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            None,
+            Default::default(),
+        )
+        .unwrap()
+        .with_transport(transport.clone());
+        let ctx = AppContext {
+            remote: Some("no-such-remote".to_string()),
+            ..AppContext::for_test(client)
+        };
+
+        // No git remote by that name, so no <remote>/HEAD either
+        let base = remote_default_branch(&ctx, "ws", "repo", "no-such-remote")
+            .await
+            .unwrap();
+        assert_eq!(base, "no-such-remote/develop");
+        assert_eq!(transport.requests().len(), 1);
+    }
+}
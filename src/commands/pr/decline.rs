@@ -0,0 +1,368 @@
+use crate::context::AppContext;
+use crate::display::{pr as pr_display, ui};
+use crate::utils::concurrency;
+use anyhow::Result;
+use clap::Args;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+#[derive(Args, Debug)]
+#[command(group(
+    clap::ArgGroup::new("target")
+        .required(true)
+        .args(["ids", "older_than"])
+))]
+pub struct DeclineArgs {
+    /// IDs of the pull requests to decline
+    pub ids: Vec<u32>,
+
+    /// Decline every open pull request not updated within this long (e.g. 90d, 12w)
+    #[arg(long, value_name = "AGE", value_parser = crate::utils::time::parse_age)]
+    pub older_than: Option<chrono::Duration>,
+
+    /// Max number of PRs to decline with --older-than [default: pr.default_limit, else 50]
+    #[arg(long, requires = "older_than")]
+    pub limit: Option<u32>,
+
+    /// Decline without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+/// Outcome of declining one pull request, for `--json`
+#[derive(serde::Serialize)]
+struct DeclineResult {
+    id: u32,
+    declined: bool,
+    error: Option<String>,
+}
+
+pub async fn pr_decline(ctx: &AppContext, args: &DeclineArgs) -> Result<()> {
+    let (workspace, repo) = ctx.require_repo()?;
+
+    let ids: Vec<u32> = match args.older_than {
+        Some(age) => {
+            let cutoff = chrono::Utc::now() - age;
+            let query = crate::api::client::pr_updated_before_query(cutoff);
+            let limit = args
+                .limit
+                .or(ctx.pr_default_limit)
+                .unwrap_or(crate::constants::DEFAULT_PR_LIST_LIMIT);
+            let prs = ctx
+                .client
+                .list_pull_requests(
+                    workspace,
+                    repo,
+                    &["OPEN".to_string()],
+                    Some(&query),
+                    Some(limit),
+                    None,
+                )
+                .await?;
+
+            if prs.is_empty() {
+                ui::info(&format!(
+                    "No open pull requests in {}/{} updated before {}",
+                    workspace,
+                    repo,
+                    cutoff.format("%Y-%m-%d %H:%M UTC")
+                ));
+                return Ok(());
+            }
+
+            use pr_display::PrColumn;
+            let columns = [
+                PrColumn::Id,
+                PrColumn::Title,
+                PrColumn::Author,
+                PrColumn::Updated,
+            ];
+            let preview = pr_display::format_pr_list(&prs, &columns, false);
+            // Keep stdout for the JSON results
+            if ctx.json {
+                eprintln!("{}", preview);
+            } else {
+                println!("{}", preview);
+            }
+            prs.iter().map(|pr| pr.id).collect()
+        }
+        None => args.ids.clone(),
+    };
+
+    // --explain stops at the first request (see CliError::Explained), so
+    // show that one directly; there's nothing to confirm
+    if ctx.client.explains()
+        && let Some(&id) = ids.first()
+    {
+        return ctx.client.decline_pull_request(workspace, repo, id).await;
+    }
+
+    if !args.yes && !confirm_decline(ids.len(), std::io::stdin().is_terminal())? {
+        ui::info("Nothing declined");
+        return Ok(());
+    }
+
+    let results = decline_all(ctx, workspace, repo, &ids).await;
+
+    if ctx.json {
+        ui::print_json_list(&results)?;
+    } else {
+        for result in &results {
+            match &result.error {
+                None => ui::success(&format!("Declined PR #{}", result.id)),
+                Some(e) => ui::error(&format!("Failed to decline PR #{}: {}", result.id, e)),
+            }
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.declined).count();
+    if failed > 0 {
+        anyhow::bail!(
+            "{} of {} pull requests could not be declined",
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+/// Decline each PR, at most `--max-concurrency` at a time, carrying on past failures
+///
+/// Results are in ID order.
+async fn decline_all(
+    ctx: &AppContext,
+    workspace: &str,
+    repo: &str,
+    ids: &[u32],
+) -> Vec<DeclineResult> {
+    let mut tasks = tokio::task::JoinSet::new();
+    let mut pr_ids = HashMap::new();
+    for &id in ids {
+        let client = ctx.client.clone();
+        let limiter = ctx.limiter.clone();
+        let (workspace, repo) = (workspace.to_string(), repo.to_string());
+        let task = tasks.spawn(async move {
+            let result =
+                concurrency::bounded(&limiter, client.decline_pull_request(&workspace, &repo, id))
+                    .await;
+            DeclineResult {
+                id,
+                declined: result.is_ok(),
+                error: result.err().map(|e| format!("{:#}", e)),
+            }
+        });
+        pr_ids.insert(task.id(), id);
+    }
+
+    collect_results(tasks, &pr_ids).await
+}
+
+/// Wait for every decline task, in PR ID order
+///
+/// A task that panicked or was cancelled counts as a failure for the PR it
+/// was declining (looked up in `pr_ids`), so it still shows in the results.
+async fn collect_results(
+    mut tasks: tokio::task::JoinSet<DeclineResult>,
+    pr_ids: &HashMap<tokio::task::Id, u32>,
+) -> Vec<DeclineResult> {
+    let mut results = Vec::with_capacity(pr_ids.len());
+    while let Some(joined) = tasks.join_next_with_id().await {
+        match joined {
+            Ok((_, result)) => results.push(result),
+            Err(e) => match pr_ids.get(&e.id()) {
+                Some(&id) => results.push(DeclineResult {
+                    id,
+                    declined: false,
+                    error: Some(format!("Decline task failed: {}", e)),
+                }),
+                None => crate::utils::debug::log(&format!("Decline task failed: {}", e)),
+            },
+        }
+    }
+    results.sort_by_key(|r| r.id);
+    results
+}
+
+/// Ask before declining; refuses when there's no terminal to ask on
+fn confirm_decline(count: usize, interactive: bool) -> Result<bool> {
+    if !interactive {
+        anyhow::bail!("Refusing to decline pull requests without confirmation; pass --yes");
+    }
+
+    let prompt = if count == 1 {
+        "Decline this pull request?".to_string()
+    } else {
+        format!("Decline these {} pull requests?", count)
+    };
+    Ok(dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fixtures::PullRequestJson;
+    use crate::api::transport::mock::MockTransport;
+    use reqwest::Method;
+    use std::sync::Arc;
+
+    const PULL_REQUESTS: &str = "/2.0/repositories/ws/repo/pullrequests";
+
+    fn context(transport: &Arc<MockTransport>) -> AppContext {
+        let client = crate::api::client::BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            Some(("user".to_string(), "pass".to_string())),
+            Default::default(),
+        )
+        .unwrap()
+        .with_transport(transport.clone());
+
+        AppContext {
+            json: true,
+            ..AppContext::for_test(client)
+        }
+    }
+
+    fn args(ids: &[u32], older_than: Option<chrono::Duration>) -> DeclineArgs {
+        DeclineArgs {
+            ids: ids.to_vec(),
+            older_than,
+            limit: None,
+            yes: true,
+        }
+    }
+
+    fn declined_paths(transport: &MockTransport) -> Vec<String> {
+        transport
+            .requests()
+            .into_iter()
+            .filter(|r| r.method == Method::POST)
+            .map(|r| {
+                r.url
+                    .trim_start_matches("https://api.bitbucket.org")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_decline_older_than_declines_selected_prs() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .json(
+                    Method::GET,
+                    PULL_REQUESTS,
+                    serde_json::json!({"values": [PullRequestJson::new(3).json(), PullRequestJson::new(5).json()]}),
+                )
+                .json(
+                    Method::POST,
+                    &format!("{}/3/decline", PULL_REQUESTS),
+                    serde_json::json!({}),
+                )
+                .json(
+                    Method::POST,
+                    &format!("{}/5/decline", PULL_REQUESTS),
+                    serde_json::json!({}),
+                ),
+        );
+        let ctx = context(&transport);
+
+        pr_decline(&ctx, &args(&[], Some(chrono::Duration::days(90))))
+            .await
+            .unwrap();
+
+        let query = transport.requests()[0].url.clone();
+        assert!(query.contains("updated_on"), "{}", query);
+        let mut declined = declined_paths(&transport);
+        declined.sort();
+        assert_eq!(
+            declined,
+            [
+                format!("{}/3/decline", PULL_REQUESTS),
+                format!("{}/5/decline", PULL_REQUESTS),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decline_older_than_with_nothing_stale() {
+        let transport = Arc::new(MockTransport::new().json(
+            Method::GET,
+            PULL_REQUESTS,
+            serde_json::json!({"values": []}),
+        ));
+
+        pr_decline(
+            &context(&transport),
+            &args(&[], Some(chrono::Duration::days(90))),
+        )
+        .await
+        .unwrap();
+
+        assert!(declined_paths(&transport).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_decline_reports_failures() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .json(
+                    Method::POST,
+                    &format!("{}/1/decline", PULL_REQUESTS),
+                    serde_json::json!({}),
+                )
+                .route(
+                    Method::POST,
+                    &format!("{}/2/decline", PULL_REQUESTS),
+                    500,
+                    "{}",
+                ),
+        );
+
+        let err = pr_decline(&context(&transport), &args(&[1, 2], None))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "1 of 2 pull requests could not be declined"
+        );
+        assert_eq!(declined_paths(&transport).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_panicked_task_counts_as_failure() {
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut pr_ids = HashMap::new();
+        let ok = tasks.spawn(async {
+            DeclineResult {
+                id: 1,
+                declined: true,
+                error: None,
+            }
+        });
+        pr_ids.insert(ok.id(), 1);
+        let panicked = tasks.spawn(async { panic!("boom") });
+        pr_ids.insert(panicked.id(), 2);
+
+        let results = collect_results(tasks, &pr_ids).await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].declined);
+        assert_eq!(results[1].id, 2);
+        assert!(!results[1].declined);
+        assert!(
+            results[1].error.as_deref().unwrap().contains("panicked"),
+            "{:?}",
+            results[1].error
+        );
+    }
+
+    #[test]
+    fn test_confirm_decline_needs_a_terminal() {
+        let err = confirm_decline(2, false).unwrap_err();
+        assert!(err.to_string().contains("--yes"), "{}", err);
+    }
+}
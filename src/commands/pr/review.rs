@@ -1,7 +1,10 @@
 use crate::context::AppContext;
+use crate::display::ui;
 use anyhow::{Context, Result};
 use clap::Args;
 use dialoguer::{Input, Select};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
 
 #[derive(Args, Debug)]
 pub struct ReviewArgs {
@@ -20,57 +23,168 @@ pub struct ReviewArgs {
     #[arg(short, long)]
     pub comment: bool,
 
-    /// The body of the review or comment (required for --comment)
+    /// The body of the review or comment (required for --comment; posted as a
+    /// comment alongside --approve or --request-changes)
     #[arg(short, long)]
     pub body: Option<String>,
+
+    /// Find the PR for this branch instead of the current one
+    #[arg(long)]
+    pub branch: Option<String>,
+
+    /// Post a batch of inline comments, a summary and a verdict from a JSON file
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["approve", "request_changes", "comment", "body"]
+    )]
+    pub from_file: Option<PathBuf>,
 }
 
-pub async fn pr_review(ctx: &AppContext, args: &ReviewArgs) -> Result<()> {
-    let workspace = ctx
-        .workspace
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No workspace found"))?;
-    let repo = ctx
-        .repo
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No repository found"))?;
-
-    // Determine PR ID
-    let pr_id = match args.id {
-        Some(id) => id,
-        None => {
-            // Try to deduce from current branch
-            let branch = crate::git::get_current_branch()?;
-            let pr = ctx
-                .client
-                .find_pull_request_by_branch(workspace, repo, &branch)
-                .await?
-                .context("No open pull request found for current branch")?;
-            pr.id
+/// A review prepared offline, as read by `--from-file`
+///
+/// ```json
+/// {
+///   "comments": [{"file": "src/main.rs", "line": 42, "body": "Off by one?"}],
+///   "summary": "A few nits, otherwise good",
+///   "verdict": "approve"
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReviewFile {
+    #[serde(default)]
+    pub comments: Vec<InlineNote>,
+    /// Posted as a general comment after the inline ones
+    pub summary: Option<String>,
+    pub verdict: Option<Verdict>,
+}
+
+/// A comment on one line of the new version of a file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InlineNote {
+    pub file: String,
+    pub line: u32,
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+    Approve,
+    RequestChanges,
+}
+
+impl ReviewFile {
+    /// Check the review is complete enough to post, reporting every problem at once
+    fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        for (i, note) in self.comments.iter().enumerate() {
+            if note.file.trim().is_empty() {
+                problems.push(format!("comments[{}]: 'file' is empty", i));
+            }
+            if note.line == 0 {
+                problems.push(format!("comments[{}]: 'line' must be 1 or greater", i));
+            }
+            if note.body.trim().is_empty() {
+                problems.push(format!("comments[{}]: 'body' is empty", i));
+            }
+        }
+        if self.summary.as_deref().is_some_and(|s| s.trim().is_empty()) {
+            problems.push("'summary' is empty".to_string());
+        }
+        if self.comments.is_empty() && self.summary.is_none() && self.verdict.is_none() {
+            problems.push("nothing to post: add comments, a summary or a verdict".to_string());
         }
-    };
 
-    // Check if flags are provided
-    if args.approve || args.request_changes || args.comment {
-        if args.approve {
-            ctx.client.approve_pr(workspace, repo, pr_id).await?;
-            println!("Approved pull request #{}", pr_id);
+        if !problems.is_empty() {
+            anyhow::bail!("Invalid review file:\n  {}", problems.join("\n  "));
         }
+        Ok(())
+    }
+}
+
+/// Read and validate a review file
+fn load_review_file(path: &Path) -> Result<ReviewFile> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read review file {}", path.display()))?;
+    let review: ReviewFile = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse review file {}", path.display()))?;
+    review.validate()?;
+    Ok(review)
+}
+
+/// One API call made by a flag-driven review
+#[derive(Debug, PartialEq)]
+enum ReviewAction<'a> {
+    Comment(&'a str),
+    Approve,
+    RequestChanges,
+}
 
-        if args.request_changes {
-            ctx.client.request_changes(workspace, repo, pr_id).await?;
-            println!("Requested changes on pull request #{}", pr_id);
+/// The calls the review flags ask for, in the order they're made
+///
+/// A body given with a verdict is posted as a comment first, as the summary
+/// of a `--from-file` review is. Returns an empty list when no flag is set.
+fn planned_actions(args: &ReviewArgs) -> Result<Vec<ReviewAction<'_>>> {
+    let mut actions = Vec::new();
+
+    if args.comment || args.approve || args.request_changes {
+        match args.body.as_deref() {
+            Some(body) => actions.push(ReviewAction::Comment(body)),
+            None if args.comment => {
+                anyhow::bail!("Comment body is required when using --comment")
+            }
+            None => {}
         }
+    }
+    if args.approve {
+        actions.push(ReviewAction::Approve);
+    }
+    if args.request_changes {
+        actions.push(ReviewAction::RequestChanges);
+    }
+    Ok(actions)
+}
+
+pub async fn pr_review(ctx: &AppContext, args: &ReviewArgs) -> Result<()> {
+    let (workspace, repo) = ctx.require_repo()?;
+
+    let pr_id = super::resolve_pr_id(
+        args.id,
+        args.branch.as_deref(),
+        &ctx.client,
+        workspace,
+        repo,
+    )
+    .await?;
+
+    if let Some(path) = &args.from_file {
+        let review = load_review_file(path)?;
+        return post_review_batch(ctx, workspace, repo, pr_id, &review).await;
+    }
 
-        if args.comment {
-            let body = args
-                .body
-                .clone()
-                .context("Comment body is required when using --comment")?;
-            ctx.client
-                .post_pr_comment(workspace, repo, pr_id, &body)
-                .await?;
-            println!("Commented on pull request #{}", pr_id);
+    let actions = planned_actions(args)?;
+    if !actions.is_empty() {
+        for action in actions {
+            match action {
+                ReviewAction::Comment(body) => {
+                    ctx.client
+                        .post_pr_comment(workspace, repo, pr_id, body)
+                        .await?;
+                    println!("Commented on pull request #{}", pr_id);
+                }
+                ReviewAction::Approve => {
+                    ctx.client.approve_pr(workspace, repo, pr_id).await?;
+                    println!("Approved pull request #{}", pr_id);
+                }
+                ReviewAction::RequestChanges => {
+                    ctx.client.request_changes(workspace, repo, pr_id).await?;
+                    println!("Requested changes on pull request #{}", pr_id);
+                }
+            }
         }
     } else {
         // Interactive mode
@@ -106,3 +220,448 @@ pub async fn pr_review(ctx: &AppContext, args: &ReviewArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Outcome of posting one inline comment, for `--json`
+#[derive(serde::Serialize)]
+struct NoteResult<'a> {
+    file: &'a str,
+    line: u32,
+    comment_id: Option<u32>,
+    error: Option<String>,
+}
+
+/// Outcome of posting the summary comment, for `--json`
+#[derive(serde::Serialize)]
+struct SummaryResult {
+    comment_id: Option<u32>,
+    error: Option<String>,
+}
+
+/// Outcome of applying the verdict, for `--json`
+#[derive(serde::Serialize)]
+struct VerdictResult {
+    verdict: &'static str,
+    applied: bool,
+    /// Why it failed, or why it wasn't attempted
+    error: Option<String>,
+}
+
+/// Everything a `--from-file` review posted, for `--json`
+#[derive(serde::Serialize)]
+struct BatchResults<'a> {
+    comments: Vec<NoteResult<'a>>,
+    summary: Option<SummaryResult>,
+    verdict: Option<VerdictResult>,
+}
+
+impl BatchResults<'_> {
+    /// Steps that failed or were skipped
+    fn failures(&self) -> usize {
+        self.comments.iter().filter(|r| r.error.is_some()).count()
+            + self.summary.iter().filter(|r| r.error.is_some()).count()
+            + self.verdict.iter().filter(|r| !r.applied).count()
+    }
+}
+
+/// Post every inline comment, then the summary and verdict
+///
+/// A step that fails is reported and the rest carry on, except that the
+/// verdict is held back when any inline comment failed, so a PR isn't
+/// approved without the comments meant to go with it. The command still
+/// fails at the end so scripts notice.
+async fn post_review_batch(
+    ctx: &AppContext,
+    workspace: &str,
+    repo: &str,
+    pr_id: u32,
+    review: &ReviewFile,
+) -> Result<()> {
+    let mut results = BatchResults {
+        comments: Vec::with_capacity(review.comments.len()),
+        summary: None,
+        verdict: None,
+    };
+
+    for note in &review.comments {
+        let location = format!("{}:{}", note.file, note.line);
+        let result = ctx
+            .client
+            .post_pr_inline_comment(workspace, repo, pr_id, &note.file, note.line, &note.body)
+            .await;
+
+        let (comment_id, error) = match result {
+            Ok(comment) => {
+                if !ctx.json {
+                    ui::success(&format!("Commented on {}", location));
+                }
+                (Some(comment.id), None)
+            }
+            Err(e) if crate::error::is_explained(&e) => return Err(e),
+            Err(e) => {
+                if !ctx.json {
+                    ui::error(&format!("Failed to comment on {}: {:#}", location, e));
+                }
+                (None, Some(format!("{:#}", e)))
+            }
+        };
+        results.comments.push(NoteResult {
+            file: &note.file,
+            line: note.line,
+            comment_id,
+            error,
+        });
+    }
+
+    if let Some(summary) = &review.summary {
+        let result = ctx
+            .client
+            .post_pr_comment(workspace, repo, pr_id, summary)
+            .await;
+        results.summary = Some(match result {
+            Ok(comment) => {
+                if !ctx.json {
+                    println!("Commented on pull request #{}", pr_id);
+                }
+                SummaryResult {
+                    comment_id: Some(comment.id),
+                    error: None,
+                }
+            }
+            Err(e) if crate::error::is_explained(&e) => return Err(e),
+            Err(e) => {
+                if !ctx.json {
+                    ui::error(&format!("Failed to post the summary: {:#}", e));
+                }
+                SummaryResult {
+                    comment_id: None,
+                    error: Some(format!("{:#}", e)),
+                }
+            }
+        });
+    }
+
+    if let Some(verdict) = &review.verdict {
+        let (name, done) = match verdict {
+            Verdict::Approve => ("approve", "Approved"),
+            Verdict::RequestChanges => ("request-changes", "Requested changes on"),
+        };
+        let failed_notes = results
+            .comments
+            .iter()
+            .filter(|r| r.error.is_some())
+            .count();
+
+        let error = if failed_notes > 0 {
+            let reason = format!(
+                "not applied because {} inline comment(s) failed to post; \
+                 fix them and run the review again",
+                failed_notes
+            );
+            if !ctx.json {
+                ui::warning(&format!("Verdict '{}' {}", name, reason));
+            }
+            Some(reason)
+        } else {
+            let result = match verdict {
+                Verdict::Approve => ctx.client.approve_pr(workspace, repo, pr_id).await,
+                Verdict::RequestChanges => ctx.client.request_changes(workspace, repo, pr_id).await,
+            };
+            match result {
+                Ok(()) => {
+                    if !ctx.json {
+                        println!("{} pull request #{}", done, pr_id);
+                    }
+                    None
+                }
+                Err(e) if crate::error::is_explained(&e) => return Err(e),
+                Err(e) => {
+                    if !ctx.json {
+                        ui::error(&format!("Failed to {}: {:#}", name, e));
+                    }
+                    Some(format!("{:#}", e))
+                }
+            }
+        };
+        results.verdict = Some(VerdictResult {
+            verdict: name,
+            applied: error.is_none(),
+            error,
+        });
+    }
+
+    if ctx.json {
+        ui::print_json(&results)?;
+    }
+
+    let failed = results.failures();
+    if failed > 0 {
+        anyhow::bail!("{} review step(s) failed or were skipped", failed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::transport::mock::MockTransport;
+    use reqwest::Method;
+    use std::sync::Arc;
+
+    const PR: &str = "/2.0/repositories/ws/repo/pullrequests/1";
+
+    fn context(transport: &Arc<MockTransport>, json: bool) -> AppContext {
+        let client = crate::api::client::BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            Some(("user".to_string(), "pass".to_string())),
+            Default::default(),
+        )
+        .unwrap()
+        .with_transport(transport.clone());
+
+        AppContext {
+            json,
+            ..AppContext::for_test(client)
+        }
+    }
+
+    fn comment(id: u32) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "content": {"raw": "ok"},
+            "user": {"display_name": "Me", "uuid": "{me}"},
+            "created_on": "2024-01-01T00:00:00+00:00"
+        })
+    }
+
+    /// The path of each request, without the host
+    fn paths(transport: &MockTransport) -> Vec<String> {
+        transport
+            .requests()
+            .iter()
+            .map(|r| {
+                r.url
+                    .trim_start_matches("https://api.bitbucket.org")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_batch_holds_back_verdict_when_a_comment_fails() {
+        // Inline comments and the summary both go to /comments, which rejects them
+        let transport = Arc::new(
+            MockTransport::new()
+                .route(Method::POST, &format!("{}/comments", PR), 400, "{}")
+                .json(
+                    Method::POST,
+                    &format!("{}/approve", PR),
+                    serde_json::json!({}),
+                ),
+        );
+        let review = parse(
+            r#"{"comments": [{"file": "a.rs", "line": 1, "body": "nit"}],
+                "summary": "done", "verdict": "approve"}"#,
+        )
+        .unwrap();
+
+        let err = post_review_batch(&context(&transport, true), "ws", "repo", 1, &review)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "3 review step(s) failed or were skipped");
+        // The summary is still attempted, the approval isn't
+        assert_eq!(
+            paths(&transport),
+            [format!("{}/comments", PR), format!("{}/comments", PR)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_reports_summary_and_verdict_failures() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .json(Method::POST, &format!("{}/comments", PR), comment(5))
+                .route(Method::POST, &format!("{}/request-changes", PR), 500, "{}"),
+        );
+        let review = parse(
+            r#"{"comments": [{"file": "a.rs", "line": 1, "body": "nit"}],
+                "summary": "done", "verdict": "request-changes"}"#,
+        )
+        .unwrap();
+
+        let err = post_review_batch(&context(&transport, false), "ws", "repo", 1, &review)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "1 review step(s) failed or were skipped");
+        assert_eq!(
+            paths(&transport),
+            [
+                format!("{}/comments", PR),
+                format!("{}/comments", PR),
+                format!("{}/request-changes", PR),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_explain_stops_at_first_request() {
+        let transport = Arc::new(MockTransport::new());
+        let mut ctx = context(&transport, true);
+        ctx.client = ctx.client.with_explain(true);
+        let review = parse(
+            r#"{"comments": [{"file": "a.rs", "line": 1, "body": "nit"},
+                             {"file": "b.rs", "line": 2, "body": "nit"}],
+                "summary": "done", "verdict": "approve"}"#,
+        )
+        .unwrap();
+
+        let err = post_review_batch(&ctx, "ws", "repo", 1, &review)
+            .await
+            .unwrap_err();
+
+        assert!(crate::error::is_explained(&err));
+        assert!(transport.requests().is_empty());
+    }
+
+    fn parse(json: &str) -> Result<ReviewFile> {
+        let review: ReviewFile = serde_json::from_str(json)?;
+        review.validate()?;
+        Ok(review)
+    }
+
+    fn review_args(
+        approve: bool,
+        request_changes: bool,
+        comment: bool,
+        body: Option<&str>,
+    ) -> ReviewArgs {
+        ReviewArgs {
+            id: Some(1),
+            approve,
+            request_changes,
+            comment,
+            body: body.map(str::to_string),
+            branch: None,
+            from_file: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_approve_with_body_comments_then_approves() {
+        let transport = Arc::new(
+            MockTransport::new()
+                .json(Method::POST, &format!("{}/comments", PR), comment(1))
+                .json(
+                    Method::POST,
+                    &format!("{}/approve", PR),
+                    serde_json::json!({}),
+                ),
+        );
+        let args = review_args(true, false, false, Some("LGTM, minor nits"));
+
+        pr_review(&context(&transport, false), &args).await.unwrap();
+
+        assert_eq!(
+            paths(&transport),
+            [format!("{}/comments", PR), format!("{}/approve", PR)]
+        );
+        let requests = transport.requests();
+        let body: serde_json::Value =
+            serde_json::from_slice(requests[0].body.as_ref().unwrap()).unwrap();
+        assert_eq!(body["content"]["raw"], "LGTM, minor nits");
+    }
+
+    #[test]
+    fn test_planned_actions() {
+        use ReviewAction::*;
+
+        let check = |args: ReviewArgs, expected: &[ReviewAction]| {
+            assert_eq!(planned_actions(&args).unwrap(), expected, "{:?}", args);
+        };
+
+        check(review_args(true, false, false, None), &[Approve]);
+        check(review_args(false, true, false, None), &[RequestChanges]);
+        check(
+            review_args(false, false, true, Some("hi")),
+            &[Comment("hi")],
+        );
+        check(
+            review_args(true, false, false, Some("LGTM, minor nits")),
+            &[Comment("LGTM, minor nits"), Approve],
+        );
+        check(
+            review_args(false, true, false, Some("Needs tests")),
+            &[Comment("Needs tests"), RequestChanges],
+        );
+        // --comment alongside a verdict still posts the body only once
+        check(
+            review_args(true, false, true, Some("LGTM")),
+            &[Comment("LGTM"), Approve],
+        );
+        // No flags: interactive mode
+        check(review_args(false, false, false, None), &[]);
+    }
+
+    #[test]
+    fn test_planned_actions_comment_needs_body() {
+        let err = planned_actions(&review_args(true, false, true, None)).unwrap_err();
+        assert!(err.to_string().contains("--comment"), "{}", err);
+    }
+
+    #[test]
+    fn test_review_file_full() {
+        let review = parse(
+            r#"{
+                "comments": [
+                    {"file": "src/main.rs", "line": 42, "body": "Off by one?"},
+                    {"file": "README.md", "line": 1, "body": "Typo"}
+                ],
+                "summary": "A few nits",
+                "verdict": "request-changes"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(review.comments.len(), 2);
+        assert_eq!(review.comments[0].file, "src/main.rs");
+        assert_eq!(review.comments[0].line, 42);
+        assert_eq!(review.summary.as_deref(), Some("A few nits"));
+        assert_eq!(review.verdict, Some(Verdict::RequestChanges));
+    }
+
+    #[test]
+    fn test_review_file_verdict_only() {
+        let review = parse(r#"{"verdict": "approve"}"#).unwrap();
+        assert!(review.comments.is_empty());
+        assert_eq!(review.verdict, Some(Verdict::Approve));
+    }
+
+    #[test]
+    fn test_review_file_reports_every_problem() {
+        let err = parse(
+            r#"{"comments": [
+                {"file": "", "line": 0, "body": "x"},
+                {"file": "a.rs", "line": 3, "body": "  "}
+            ]}"#,
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("comments[0]: 'file' is empty"), "{}", err);
+        assert!(
+            err.contains("comments[0]: 'line' must be 1 or greater"),
+            "{}",
+            err
+        );
+        assert!(err.contains("comments[1]: 'body' is empty"), "{}", err);
+    }
+
+    #[test]
+    fn test_review_file_rejects_empty_and_unknown() {
+        assert!(parse("{}").is_err());
+        assert!(parse(r#"{"verdict": "lgtm"}"#).is_err());
+        assert!(parse(r#"{"comments": [{"path": "a.rs", "line": 1, "body": "x"}]}"#).is_err());
+    }
+}
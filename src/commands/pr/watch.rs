@@ -0,0 +1,202 @@
+//! `bb pr list --watch`: a pull request table that refreshes in place
+use crate::api::models::PullRequest;
+use crate::display::{pr as pr_display, ui};
+use anyhow::{Context, Result};
+use crossterm::{cursor, execute, terminal};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// How often the terminal size is checked for a redraw between polls
+const RESIZE_CHECK: Duration = Duration::from_millis(500);
+
+/// What a poll remembers about a PR to spot changes in the next one
+#[derive(Debug, PartialEq)]
+struct PrSnapshot {
+    state: String,
+    draft: bool,
+    approvals: usize,
+}
+
+impl PrSnapshot {
+    fn of(pr: &PullRequest) -> Self {
+        Self {
+            state: pr.state.clone(),
+            draft: pr.is_draft(),
+            approvals: pr.approvers().len(),
+        }
+    }
+}
+
+/// Poll `fetch` every `interval` and redraw the PR table until Ctrl-C
+///
+/// Rows whose state, draft flag or approval count changed since the previous
+/// poll, and PRs that newly appeared, are highlighted. A failed poll keeps
+/// the last table on screen and shows the error; the next poll retries.
+pub async fn watch_pr_list<F, Fut>(
+    title: &str,
+    mut fetch: F,
+    columns: &[pr_display::PrColumn],
+    interval: Duration,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Vec<PullRequest>>>,
+{
+    let mut previous: Option<HashMap<u32, PrSnapshot>> = None;
+    let mut prs: Vec<PullRequest> = Vec::new();
+    let mut changes = Changes::default();
+    let mut status = String::new();
+    let mut size = terminal::size().ok();
+    let mut next_poll = Instant::now();
+
+    // One listener for the whole watch, so Ctrl-C during a fetch also ends cleanly
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        if Instant::now() >= next_poll {
+            next_poll = Instant::now() + interval;
+            let fetched = tokio::select! {
+                _ = &mut ctrl_c => break,
+                fetched = fetch() => fetched,
+            };
+            match fetched {
+                Ok(fetched) => {
+                    let snapshots: HashMap<u32, PrSnapshot> = fetched
+                        .iter()
+                        .map(|pr| (pr.id, PrSnapshot::of(pr)))
+                        .collect();
+                    changes = previous
+                        .as_ref()
+                        .map(|previous| diff_snapshots(previous, &snapshots))
+                        .unwrap_or_default();
+                    previous = Some(snapshots);
+                    prs = fetched;
+                    status = format!("Updated {}", chrono::Local::now().format("%H:%M:%S"));
+                }
+                Err(e) => {
+                    status = format!(
+                        "Refresh failed at {}: {:#}",
+                        chrono::Local::now().format("%H:%M:%S"),
+                        e
+                    );
+                }
+            }
+            draw(title, &prs, columns, &changes, &status, interval)?;
+        }
+
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            _ = tokio::time::sleep(RESIZE_CHECK.min(next_poll.saturating_duration_since(Instant::now()))) => {}
+        }
+
+        // Tables are sized to the terminal, so a resize needs a redraw
+        let new_size = terminal::size().ok();
+        if new_size != size {
+            size = new_size;
+            draw(title, &prs, columns, &changes, &status, interval)?;
+        }
+    }
+
+    println!();
+    Ok(())
+}
+
+/// Differences between two polls
+#[derive(Debug, Default, PartialEq)]
+struct Changes {
+    /// PRs that are new or whose snapshot changed
+    changed: HashSet<u32>,
+    /// PRs no longer listed (e.g. merged or declined when listing open PRs)
+    gone: Vec<u32>,
+}
+
+fn diff_snapshots(
+    previous: &HashMap<u32, PrSnapshot>,
+    current: &HashMap<u32, PrSnapshot>,
+) -> Changes {
+    let changed = current
+        .iter()
+        .filter(|(id, snapshot)| previous.get(id) != Some(snapshot))
+        .map(|(id, _)| *id)
+        .collect();
+    let mut gone: Vec<u32> = previous
+        .keys()
+        .filter(|id| !current.contains_key(id))
+        .copied()
+        .collect();
+    gone.sort_unstable();
+    Changes { changed, gone }
+}
+
+fn draw(
+    title: &str,
+    prs: &[PullRequest],
+    columns: &[pr_display::PrColumn],
+    changes: &Changes,
+    status: &str,
+    interval: Duration,
+) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    execute!(
+        stdout,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0)
+    )
+    .context("Failed to redraw the terminal")?;
+
+    println!(
+        "Every {}s: {}  ({}; Ctrl-C to quit)\n",
+        interval.as_secs(),
+        title,
+        status
+    );
+    if prs.is_empty() {
+        ui::info("No pull requests");
+    } else {
+        println!(
+            "{}",
+            pr_display::format_pr_list_highlighted(prs, columns, false, &changes.changed)
+        );
+    }
+    if !changes.gone.is_empty() {
+        let ids: Vec<String> = changes.gone.iter().map(|id| format!("#{}", id)).collect();
+        println!("\nNo longer listed: {}", ids.join(", "));
+    }
+    stdout.flush().context("Failed to redraw the terminal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(state: &str, approvals: usize) -> PrSnapshot {
+        PrSnapshot {
+            state: state.to_string(),
+            draft: false,
+            approvals,
+        }
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let previous = HashMap::from([
+            (1, snapshot("OPEN", 0)),
+            (2, snapshot("OPEN", 1)),
+            (3, snapshot("OPEN", 0)),
+        ]);
+        let current = HashMap::from([
+            (1, snapshot("OPEN", 0)),
+            (2, snapshot("OPEN", 2)),
+            (4, snapshot("OPEN", 0)),
+        ]);
+
+        let changes = diff_snapshots(&previous, &current);
+        assert_eq!(changes.changed, HashSet::from([2, 4]));
+        assert_eq!(changes.gone, vec![3]);
+
+        assert_eq!(diff_snapshots(&current, &current), Changes::default());
+    }
+}
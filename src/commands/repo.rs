@@ -1,5 +1,8 @@
+use super::examples;
+use crate::api::models::Repository;
 use crate::context::AppContext;
 use crate::display::ui;
+use crate::utils::cache;
 use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
 
@@ -12,36 +15,356 @@ pub struct RepoArgs {
 #[derive(Subcommand)]
 pub enum RepoCommands {
     /// List repositories in the workspace
+    #[command(after_help = examples::REPO_LIST)]
     List {
         /// Workspace to list repositories from (defaults to configured workspace)
         #[arg(long, short)]
         workspace: Option<String>,
 
-        /// Limit the number of repositories to return (default: 100)
-        #[arg(long, default_value = "100")]
-        limit: u32,
+        /// Limit the number of repositories to return [default: repo.default_limit, else 100]
+        #[arg(long)]
+        limit: Option<u32>,
+
+        /// Repositories requested per API call (max 100; fewer round-trips when larger)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        page_size: Option<u32>,
+
+        /// Print only the number of repositories
+        #[arg(long)]
+        count: bool,
+
+        /// Only repositories you are a member of (same as --role member)
+        #[arg(long, conflicts_with = "role")]
+        mine: bool,
+
+        /// Only repositories where you have this role
+        #[arg(long, value_parser = ["member", "contributor", "admin", "owner"])]
+        role: Option<String>,
+
+        /// Skip the cached result and fetch the list live
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Show the repository's default branch, or change it (admin only)
+    #[command(after_help = examples::REPO_DEFAULT_BRANCH)]
+    DefaultBranch {
+        /// Branch to make the default; omit to print the current one
+        name: Option<String>,
+
+        /// Change it without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Open the repository in the browser
+    #[command(after_help = examples::REPO_BROWSE)]
+    Browse {
+        /// Repository as WORKSPACE/REPO, or REPO in the current workspace (defaults to the current one)
+        repository: Option<String>,
+
+        /// Open the Pipelines page
+        #[arg(long, conflicts_with_all = ["branches", "settings"])]
+        pipelines: bool,
+
+        /// Open the branches page
+        #[arg(long, conflicts_with = "settings")]
+        branches: bool,
+
+        /// Open the repository settings
+        #[arg(long)]
+        settings: bool,
     },
 }
 
+const REPO_CACHE_NAMESPACE: &str = "repos";
+
 pub async fn handle(ctx: &AppContext, args: RepoArgs) -> Result<()> {
     match args.command {
-        RepoCommands::List { workspace, limit } => {
+        RepoCommands::List {
+            workspace,
+            limit,
+            page_size,
+            count,
+            mine,
+            role,
+            refresh,
+        } => {
             let ws = workspace
                 .or_else(|| ctx.workspace.clone())
                 .context("No workspace configured. Please set a default workspace with 'bb config set workspace <NAME>' or provide --workspace")?;
 
+            let limit = limit
+                .or(ctx.repo_default_limit)
+                .unwrap_or(crate::constants::DEFAULT_REPO_LIST_LIMIT);
+
             let client = ctx.client.clone(); // Use client from context which is already initialized with auth
 
-            ui::info(&format!("Fetching repositories for workspace '{}'...", ws));
+            let role = if mine {
+                Some("member".to_string())
+            } else {
+                role
+            };
 
-            let repos = client.list_repositories(&ws, Some(limit)).await?;
+            if count {
+                println!("{}", client.count_repositories(&ws, role.as_deref()).await?);
+                return Ok(());
+            }
+
+            let cache_key = repo_list_cache_key(&ctx.profile_name, &ws, role.as_deref(), limit);
+            let ttl = std::time::Duration::from_secs(crate::constants::REPO_CACHE_TTL_SECS);
+            // --explain must show the request even when the cache would answer
+            let cached = if refresh || ctx.client.explains() {
+                None
+            } else {
+                cache::read::<Vec<Repository>>(REPO_CACHE_NAMESPACE, &cache_key, ttl)
+            };
+
+            let repos = match cached {
+                Some(hit) => {
+                    crate::utils::debug::log(&format!(
+                        "Using cached repositories for workspace '{}' (cached {} ago, --refresh to update)",
+                        ws,
+                        cache::format_age(hit.age)
+                    ));
+                    hit.data
+                }
+                None => {
+                    if let Some(size) = page_size.filter(|s| *s > crate::constants::MAX_PAGE_LEN) {
+                        ui::warning(&format!(
+                            "--page-size {} exceeds Bitbucket's maximum; using {}",
+                            size,
+                            crate::constants::MAX_PAGE_LEN
+                        ));
+                    }
+
+                    ui::info(&format!("Fetching repositories for workspace '{}'...", ws));
+
+                    let repos = client
+                        .list_repositories(&ws, role.as_deref(), Some(limit), page_size)
+                        .await?;
+                    if let Err(e) = cache::write(REPO_CACHE_NAMESPACE, &cache_key, &repos) {
+                        crate::utils::debug::log(&format!("Failed to cache repositories: {}", e));
+                    }
+                    repos
+                }
+            };
 
             if ctx.json {
-                ui::print_json(&repos)?;
+                ui::print_json_list(&repos)?;
             } else {
                 crate::display::repo::print_repo_list(&repos);
             }
         }
+        RepoCommands::DefaultBranch { name, yes } => {
+            let (workspace, repo) = ctx.require_repo()?;
+            let repository = ctx.client.get_repository(workspace, repo).await?;
+            let current = repository.mainbranch.as_ref().map(|b| b.name.as_str());
+
+            let Some(name) = name else {
+                let current = current.context("Repository has no default branch")?;
+                if ctx.json {
+                    ui::print_json(&serde_json::json!({ "name": current }))?;
+                } else {
+                    println!("{}", current);
+                }
+                return Ok(());
+            };
+
+            if current == Some(name.as_str()) {
+                ui::info(&format!("'{}' is already the default branch", name));
+                return Ok(());
+            }
+
+            if !yes && !confirm_default_branch_change(workspace, repo, current, &name)? {
+                ui::info("Default branch left unchanged");
+                return Ok(());
+            }
+
+            let updated = ctx
+                .client
+                .set_default_branch(workspace, repo, &name)
+                .await?;
+
+            if ctx.json {
+                ui::print_json(&updated)?;
+            } else {
+                ui::success(&format!(
+                    "Default branch of {}/{} is now '{}'",
+                    workspace, repo, name
+                ));
+            }
+        }
+        RepoCommands::Browse {
+            repository,
+            pipelines,
+            branches,
+            settings,
+        } => {
+            let (workspace, repo) = match repository.as_deref().map(|r| (r, r.split_once('/'))) {
+                Some((_, Some((workspace, repo)))) => (workspace, repo),
+                Some((repo, None)) => (
+                    ctx.workspace
+                        .as_deref()
+                        .context("No workspace found. Pass the repository as WORKSPACE/REPO")?,
+                    repo,
+                ),
+                None => ctx.require_repo()?,
+            };
+
+            let page = if pipelines {
+                Some("pipelines")
+            } else if branches {
+                Some("branches")
+            } else if settings {
+                Some("admin")
+            } else {
+                None
+            };
+
+            let html_url = repo_html_url(ctx, workspace, repo).await?;
+            if crate::utils::browser::open_url(&repo_web_url(&html_url, page)) {
+                ui::success(&format!("Opened {}/{} in browser", workspace, repo));
+            }
+        }
     }
     Ok(())
 }
+
+/// A repository's web page
+///
+/// Server instances have their own host, so the URL the API reports is
+/// preferred; if it can't be fetched, the bitbucket.org URL is used.
+async fn repo_html_url(ctx: &AppContext, workspace: &str, repo: &str) -> Result<String> {
+    let fallback = || format!("https://bitbucket.org/{}/{}", workspace, repo);
+    match ctx.client.get_repository(workspace, repo).await {
+        Ok(repository) => Ok(repository
+            .links
+            .map(|links| links.html.href)
+            .unwrap_or_else(fallback)),
+        Err(e) if crate::error::is_explained(&e) => Err(e),
+        Err(e) => {
+            crate::utils::debug::log(&format!("Failed to get repository links: {:#}", e));
+            Ok(fallback())
+        }
+    }
+}
+
+/// Web URL of a repository page, or of one of its tabs (`pipelines`, `branches`, `admin`)
+fn repo_web_url(html_url: &str, page: Option<&str>) -> String {
+    let html_url = html_url.trim_end_matches('/');
+    match page {
+        Some(page) => format!("{}/{}", html_url, page),
+        None => html_url.to_string(),
+    }
+}
+
+/// Ask before changing the default branch; refuses when there's no terminal to ask on
+fn confirm_default_branch_change(
+    workspace: &str,
+    repo: &str,
+    current: Option<&str>,
+    name: &str,
+) -> Result<bool> {
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("Refusing to change the default branch without confirmation; pass --yes");
+    }
+
+    let prompt = format!(
+        "Change the default branch of {}/{} from '{}' to '{}'?",
+        workspace,
+        repo,
+        current.unwrap_or("(none)"),
+        name
+    );
+    Ok(dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?)
+}
+
+/// Cache key for a `repo list` result
+///
+/// Different profiles (accounts) and filters give different lists, so each
+/// gets its own entry.
+fn repo_list_cache_key(profile: &str, workspace: &str, role: Option<&str>, limit: u32) -> String {
+    format!(
+        "{}__{}__role-{}__limit-{}",
+        profile,
+        workspace,
+        role.unwrap_or("any"),
+        limit
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_list_cache_key_is_per_profile() {
+        let work = repo_list_cache_key("work", "acme", None, 25);
+        assert_ne!(work, repo_list_cache_key("personal", "acme", None, 25));
+        assert_ne!(
+            work,
+            repo_list_cache_key("work", "acme", Some("member"), 25)
+        );
+        assert_eq!(work, repo_list_cache_key("work", "acme", None, 25));
+    }
+
+    #[tokio::test]
+    async fn test_repo_html_url_falls_back_when_lookup_fails() {
+        use crate::api::transport::mock::MockTransport;
+
+        let transport = std::sync::Arc::new(
+            MockTransport::new()
+                .json(
+                    reqwest::Method::GET,
+                    "/2.0/repositories/acme/widgets",
+                    serde_json::json!({
+                        "name": "widgets",
+                        "full_name": "acme/widgets",
+                        "uuid": "{w}",
+                        "links": {"html": {"href": "https://git.acme.test/acme/widgets"}}
+                    }),
+                )
+                .route(
+                    reqwest::Method::GET,
+                    "/2.0/repositories/acme/gone",
+                    500,
+                    "oops",
+                ),
+        );
+        let client = crate::api::client::BitbucketClient::new(
+            "https://api.bitbucket.org/2.0".to_string(),
+            None,
+            Default::default(),
+        )
+        .unwrap()
+        .with_transport(transport);
+        let ctx = AppContext::for_test(client);
+
+        assert_eq!(
+            repo_html_url(&ctx, "acme", "widgets").await.unwrap(),
+            "https://git.acme.test/acme/widgets"
+        );
+        assert_eq!(
+            repo_html_url(&ctx, "acme", "gone").await.unwrap(),
+            "https://bitbucket.org/acme/gone"
+        );
+    }
+
+    #[test]
+    fn test_repo_web_url() {
+        let html = "https://bitbucket.org/acme/widgets";
+        assert_eq!(repo_web_url(html, None), html);
+        assert_eq!(
+            repo_web_url("https://bitbucket.org/acme/widgets/", Some("pipelines")),
+            "https://bitbucket.org/acme/widgets/pipelines"
+        );
+        assert_eq!(
+            repo_web_url(html, Some("admin")),
+            "https://bitbucket.org/acme/widgets/admin"
+        );
+    }
+}
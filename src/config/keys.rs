@@ -0,0 +1,255 @@
+//! The configuration keys `bb config set` accepts, and what values they take
+//!
+//! Keys are checked against this registry so a typo doesn't silently write a
+//! setting that's never read. `--force` skips the check for keys added by
+//! newer versions.
+use anyhow::Result;
+
+/// What a key's value must look like
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueKind {
+    Text,
+    Bool,
+    /// A positive whole number
+    Count,
+    /// A whole number, zero included
+    Number,
+    /// An absolute http(s) URL
+    Url,
+    OneOf(&'static [&'static str]),
+}
+
+/// Which file a key is written to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Global,
+    Local,
+}
+
+/// Keys read from the global config; `*` stands for any single segment
+const GLOBAL_KEYS: &[(&str, ValueKind)] = &[
+    ("user", ValueKind::Text),
+    ("credential_store", ValueKind::OneOf(&["keyring", "file"])),
+    ("json_envelope", ValueKind::Bool),
+    ("hyperlinks", ValueKind::Bool),
+    ("jira_url", ValueKind::Url),
+    ("pr.default_limit", ValueKind::Count),
+    ("repo.default_limit", ValueKind::Count),
+    ("profile.*.workspace", ValueKind::Text),
+    ("profile.*.user", ValueKind::Text),
+    ("profile.*.headers.*", ValueKind::Text),
+    ("profile.*.timeout", ValueKind::Count),
+    ("profile.*.retries", ValueKind::Number),
+];
+
+/// Keys read from a project's `.bb-cli` file
+const LOCAL_KEYS: &[(&str, ValueKind)] = &[
+    ("project.workspace", ValueKind::Text),
+    ("project.repository", ValueKind::Text),
+    ("project.remote", ValueKind::Text),
+];
+
+/// Short forms `config set` expands before writing, offered as suggestions too
+const SHORT_KEYS: &[(&str, Scope)] = &[
+    ("workspace", Scope::Global),
+    ("workspace", Scope::Local),
+    ("repository", Scope::Local),
+    ("remote", Scope::Local),
+];
+
+/// Check that `key` (after short-key expansion) is known in `scope` and
+/// that `value` suits it
+pub fn validate(key: &str, value: &str, scope: Scope) -> Result<()> {
+    let known = match scope {
+        Scope::Global => GLOBAL_KEYS,
+        Scope::Local => LOCAL_KEYS,
+    };
+    let Some(kind) = known
+        .iter()
+        .find(|(pattern, _)| matches_pattern(pattern, key))
+        .map(|(_, kind)| *kind)
+    else {
+        let mut message = format!("Unknown config key '{}'", key);
+        if let Some(suggestion) = suggest(key, scope) {
+            message.push_str(&format!(". Did you mean '{}'?", suggestion));
+        }
+        anyhow::bail!("{} (pass --force to set it anyway)", message);
+    };
+
+    check_value(kind, value).map_err(|expected| {
+        anyhow::anyhow!(
+            "Invalid value '{}' for {}: expected {} (pass --force to set it anyway)",
+            value,
+            key,
+            expected
+        )
+    })
+}
+
+/// Whether a dotted key matches a registry pattern segment by segment
+fn matches_pattern(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    let key: Vec<&str> = key.split('.').collect();
+    pattern.len() == key.len()
+        && pattern
+            .iter()
+            .zip(&key)
+            .all(|(p, k)| !k.is_empty() && (*p == "*" || p == k))
+}
+
+/// The known key closest to `key`, if it's close enough to be a typo
+///
+/// Wildcard segments are filled in from `key`, so `profile.work.worksapce`
+/// suggests `profile.work.workspace`.
+fn suggest(key: &str, scope: Scope) -> Option<String> {
+    let known = match scope {
+        Scope::Global => GLOBAL_KEYS,
+        Scope::Local => LOCAL_KEYS,
+    };
+    let segments: Vec<&str> = key.split('.').collect();
+
+    let candidates = known
+        .iter()
+        .map(|(pattern, _)| {
+            pattern
+                .split('.')
+                .enumerate()
+                .map(|(i, p)| match (p, segments.get(i)) {
+                    ("*", Some(segment)) => *segment,
+                    _ => p,
+                })
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .chain(
+            SHORT_KEYS
+                .iter()
+                .filter(|(_, s)| *s == scope)
+                .map(|(short, _)| short.to_string()),
+        );
+
+    // Allow roughly one edit per four characters, and always at least two
+    let max_distance = (key.chars().count() / 4).max(2);
+    candidates
+        .map(|candidate| (edit_distance(key, &candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// `Err` describes what was expected
+fn check_value(kind: ValueKind, value: &str) -> Result<(), String> {
+    let ok = match kind {
+        ValueKind::Text => !value.trim().is_empty(),
+        ValueKind::Bool => matches!(value, "true" | "false"),
+        ValueKind::Count => value.parse::<u32>().is_ok_and(|n| n > 0),
+        ValueKind::Number => value.parse::<u32>().is_ok(),
+        ValueKind::Url => reqwest::Url::parse(value)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host()),
+        ValueKind::OneOf(allowed) => allowed.contains(&value),
+    };
+    if ok {
+        return Ok(());
+    }
+
+    Err(match kind {
+        ValueKind::Text => "a non-empty value".to_string(),
+        ValueKind::Bool => "true or false".to_string(),
+        ValueKind::Count => "a positive whole number".to_string(),
+        ValueKind::Number => "a whole number".to_string(),
+        ValueKind::Url => "an http(s) URL such as https://acme.atlassian.net".to_string(),
+        ValueKind::OneOf(allowed) => format!("one of: {}", allowed.join(", ")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("workspace", "workspace"), 0);
+        assert_eq!(edit_distance("worksapce", "workspace"), 2);
+        assert_eq!(edit_distance("user", "usr"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_suggest() {
+        assert_eq!(
+            suggest("worksapce", Scope::Global),
+            Some("workspace".to_string())
+        );
+        assert_eq!(
+            suggest("profile.work.worksapce", Scope::Global),
+            Some("profile.work.workspace".to_string())
+        );
+        assert_eq!(
+            suggest("pr.default_limt", Scope::Global),
+            Some("pr.default_limit".to_string())
+        );
+        assert_eq!(
+            suggest("repositroy", Scope::Local),
+            Some("repository".to_string())
+        );
+        assert_eq!(
+            suggest("jira", Scope::Global),
+            None,
+            "too far from jira_url to be a typo"
+        );
+        assert_eq!(suggest("completely_unrelated", Scope::Global), None);
+    }
+
+    #[test]
+    fn test_validate_keys() {
+        assert!(validate("user", "work", Scope::Global).is_ok());
+        assert!(validate("profile.work.workspace", "acme", Scope::Global).is_ok());
+        assert!(validate("profile.work.headers.X-Team", "core", Scope::Global).is_ok());
+        assert!(validate("project.remote", "upstream", Scope::Local).is_ok());
+
+        let err = validate("worksapce", "acme", Scope::Global).unwrap_err();
+        assert!(
+            err.to_string().contains("Did you mean 'workspace'?"),
+            "{}",
+            err
+        );
+        assert!(err.to_string().contains("--force"));
+
+        // Project keys aren't read from the global file
+        assert!(validate("project.workspace", "acme", Scope::Global).is_err());
+        assert!(validate("profile..workspace", "acme", Scope::Global).is_err());
+    }
+
+    #[test]
+    fn test_validate_values() {
+        assert!(validate("pr.default_limit", "200", Scope::Global).is_ok());
+        assert!(validate("pr.default_limit", "0", Scope::Global).is_err());
+        assert!(validate("pr.default_limit", "lots", Scope::Global).is_err());
+        assert!(validate("profile.work.retries", "0", Scope::Global).is_ok());
+        assert!(validate("profile.work.retries", "-1", Scope::Global).is_err());
+        assert!(validate("profile.work.timeout", "0", Scope::Global).is_err());
+        assert!(validate("hyperlinks", "false", Scope::Global).is_ok());
+        assert!(validate("hyperlinks", "nope", Scope::Global).is_err());
+        assert!(validate("jira_url", "https://acme.atlassian.net", Scope::Global).is_ok());
+        assert!(validate("jira_url", "acme.atlassian.net", Scope::Global).is_err());
+
+        let err = validate("credential_store", "vault", Scope::Global).unwrap_err();
+        assert!(err.to_string().contains("one of: keyring, file"), "{}", err);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::context::{Source, Sourced};
 use anyhow::{Context, Result};
 use config::{Config, FileFormat};
 use dirs;
@@ -6,6 +7,18 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct ProfileConfig {
     pub user: Option<String>,
+    /// Where API tokens are stored: "keyring" (default) or "file"
+    pub credential_store: Option<String>,
+    /// Wrap JSON output in a versioned envelope
+    pub json_envelope: Option<bool>,
+    /// Set to false to print URLs as plain text instead of terminal hyperlinks
+    pub hyperlinks: Option<bool>,
+    /// Jira site that issue keys like `PROJ-123` link to, e.g. `https://acme.atlassian.net`
+    pub jira_url: Option<String>,
+    /// `[pr]` settings, e.g. `pr.default_limit`
+    pub pr: Option<ListSettings>,
+    /// `[repo]` settings, e.g. `repo.default_limit`
+    pub repo: Option<ListSettings>,
     #[serde(rename = "profile")]
     pub profiles: Option<std::collections::HashMap<String, Profile>>,
 }
@@ -14,6 +27,19 @@ pub struct ProfileConfig {
 pub struct Profile {
     pub workspace: Option<String>,
     pub user: Option<String>,
+    /// Extra HTTP headers sent with every API request (`[profile.<name>.headers]`)
+    pub headers: Option<std::collections::HashMap<String, String>>,
+    /// Request timeout in seconds
+    pub timeout: Option<u64>,
+    /// Extra attempts for requests that fail transiently
+    pub retries: Option<u32>,
+}
+
+/// Defaults for a `list` command
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ListSettings {
+    /// Used when `--limit` isn't passed
+    pub default_limit: Option<u32>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -70,23 +96,49 @@ impl ProfileConfig {
     }
 
     pub fn get_active_profile(&self) -> Option<&Profile> {
-        let profile_name = self.user.as_deref().unwrap_or("default");
+        self.get_profile(None)
+    }
+
+    /// Name of the profile in effect: the `--profile` override, then the
+    /// configured `user`, then `default`
+    pub fn profile_name<'a>(&'a self, profile_override: Option<&'a str>) -> &'a str {
+        profile_override
+            .or(self.user.as_deref())
+            .unwrap_or("default")
+    }
+
+    /// The profile in effect, honoring a `--profile` override
+    pub fn get_profile(&self, profile_override: Option<&str>) -> Option<&Profile> {
+        let profile_name = self.profile_name(profile_override);
         self.profiles.as_ref().and_then(|p| p.get(profile_name))
     }
 
-    pub fn get_default_user(&self) -> Option<String> {
-        self.get_active_profile().and_then(|p| p.user.clone())
+    /// Every distinct user configured across profiles, sorted
+    pub fn profile_users(&self) -> Vec<&str> {
+        let mut users: Vec<&str> = self
+            .profiles
+            .iter()
+            .flatten()
+            .filter_map(|(_, p)| p.user.as_deref())
+            .collect();
+        users.sort_unstable();
+        users.dedup();
+        users
+    }
+
+    /// The user of the profile in effect, honoring a `--profile` override
+    pub fn get_profile_user(&self, profile_override: Option<&str>) -> Option<String> {
+        self.get_profile(profile_override)
+            .and_then(|p| p.user.clone())
     }
 
     pub fn create_client(
         &self,
         profile_override: Option<&str>,
+        options: crate::api::client::ClientOptions,
     ) -> Result<crate::api::client::BitbucketClient> {
-        let profile_name = profile_override
-            .or(self.user.as_deref())
-            .unwrap_or("default");
-
-        let profile = self.profiles.as_ref().and_then(|p| p.get(profile_name));
+        let profile_name = self.profile_name(profile_override);
+        let profile = self.get_profile(profile_override);
 
         if let Some(p) = profile {
             crate::utils::debug::log(&format!("Profile loaded. User: {:?}", p.user));
@@ -114,7 +166,13 @@ impl ProfileConfig {
             crate::utils::debug::log("No user configured in profile. Running unauthenticated.");
         }
 
-        crate::api::client::BitbucketClient::new(base_url, auth)
+        let client = crate::api::client::BitbucketClient::new(base_url, auth, options)?;
+        match profile.and_then(|p| p.headers.as_ref()) {
+            Some(headers) => client
+                .with_headers(headers)
+                .with_context(|| format!("Invalid headers in profile '{}'", profile_name)),
+            None => Ok(client),
+        }
     }
 }
 
@@ -122,14 +180,11 @@ fn build_global_config() -> Result<Config> {
     let mut builder = Config::builder();
 
     // Global config: ~/.config/bb-cli/config.toml
-    if let Some(config_dir) = get_config_dir() {
-        let global_config_path = config_dir
-            .join(crate::constants::CONFIG_DIR_NAME)
-            .join(crate::constants::CONFIG_FILE_NAME);
-        if global_config_path.exists() {
-            builder =
-                builder.add_source(config::File::from(global_config_path).format(FileFormat::Toml));
-        }
+    if let Some(global_config_path) = get_global_config_path()
+        && global_config_path.exists()
+    {
+        builder =
+            builder.add_source(config::File::from(global_config_path).format(FileFormat::Toml));
     }
 
     builder
@@ -148,6 +203,99 @@ pub fn get_config_dir() -> Option<std::path::PathBuf> {
     }
 }
 
+/// Path of the global config file (~/.config/bb-cli/config.toml)
+pub fn get_global_config_path() -> Option<std::path::PathBuf> {
+    get_config_dir().map(|d| {
+        d.join(crate::constants::CONFIG_DIR_NAME)
+            .join(crate::constants::CONFIG_FILE_NAME)
+    })
+}
+
+/// Read a TOML file into an editable document, if it exists
+fn read_toml_document(path: &std::path::Path) -> Result<Option<toml_edit::DocumentMut>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read configuration at {:?}", path))?;
+    let doc = content
+        .parse::<toml_edit::DocumentMut>()
+        .with_context(|| format!("Failed to parse configuration at {:?}", path))?;
+    Ok(Some(doc))
+}
+
+/// Look up a dotted key (e.g. `profile.work.user`) in a TOML document
+///
+/// Strings are returned unquoted; other values use their TOML representation.
+fn lookup_dotted(doc: &toml_edit::DocumentMut, key: &str) -> Option<String> {
+    let mut item = doc.as_item();
+    for part in key.split('.') {
+        item = item.get(part)?;
+    }
+
+    match item {
+        toml_edit::Item::Value(toml_edit::Value::String(s)) => Some(s.value().clone()),
+        toml_edit::Item::Value(v) => Some(v.to_string().trim().to_string()),
+        toml_edit::Item::Table(t) => Some(t.to_string().trim().to_string()),
+        toml_edit::Item::ArrayOfTables(a) => Some(a.to_string().trim().to_string()),
+        toml_edit::Item::None => None,
+    }
+}
+
+/// Resolve a configuration key against the merged global + local config
+///
+/// Short keys (`workspace`, `repository`, `remote`) resolve like a command
+/// would: the local `[project]` table wins over the active global profile.
+/// Any other key is treated as a dotted path, checked in the local file first
+/// and then in the global file.
+///
+/// # Returns
+///
+/// The effective value and where it came from, or `None` if unset.
+pub fn get_config_value(key: &str, repo_root: Option<&std::path::Path>) -> Result<Option<Sourced>> {
+    let local_doc = match repo_root {
+        Some(root) => read_toml_document(&root.join(crate::constants::LOCAL_CONFIG_FILE_NAME))?,
+        None => None,
+    };
+    let global_doc = match get_global_config_path() {
+        Some(path) => read_toml_document(&path)?,
+        None => None,
+    };
+
+    Ok(resolve_config_value(
+        key,
+        local_doc.as_ref(),
+        global_doc.as_ref(),
+    ))
+}
+
+fn resolve_config_value(
+    key: &str,
+    local_doc: Option<&toml_edit::DocumentMut>,
+    global_doc: Option<&toml_edit::DocumentMut>,
+) -> Option<Sourced> {
+    let (local_key, global_key) = if ["workspace", "repository", "remote"].contains(&key) {
+        let profile_name = global_doc
+            .and_then(|d| lookup_dotted(d, "user"))
+            .unwrap_or_else(|| "default".to_string());
+        (
+            format!("project.{}", key),
+            format!("profile.{}.{}", profile_name, key),
+        )
+    } else {
+        (key.to_string(), key.to_string())
+    };
+
+    local_doc
+        .and_then(|d| lookup_dotted(d, &local_key))
+        .map(|v| (v, Source::LocalConfig))
+        .or_else(|| {
+            global_doc
+                .and_then(|d| lookup_dotted(d, &global_key))
+                .map(|v| (v, Source::GlobalConfig))
+        })
+}
+
 pub fn set_config_value(key: &str, value: &str) -> Result<()> {
     if let Some(config_dir) = get_config_dir() {
         let config_dir = config_dir.join(crate::constants::CONFIG_DIR_NAME);
@@ -163,75 +311,221 @@ pub fn set_config_value(key: &str, value: &str) -> Result<()> {
             .parse::<toml_edit::DocumentMut>()
             .unwrap_or_default();
 
-        // Handle nested keys (e.g. profile.default.user)
-        let parts: Vec<&str> = key.split('.').collect();
-        let mut current_table = doc.as_table_mut();
+        set_dotted(&mut doc, key, value)?;
 
-        // TODO Evaluate if mixing loop and iterator can be improved
-        for (i, part) in parts.iter().enumerate() {
-            if i == parts.len() - 1 {
-                // Last part, set the value
-                current_table.insert(part, toml_edit::Item::Value(toml_edit::Value::from(value)));
-            } else {
-                // Intermediate part, navigate or create table
-                let entry = current_table
-                    .entry(part)
-                    .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+        std::fs::write(&config_path, doc.to_string())?;
+    }
+    Ok(())
+}
 
-                if let toml_edit::Item::Table(t) = entry {
-                    current_table = t;
-                } else {
-                    // It might be an inline table or something else.
-                    // For simplicity, if it's not a table, we can't easily descend.
-                    // But `or_insert` with Table should work for new entries.
-                    // If it exists and is not a table, we have a conflict.
-                    return Err(anyhow::anyhow!("Config key conflict at '{}'", part));
+/// Set a dotted key in the local `.bb-cli` project file under `target_dir`
+///
+/// The file is created if missing; other keys and comments are preserved.
+pub fn set_local_config_value(target_dir: &std::path::Path, key: &str, value: &str) -> Result<()> {
+    let config_path = target_dir.join(crate::constants::LOCAL_CONFIG_FILE_NAME);
+    let mut doc = read_toml_document(&config_path)?.unwrap_or_default();
+
+    set_dotted(&mut doc, key, value)?;
+
+    std::fs::write(&config_path, doc.to_string())
+        .with_context(|| format!("Failed to write configuration at {:?}", config_path))?;
+    Ok(())
+}
+
+/// Set a dotted key (e.g. `profile.default.user`) to a string value,
+/// creating intermediate tables and keeping any comment on an existing value
+fn set_dotted(doc: &mut toml_edit::DocumentMut, key: &str, value: &str) -> Result<()> {
+    // Handle nested keys (e.g. profile.default.user)
+    let parts: Vec<&str> = key.split('.').collect();
+    let mut current_table = doc.as_table_mut();
+
+    // TODO Evaluate if mixing loop and iterator can be improved
+    for (i, part) in parts.iter().enumerate() {
+        if i == parts.len() - 1 {
+            // Last part, set the value
+            match current_table
+                .get_mut(part)
+                .and_then(|item| item.as_value_mut())
+            {
+                Some(existing) => {
+                    let decor = existing.decor().clone();
+                    *existing = toml_edit::Value::from(value);
+                    *existing.decor_mut() = decor;
+                }
+                None => {
+                    current_table
+                        .insert(part, toml_edit::Item::Value(toml_edit::Value::from(value)));
                 }
             }
-        }
+        } else {
+            // Intermediate part, navigate or create table
+            let entry = current_table
+                .entry(part)
+                .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
 
-        std::fs::write(&config_path, doc.to_string())?;
-        // println!("Updated configuration at: {:?}", config_path);
+            if let toml_edit::Item::Table(t) = entry {
+                current_table = t;
+            } else {
+                // It might be an inline table or something else.
+                // For simplicity, if it's not a table, we can't easily descend.
+                // But `or_insert` with Table should work for new entries.
+                // If it exists and is not a table, we have a conflict.
+                return Err(anyhow::anyhow!("Config key conflict at '{}'", part));
+            }
+        }
     }
+
     Ok(())
 }
 
+/// Write the local `.bb-cli` project file
+///
+/// If the file already exists, the given values are merged into its
+/// `[project]` table, keeping unrelated keys and comments. With `force`, the
+/// file is replaced instead. Empty values are skipped so they never clobber
+/// an existing setting.
 pub fn init_local_config(
     target_dir: &std::path::Path,
     workspace: &str,
     repo: &str,
     remote: &str,
+    force: bool,
 ) -> Result<()> {
     let config_path = target_dir.join(crate::constants::LOCAL_CONFIG_FILE_NAME);
 
-    if config_path.exists() {
-        return Err(anyhow::anyhow!(
-            "Local configuration file already exists at {:?}",
-            config_path
-        ));
+    let mut doc = if force {
+        toml_edit::DocumentMut::new()
+    } else {
+        read_toml_document(&config_path)?.unwrap_or_default()
+    };
+
+    let project = doc
+        .entry("project")
+        .or_insert(toml_edit::Item::Table(toml_edit::Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("'project' in {:?} is not a table", config_path))?;
+
+    for (key, value) in [
+        ("workspace", workspace),
+        ("repository", repo),
+        ("remote", remote),
+    ] {
+        if value.is_empty() {
+            continue;
+        }
+
+        match project.get_mut(key).and_then(|item| item.as_value_mut()) {
+            Some(existing) => {
+                // Keep any trailing comment attached to the old value
+                let decor = existing.decor().clone();
+                *existing = toml_edit::Value::from(value);
+                *existing.decor_mut() = decor;
+            }
+            None => {
+                project.insert(key, toml_edit::Item::Value(toml_edit::Value::from(value)));
+            }
+        }
     }
 
-    let mut doc = toml_edit::DocumentMut::new();
+    std::fs::write(&config_path, doc.to_string())?;
+    Ok(())
+}
 
-    // Create [project]
-    let mut project = toml_edit::Table::new();
-    project.insert(
-        "workspace",
-        toml_edit::Item::Value(toml_edit::Value::from(workspace)),
-    );
-    project.insert(
-        "repository",
-        toml_edit::Item::Value(toml_edit::Value::from(repo)),
-    );
-    project.insert(
-        "remote",
-        toml_edit::Item::Value(toml_edit::Value::from(remote)),
-    );
+/// Upgrade the global config file in place
+///
+/// Backs up the original to `config.toml.bak` before writing. Running it on
+/// an already-current file changes nothing.
+///
+/// # Returns
+///
+/// A description of each change applied, or `None` if there is no config file.
+pub fn migrate_global_config(dry_run: bool) -> Result<Option<Vec<String>>> {
+    let Some(path) = get_global_config_path() else {
+        return Ok(None);
+    };
+    let Some(mut doc) = read_toml_document(&path)? else {
+        return Ok(None);
+    };
+
+    let changes = migrate_config_document(&mut doc);
+    if changes.is_empty() || dry_run {
+        return Ok(Some(changes));
+    }
 
-    doc.insert("project", toml_edit::Item::Table(project));
+    let backup_path = path.with_extension("toml.bak");
+    std::fs::copy(&path, &backup_path)
+        .with_context(|| format!("Failed to back up configuration to {:?}", backup_path))?;
+    std::fs::write(&path, doc.to_string())
+        .with_context(|| format!("Failed to write configuration at {:?}", path))?;
 
-    std::fs::write(&config_path, doc.to_string())?;
-    Ok(())
+    Ok(Some(changes))
+}
+
+/// Apply known schema migrations to a global config document
+///
+/// - `[profiles.<name>]` tables are renamed to `[profile.<name>]`
+/// - A bare top-level `workspace` moves into the active profile
+fn migrate_config_document(doc: &mut toml_edit::DocumentMut) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if let Some(toml_edit::Item::Table(legacy)) = doc.remove("profiles") {
+        let profiles = doc
+            .entry("profile")
+            .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+        if let Some(profiles) = profiles.as_table_mut() {
+            profiles.set_implicit(true);
+            for (name, item) in legacy.into_iter() {
+                if profiles.contains_key(&name) {
+                    changes.push(format!(
+                        "Dropped [profiles.{}]: [profile.{}] already exists",
+                        name, name
+                    ));
+                } else {
+                    profiles.insert(&name, item);
+                    changes.push(format!("Renamed [profiles.{}] to [profile.{}]", name, name));
+                }
+            }
+        }
+    }
+
+    let workspace = doc
+        .get("workspace")
+        .and_then(|item| item.as_str())
+        .map(str::to_string);
+    if let Some(workspace) = workspace {
+        doc.remove("workspace");
+
+        let profile_name = doc
+            .get("user")
+            .and_then(|item| item.as_str())
+            .unwrap_or("default")
+            .to_string();
+        let profiles = doc
+            .entry("profile")
+            .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+        if let Some(profiles) = profiles.as_table_mut() {
+            profiles.set_implicit(true);
+            let profile = profiles
+                .entry(&profile_name)
+                .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+            if let Some(profile) = profile.as_table_mut() {
+                if profile.contains_key("workspace") {
+                    changes.push(format!(
+                        "Dropped top-level workspace '{}': profile.{}.workspace is already set",
+                        workspace, profile_name
+                    ));
+                } else {
+                    profile.insert("workspace", toml_edit::value(workspace.as_str()));
+                    changes.push(format!(
+                        "Moved top-level workspace '{}' to profile.{}.workspace",
+                        workspace, profile_name
+                    ));
+                }
+            }
+        }
+    }
+
+    changes
 }
 
 #[cfg(test)]
@@ -247,12 +541,16 @@ mod tests {
             Profile {
                 workspace: Some("ws".to_string()),
                 user: Some("default_user".to_string()),
+                headers: None,
+                timeout: None,
+                retries: None,
             },
         );
 
         let config = ProfileConfig {
             user: None,
             profiles: Some(profiles),
+            ..Default::default()
         };
 
         let profile = config.get_active_profile();
@@ -269,12 +567,16 @@ mod tests {
             Profile {
                 workspace: Some("custom_ws".to_string()),
                 user: Some("custom_user".to_string()),
+                headers: None,
+                timeout: None,
+                retries: None,
             },
         );
 
         let config = ProfileConfig {
             user: Some("custom".to_string()),
             profiles: Some(profiles),
+            ..Default::default()
         };
 
         let profile = config.get_active_profile();
@@ -290,15 +592,19 @@ mod tests {
             Profile {
                 workspace: Some("ws".to_string()),
                 user: Some("test_user".to_string()),
+                headers: None,
+                timeout: None,
+                retries: None,
             },
         );
 
         let config = ProfileConfig {
             user: None,
             profiles: Some(profiles),
+            ..Default::default()
         };
 
-        let user = config.get_default_user();
+        let user = config.get_profile_user(None);
         assert_eq!(user, Some("test_user".to_string()));
     }
 
@@ -310,15 +616,317 @@ mod tests {
             Profile {
                 workspace: Some("ws".to_string()),
                 user: None,
+                headers: None,
+                timeout: None,
+                retries: None,
             },
         );
 
         let config = ProfileConfig {
             user: None,
             profiles: Some(profiles),
+            ..Default::default()
         };
 
-        let user = config.get_default_user();
+        let user = config.get_profile_user(None);
         assert_eq!(user, None);
     }
+
+    #[test]
+    fn test_profile_users() {
+        let profile = |user: Option<&str>| Profile {
+            workspace: None,
+            user: user.map(str::to_string),
+            headers: None,
+            timeout: None,
+            retries: None,
+        };
+        let mut profiles = HashMap::new();
+        profiles.insert("work".to_string(), profile(Some("me@work.com")));
+        profiles.insert("ci".to_string(), profile(Some("me@work.com")));
+        profiles.insert("personal".to_string(), profile(Some("me@home.com")));
+        profiles.insert("anon".to_string(), profile(None));
+
+        let config = ProfileConfig {
+            profiles: Some(profiles),
+            ..Default::default()
+        };
+        assert_eq!(config.profile_users(), vec!["me@home.com", "me@work.com"]);
+        assert!(ProfileConfig::default().profile_users().is_empty());
+    }
+
+    #[test]
+    fn test_profile_override_selects_named_profile() {
+        let profile = |user: &str| Profile {
+            workspace: None,
+            user: Some(user.to_string()),
+            headers: None,
+            timeout: None,
+            retries: None,
+        };
+        let mut profiles = HashMap::new();
+        profiles.insert("personal".to_string(), profile("me@home.com"));
+        profiles.insert("work".to_string(), profile("me@work.com"));
+
+        let config = ProfileConfig {
+            user: Some("personal".to_string()),
+            profiles: Some(profiles),
+            ..Default::default()
+        };
+
+        assert_eq!(config.profile_name(Some("work")), "work");
+        assert_eq!(
+            config.get_profile_user(Some("work")).as_deref(),
+            Some("me@work.com")
+        );
+        // Without an override the configured active profile wins
+        assert_eq!(config.profile_name(None), "personal");
+        assert_eq!(
+            config.get_profile_user(None).as_deref(),
+            Some("me@home.com")
+        );
+        // An unknown override doesn't fall back to the active profile
+        assert!(config.get_profile(Some("missing")).is_none());
+    }
+
+    fn doc(content: &str) -> toml_edit::DocumentMut {
+        content.parse().unwrap()
+    }
+
+    #[test]
+    fn test_list_default_limits_deserialize() {
+        // `bb config set` writes strings; integers written by hand must work too
+        let toml = "[pr]\ndefault_limit = \"200\"\n\n[repo]\ndefault_limit = 25\n";
+        let config: ProfileConfig = Config::builder()
+            .add_source(config::File::from_str(toml, FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        assert_eq!(config.pr.unwrap().default_limit, Some(200));
+        assert_eq!(config.repo.unwrap().default_limit, Some(25));
+    }
+
+    #[test]
+    fn test_init_local_config_fresh() {
+        let dir = tempfile::tempdir().unwrap();
+        init_local_config(dir.path(), "ws", "repo", "origin", false).unwrap();
+
+        let content =
+            std::fs::read_to_string(dir.path().join(crate::constants::LOCAL_CONFIG_FILE_NAME))
+                .unwrap();
+        let doc = content.parse::<toml_edit::DocumentMut>().unwrap();
+        assert_eq!(
+            lookup_dotted(&doc, "project.workspace").as_deref(),
+            Some("ws")
+        );
+        assert_eq!(
+            lookup_dotted(&doc, "project.repository").as_deref(),
+            Some("repo")
+        );
+        assert_eq!(
+            lookup_dotted(&doc, "project.remote").as_deref(),
+            Some("origin")
+        );
+    }
+
+    #[test]
+    fn test_init_local_config_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(crate::constants::LOCAL_CONFIG_FILE_NAME);
+        std::fs::write(
+            &path,
+            "# team settings\n[project]\nworkspace = \"old\" # keep me\nrepository = \"repo\"\nextra = \"value\"\n",
+        )
+        .unwrap();
+
+        init_local_config(dir.path(), "new", "", "upstream", false).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("# team settings"));
+        assert!(content.contains("# keep me"));
+
+        let doc = content.parse::<toml_edit::DocumentMut>().unwrap();
+        assert_eq!(
+            lookup_dotted(&doc, "project.workspace").as_deref(),
+            Some("new")
+        );
+        // Empty values don't clobber existing settings
+        assert_eq!(
+            lookup_dotted(&doc, "project.repository").as_deref(),
+            Some("repo")
+        );
+        assert_eq!(
+            lookup_dotted(&doc, "project.remote").as_deref(),
+            Some("upstream")
+        );
+        assert_eq!(
+            lookup_dotted(&doc, "project.extra").as_deref(),
+            Some("value")
+        );
+    }
+
+    #[test]
+    fn test_init_local_config_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(crate::constants::LOCAL_CONFIG_FILE_NAME);
+        std::fs::write(&path, "[project]\nextra = \"value\"\n").unwrap();
+
+        init_local_config(dir.path(), "ws", "repo", "origin", true).unwrap();
+
+        let doc = std::fs::read_to_string(&path)
+            .unwrap()
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        assert_eq!(
+            lookup_dotted(&doc, "project.workspace").as_deref(),
+            Some("ws")
+        );
+        assert_eq!(lookup_dotted(&doc, "project.extra"), None);
+    }
+
+    #[test]
+    fn test_lookup_dotted() {
+        let global = doc("user = \"work\"\n[profile.work]\nuser = \"me@example.com\"\n");
+        assert_eq!(lookup_dotted(&global, "user"), Some("work".to_string()));
+        assert_eq!(
+            lookup_dotted(&global, "profile.work.user"),
+            Some("me@example.com".to_string())
+        );
+        assert_eq!(lookup_dotted(&global, "profile.other.user"), None);
+    }
+
+    #[test]
+    fn test_resolve_config_value_local_wins() {
+        let global = doc(
+            "user = \"work\"\n[profile.work]\nworkspace = \"global_ws\"\nremote = \"upstream\"\n",
+        );
+        let local = doc("[project]\nworkspace = \"local_ws\"\nrepository = \"repo\"\n");
+
+        assert_eq!(
+            resolve_config_value("workspace", Some(&local), Some(&global)),
+            Some(("local_ws".to_string(), Source::LocalConfig))
+        );
+        assert_eq!(
+            resolve_config_value("repository", Some(&local), Some(&global)),
+            Some(("repo".to_string(), Source::LocalConfig))
+        );
+        assert_eq!(
+            resolve_config_value("remote", Some(&local), Some(&global)),
+            Some(("upstream".to_string(), Source::GlobalConfig))
+        );
+        assert_eq!(
+            resolve_config_value("profile.work.workspace", Some(&local), Some(&global)),
+            Some(("global_ws".to_string(), Source::GlobalConfig))
+        );
+        assert_eq!(
+            resolve_config_value("missing", Some(&local), Some(&global)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_migrate_moves_top_level_workspace() {
+        let mut doc =
+            "user = \"work\"\nworkspace = \"acme\"\n\n[profile.work]\nuser = \"me@acme.com\"\n"
+                .parse::<toml_edit::DocumentMut>()
+                .unwrap();
+
+        let changes = migrate_config_document(&mut doc);
+
+        assert_eq!(changes.len(), 1);
+        assert!(doc.get("workspace").is_none());
+        assert_eq!(
+            lookup_dotted(&doc, "profile.work.workspace").as_deref(),
+            Some("acme")
+        );
+        assert_eq!(
+            lookup_dotted(&doc, "profile.work.user").as_deref(),
+            Some("me@acme.com")
+        );
+    }
+
+    #[test]
+    fn test_migrate_keeps_existing_profile_workspace() {
+        let mut doc = "workspace = \"old\"\n\n[profile.default]\nworkspace = \"new\"\n"
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+
+        let changes = migrate_config_document(&mut doc);
+
+        assert!(changes[0].starts_with("Dropped"));
+        assert_eq!(
+            lookup_dotted(&doc, "profile.default.workspace").as_deref(),
+            Some("new")
+        );
+    }
+
+    #[test]
+    fn test_migrate_renames_profiles_table() {
+        let mut doc =
+            "# my config\nuser = \"default\"\n\n[profiles.default]\nuser = \"me\" # login\n"
+                .parse::<toml_edit::DocumentMut>()
+                .unwrap();
+
+        let changes = migrate_config_document(&mut doc);
+
+        assert_eq!(changes.len(), 1);
+        assert!(doc.get("profiles").is_none());
+        assert_eq!(
+            lookup_dotted(&doc, "profile.default.user").as_deref(),
+            Some("me")
+        );
+        let output = doc.to_string();
+        assert!(output.contains("# my config"));
+        assert!(output.contains("# login"));
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let mut doc = "workspace = \"acme\"\n\n[profiles.work]\nuser = \"me\"\n"
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+
+        assert_eq!(migrate_config_document(&mut doc).len(), 2);
+        let migrated = doc.to_string();
+
+        assert!(migrate_config_document(&mut doc).is_empty());
+        assert_eq!(doc.to_string(), migrated);
+    }
+
+    #[test]
+    fn test_set_local_config_value_creates_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        set_local_config_value(dir.path(), "project.workspace", "acme").unwrap();
+
+        let doc = read_toml_document(&dir.path().join(crate::constants::LOCAL_CONFIG_FILE_NAME))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            lookup_dotted(&doc, "project.workspace").as_deref(),
+            Some("acme")
+        );
+    }
+
+    #[test]
+    fn test_set_local_config_value_preserves_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(crate::constants::LOCAL_CONFIG_FILE_NAME);
+        std::fs::write(
+            &path,
+            "# project settings\n[project]\nworkspace = \"old\" # team ws\nrepository = \"app\"\n",
+        )
+        .unwrap();
+
+        set_local_config_value(dir.path(), "project.workspace", "new").unwrap();
+        set_local_config_value(dir.path(), "project.remote", "upstream").unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("# project settings"));
+        assert!(content.contains("workspace = \"new\" # team ws"));
+        assert!(content.contains("repository = \"app\""));
+        assert!(content.contains("remote = \"upstream\""));
+    }
 }
@@ -3,7 +3,12 @@ use std::io::{self, Write};
 
 use crate::display::ui;
 
-pub fn interactive_init() -> Result<()> {
+/// Interactively initialize the global profile or a local project file
+///
+/// # Arguments
+///
+/// * `force` - Overwrite an existing local project file instead of merging into it
+pub fn interactive_init(force: bool) -> Result<()> {
     ui::info("Initializing config...");
     // Interactive setup
 
@@ -26,7 +31,7 @@ pub fn interactive_init() -> Result<()> {
         let target_dir = crate::git::get_repo_root()
             .unwrap_or_else(|_| std::env::current_dir().expect("Failed to get current directory"));
 
-        crate::config::manager::init_local_config(&target_dir, &workspace, &repo, &remote)?;
+        crate::config::manager::init_local_config(&target_dir, &workspace, &repo, &remote, force)?;
         ui::success(&format!(
             "Local configuration initialized at {:?}",
             target_dir
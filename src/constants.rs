@@ -1,10 +1,56 @@
 pub const BB_CLI_IDENTIFIER: &str = "bb-cli";
 pub const CONFIG_DIR_NAME: &str = BB_CLI_IDENTIFIER;
 pub const KEYRING_SERVICE_NAME: &str = BB_CLI_IDENTIFIER;
+pub const CACHE_DIR_NAME: &str = BB_CLI_IDENTIFIER;
 pub const CONFIG_FILE_NAME: &str = "config.toml";
+pub const CREDENTIALS_FILE_NAME: &str = "credentials.json";
 pub const LOCAL_CONFIG_FILE_NAME: &str = ".bb-cli";
 pub const DEFAULT_API_URL: &str = "https://api.bitbucket.org/2.0";
 
+// Environment variables naming the workspace and repository slug, below -R/--repo
+pub const WORKSPACE_ENV: &str = "BB_WORKSPACE";
+pub const REPO_ENV: &str = "BB_REPO";
+
+// Environment variables overriding the profile's request timeout and retries, below the flags
+pub const TIMEOUT_ENV: &str = "BB_TIMEOUT";
+pub const RETRIES_ENV: &str = "BB_RETRIES";
+
+// Request timeout and retry count when nothing else sets them
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+pub const DEFAULT_RETRIES: u32 = 0;
+
+// Longest age `--older-than` accepts (100 years), so `now - age` is still a date
+pub const MAX_AGE_DAYS: i64 = 36525;
+
+// Times a diff download picks up where a dropped connection left off
+pub const MAX_DIFF_RESUMES: u32 = 3;
+
+// Token scopes needed for the full command set (reviews, comments, merges)
+pub const REQUIRED_SCOPES: &[&str] = &["account", "repository:write", "pullrequest:write"];
+
+// Version of the `--json-envelope` output format; bump on breaking changes
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+// JSON arrays longer than this are written compact (unless `--pretty`), with a warning on stderr
+pub const JSON_COMPACT_THRESHOLD: usize = 1000;
+
+// Largest `pagelen` Bitbucket accepts for list endpoints
+pub const MAX_PAGE_LEN: u32 = 100;
+
+// Fallback `--limit` for list commands when the config sets no default
+pub const DEFAULT_PR_LIST_LIMIT: u32 = 50;
+pub const DEFAULT_REPO_LIST_LIMIT: u32 = 100;
+
+// How long `bb repo list` results are served from the on-disk cache
+pub const REPO_CACHE_TTL_SECS: u64 = 300;
+
+// How long `bb pr view` serves a merged PR from the on-disk cache
+pub const PR_CACHE_TTL_SECS: u64 = 3600;
+
+// Patches larger than this aren't cached; least recently used ones go once the total is over
+pub const DIFF_CACHE_MAX_ENTRY_BYTES: u64 = 20 * 1024 * 1024;
+pub const DIFF_CACHE_MAX_BYTES: u64 = 200 * 1024 * 1024;
+
 // Display constants
 pub const DEFAULT_TABLE_WIDTH: u16 = 120;
 pub const MAX_TABLE_WIDTH: u16 = 120;
@@ -1,130 +1,995 @@
-use crate::api::client::BitbucketClient;
+use crate::api::client::{BitbucketClient, ClientOptions};
 use crate::cli::Cli;
-use crate::config::manager::ProfileConfig;
+use crate::config::manager::{LocalProjectConfig, Profile, ProfileConfig, ProjectContext};
 use crate::{display, git, utils};
 use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 
 pub struct AppContext {
     pub client: BitbucketClient,
     pub json: bool,
     pub workspace: Option<String>,
     pub repo: Option<String>,
+    /// Shared limiter bounding concurrent requests (`--max-concurrency`)
+    pub limiter: Arc<Semaphore>,
+    /// `pr.default_limit` from the global config
+    pub pr_default_limit: Option<u32>,
+    /// `repo.default_limit` from the global config
+    pub repo_default_limit: Option<u32>,
+    /// `--profile` override; `None` means the configured active profile
+    pub profile: Option<String>,
+    /// Name of the profile in effect, which scopes cached API results to its account
+    pub profile_name: String,
+    /// `jira_url` from the global config, for linking issue keys
+    pub jira_url: Option<String>,
+    /// Git remote the repository was detected from; `None` means `origin`
+    pub remote: Option<String>,
+    /// Which layer each resolved setting came from (`config list --sources`)
+    pub sources: Sources,
+    /// `--no-config`: commands shouldn't read the config files either
+    pub no_config: bool,
 }
 
-impl AppContext {
-    pub fn new(cli: &Cli) -> Result<Self> {
-        // 1. Load Global Config (Preferences & Auth)
-        let global_config = match ProfileConfig::load_global() {
-            Ok(c) => c,
-            Err(e) => {
-                // If it's a parse error or IO error other than NotFound, we should probably fail?
-                // For now, keeping warning behavior but making it more visible if needed.
-                // But plan said "Improve error visibility".
-                // If the file exists but is invalid, we should error.
-                // load_global uses build_global_config which uses config crate.
-                // We can't easily distinguish "not found" from "parse error" without inspecting error.
-                // But usually config crate handles "not found" by just returning default if we set it up that way,
-                // but here we are adding source file.
-                // Let's just warn for now as per existing behavior but maybe upgrade to error if it's critical?
-                // The user review didn't explicitly demand erroring out, just "Improve error visibility".
-                if !cli.quiet {
-                    display::ui::warning(&format!("Failed to load global config: {}", e));
-                }
-                ProfileConfig::default()
-            }
+/// Every layer that can name the workspace and repository
+struct CoordinateLayers<'a> {
+    /// `-R/--repo`, as `workspace/repo` or just `repo`
+    cli_repo: Option<&'a str>,
+    /// [`crate::constants::WORKSPACE_ENV`]
+    env_workspace: Option<String>,
+    /// [`crate::constants::REPO_ENV`]
+    env_repo: Option<String>,
+    /// `[project]` table of the local `.bb-cli`
+    project: Option<&'a ProjectContext>,
+    /// `(workspace, repo)` parsed from the git remote, and which remote
+    git: Option<((String, String), Source)>,
+    /// The active profile's workspace and the profile's name
+    profile_workspace: Option<(String, String)>,
+}
+
+impl CoordinateLayers<'_> {
+    /// The workspace and repository in effect, each with the layer it came from
+    ///
+    /// Workspace priority: CLI > environment > local config > git remote > global config.
+    /// Repository priority: CLI > environment > local config > git remote.
+    fn resolve(self) -> (Option<Sourced>, Option<Sourced>) {
+        // A bare `-R repo` leaves the workspace to the other layers
+        let (cli_workspace, cli_repo) = match self.cli_repo.map(|r| r.split_once('/')) {
+            Some(Some((w, r))) => (Some(w.to_string()), Some(r.to_string())),
+            Some(None) => (None, self.cli_repo.map(str::to_string)),
+            None => (None, None),
+        };
+        let (git_coords, git_source) = match self.git {
+            Some(((w, r), source)) => ((Some(w), Some(r)), Some(source)),
+            None => ((None, None), None),
         };
+        let (profile_workspace, profile_name) = self.profile_workspace.unzip();
 
-        // 2. Get Git Context (Repo Root) - ONCE
-        let repo_root = git::get_repo_root().ok();
+        let workspace = first_set([
+            (cli_workspace, Some(Source::Flag("--repo"))),
+            (
+                self.env_workspace,
+                Some(Source::Env(crate::constants::WORKSPACE_ENV)),
+            ),
+            (
+                self.project.and_then(|p| p.workspace.clone()),
+                Some(Source::LocalConfig),
+            ),
+            (git_coords.0, git_source.clone()),
+            (profile_workspace, profile_name.map(Source::Profile)),
+        ]);
+        let repo = first_set([
+            (cli_repo, Some(Source::Flag("--repo"))),
+            (self.env_repo, Some(Source::Env(crate::constants::REPO_ENV))),
+            (
+                self.project.and_then(|p| p.repository.clone()),
+                Some(Source::LocalConfig),
+            ),
+            (git_coords.1, git_source),
+        ]);
+        (workspace, repo)
+    }
+}
 
-        // 3. Load Local Config (Project overrides)
-        // Pass the already resolved repo_root
-        let local_config = match ProfileConfig::load_local(repo_root.as_deref()) {
-            Ok(c) => c,
-            Err(e) => {
-                if !cli.quiet {
-                    display::ui::warning(&format!("Failed to load local config: {}", e));
-                }
-                None
-            }
-        };
+/// The workspace, repository and git remote a command runs against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coordinates {
+    pub workspace: Option<Sourced>,
+    pub repo: Option<Sourced>,
+    /// The git remote coordinates are read from; `None` outside a git
+    /// repository, `origin` with [`Source::Default`] when nothing names one
+    pub remote: Option<Sourced>,
+}
+
+/// Command-line flags that take priority in [`resolve_coordinates`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoordinateOverrides<'a> {
+    /// `-R/--repo`
+    pub repo: Option<&'a str>,
+    /// `--remote`
+    pub remote: Option<&'a str>,
+    /// `--profile`
+    pub profile: Option<&'a str>,
+}
+
+/// Environment variables that name the workspace and repository, below the flags
+#[derive(Debug, Clone, Default)]
+pub struct CoordinateEnv {
+    /// [`crate::constants::WORKSPACE_ENV`]
+    pub workspace: Option<String>,
+    /// [`crate::constants::REPO_ENV`]
+    pub repo: Option<String>,
+}
 
-        // 4. Resolve Git Remote Info
-        // We need to know which remote to check.
-        let remote_name = cli.remote.as_deref().or(local_config
-            .as_ref()
-            .and_then(|c| c.project.as_ref())
-            .and_then(|p| p.remote.as_deref()));
+impl CoordinateEnv {
+    /// The variables as this process sees them
+    pub fn from_env() -> Self {
+        Self {
+            workspace: env_value(crate::constants::WORKSPACE_ENV),
+            repo: env_value(crate::constants::REPO_ENV),
+        }
+    }
+}
 
-        let git_info = if repo_root.is_some() {
-            match git::get_repo_info(remote_name) {
-                Ok((ws, repo)) => Some((ws, repo)),
+/// Resolve the workspace, repository and remote the way every command does
+///
+/// `repo_root` is the git repository to read remotes and the current branch
+/// from (`None` when not in one); `project` is its `.bb-cli` `[project]` table.
+pub fn resolve_coordinates(
+    repo_root: Option<&std::path::Path>,
+    global_config: &ProfileConfig,
+    project: Option<&ProjectContext>,
+    overrides: CoordinateOverrides,
+    env: CoordinateEnv,
+) -> Coordinates {
+    // Priority: CLI > Local Config > current branch's tracking remote > origin
+    let remote = overrides
+        .remote
+        .map(|r| (r.to_string(), Source::Flag("--remote")))
+        .or_else(|| {
+            project
+                .and_then(|p| p.remote.clone())
+                .map(|r| (r, Source::LocalConfig))
+        })
+        .or_else(|| {
+            let branch = git::current_branch_in(repo_root?).ok()?;
+            git::branch_remote_in(repo_root?, &branch).map(|r| (r, Source::BranchRemote(branch)))
+        });
+    let remote =
+        repo_root.map(|_| remote.unwrap_or_else(|| ("origin".to_string(), Source::Default)));
+
+    let git_info = match (repo_root, &remote) {
+        (Some(root), Some((name, _))) => {
+            utils::debug::log(&format!("Using git remote '{}'", name));
+            match git::repo_info_in(root, Some(name)) {
+                Ok(info) => Some((info, Source::GitRemote(name.clone()))),
                 Err(e) => {
                     utils::debug::log(&format!("Failed to get git repo info: {}", e));
                     None
                 }
             }
-        } else {
-            None
-        };
+        }
+        _ => None,
+    };
 
-        let cli_coords = if let Some(r) = &cli.repo {
-            if let Some((w, r)) = r.split_once('/') {
-                Some((Some(w.to_string()), Some(r.to_string())))
-            } else {
-                // If no slash, treat as just repo name, workspace remains None (to be resolved later)
-                Some((None, Some(r.to_string())))
+    let profile_name = global_config.profile_name(overrides.profile);
+    let (workspace, repo) = CoordinateLayers {
+        cli_repo: overrides.repo,
+        env_workspace: env.workspace,
+        env_repo: env.repo,
+        project,
+        git: git_info,
+        profile_workspace: global_config
+            .get_profile(overrides.profile)
+            .and_then(|p| p.workspace.clone())
+            .map(|w| (w, profile_name.to_string())),
+    }
+    .resolve();
+
+    Coordinates {
+        workspace,
+        repo,
+        remote,
+    }
+}
+
+/// Every layer that can set the request timeout and retries
+///
+/// Priority: CLI > environment > active profile > default.
+struct NetworkLayers<'a> {
+    cli_timeout: Option<u64>,
+    cli_retries: Option<u32>,
+    /// [`crate::constants::TIMEOUT_ENV`], unparsed
+    env_timeout: Option<String>,
+    /// [`crate::constants::RETRIES_ENV`], unparsed
+    env_retries: Option<String>,
+    profile: Option<&'a Profile>,
+}
+
+impl NetworkLayers<'_> {
+    /// The client options in effect; fails if an environment variable isn't a
+    /// number (or the timeout is zero)
+    fn resolve(self) -> Result<ClientOptions> {
+        let defaults = ClientOptions::default();
+
+        let env_timeout = parse_env::<u64>(crate::constants::TIMEOUT_ENV, self.env_timeout)?;
+        if env_timeout == Some(0) {
+            anyhow::bail!(
+                "Invalid {} '0': the timeout must be at least 1 second",
+                crate::constants::TIMEOUT_ENV
+            );
+        }
+        let timeout = self
+            .cli_timeout
+            .or(env_timeout)
+            .or(self
+                .profile
+                .and_then(|p| p.timeout)
+                .filter(|secs| *secs > 0))
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(defaults.timeout);
+
+        let env_retries = parse_env::<u32>(crate::constants::RETRIES_ENV, self.env_retries)?;
+        let retries = self
+            .cli_retries
+            .or(env_retries)
+            .or(self.profile.and_then(|p| p.retries))
+            .unwrap_or(defaults.retries);
+
+        Ok(ClientOptions { timeout, retries })
+    }
+}
+
+/// Parse a numeric environment variable, naming it in the error
+fn parse_env<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<Option<T>> {
+    value
+        .map(|v| {
+            v.parse::<T>()
+                .map_err(|_| anyhow::anyhow!("Invalid {} '{}': expected a whole number", name, v))
+        })
+        .transpose()
+}
+
+/// A resolved value and the layer it came from
+pub type Sourced = (String, Source);
+
+/// The first candidate with a value, highest priority first
+fn first_set<const N: usize>(candidates: [(Option<String>, Option<Source>); N]) -> Option<Sourced> {
+    candidates
+        .into_iter()
+        .find_map(|(value, source)| value.zip(source))
+}
+
+/// A non-empty environment variable, trimmed
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Where a resolved setting came from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Source {
+    /// A command-line flag, e.g. `--repo`
+    Flag(&'static str),
+    /// This environment variable
+    Env(&'static str),
+    /// The project's `.bb-cli` file
+    LocalConfig,
+    /// Parsed from the URL of this git remote
+    GitRemote(String),
+    /// The remote this branch tracks (`branch.<name>.remote`)
+    BranchRemote(String),
+    /// This profile in the global config
+    Profile(String),
+    /// A top-level key of the global config
+    GlobalConfig,
+    /// Built-in default
+    #[default]
+    Default,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Flag(flag) => write!(f, "{} flag", flag),
+            Source::Env(name) => write!(f, "{} environment variable", name),
+            Source::LocalConfig => write!(f, "local {}", crate::constants::LOCAL_CONFIG_FILE_NAME),
+            Source::GitRemote(remote) => write!(f, "git remote {}", remote),
+            Source::BranchRemote(branch) => write!(f, "tracking remote of branch {}", branch),
+            Source::Profile(name) => write!(f, "profile {} in global config", name),
+            Source::GlobalConfig => write!(f, "global config"),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Sources of the settings resolved in [`AppContext::new`]; `None` where the
+/// setting has no value
+#[derive(Debug, Clone, Default)]
+pub struct Sources {
+    pub profile: Source,
+    pub workspace: Option<Source>,
+    pub repo: Option<Source>,
+    pub remote: Option<Source>,
+}
+
+/// The global config (from `load_global`) and the local `.bb-cli`, or
+/// neither with `--no-config`
+///
+/// A file that fails to load is skipped with a warning (unless `quiet`).
+fn load_config_files(
+    repo_root: Option<&std::path::Path>,
+    load_global: impl FnOnce() -> Result<ProfileConfig>,
+    no_config: bool,
+    quiet: bool,
+) -> (ProfileConfig, Option<LocalProjectConfig>) {
+    if no_config {
+        utils::debug::log("Ignoring config files (--no-config)");
+        return (ProfileConfig::default(), None);
+    }
+
+    let global_config = match load_global() {
+        Ok(c) => c,
+        Err(e) => {
+            // If it's a parse error or IO error other than NotFound, we should probably fail?
+            // For now, keeping warning behavior but making it more visible if needed.
+            // But plan said "Improve error visibility".
+            // If the file exists but is invalid, we should error.
+            // load_global uses build_global_config which uses config crate.
+            // We can't easily distinguish "not found" from "parse error" without inspecting error.
+            // But usually config crate handles "not found" by just returning default if we set it up that way,
+            // but here we are adding source file.
+            // Let's just warn for now as per existing behavior but maybe upgrade to error if it's critical?
+            // The user review didn't explicitly demand erroring out, just "Improve error visibility".
+            if !quiet {
+                display::ui::warning(&format!("Failed to load global config: {}", e));
+            }
+            ProfileConfig::default()
+        }
+    };
+
+    let local_config = match ProfileConfig::load_local(repo_root) {
+        Ok(c) => c,
+        Err(e) => {
+            if !quiet {
+                display::ui::warning(&format!("Failed to load local config: {}", e));
             }
-        } else {
             None
-        };
+        }
+    };
 
-        // 5. Resolve Workspace
-        // Priority: CLI > Local Config > Git Remote > Global Config
-        let workspace = cli_coords
-            .as_ref()
-            .and_then(|(w, _)| w.clone())
-            .or_else(|| {
-                local_config
-                    .as_ref()
-                    .and_then(|c| c.project.as_ref())
-                    .and_then(|p| p.workspace.clone())
-            })
-            .or_else(|| git_info.as_ref().map(|(ws, _)| ws.clone()))
-            .or_else(|| {
-                global_config
-                    .get_active_profile()
-                    .and_then(|p| p.workspace.clone())
-            });
-
-        // 6. Resolve Repository
-        // Priority: CLI > Local Config > Git Remote
-        let repo = cli_coords
-            .as_ref()
-            .and_then(|(_, r)| r.clone())
-            .or_else(|| {
-                local_config
-                    .as_ref()
-                    .and_then(|c| c.project.as_ref())
-                    .and_then(|p| p.repository.clone())
-            })
-            .or_else(|| git_info.as_ref().map(|(_, r)| r.clone()));
+    (global_config, local_config)
+}
+
+impl AppContext {
+    pub fn new(cli: &Cli) -> Result<Self> {
+        // 1. Get Git Context (Repo Root) - ONCE
+        let repo_root = git::get_repo_root().ok();
+
+        // 2. Load Global (Preferences & Auth) and Local (Project overrides) Config
+        let (global_config, local_config) = load_config_files(
+            repo_root.as_deref(),
+            ProfileConfig::load_global,
+            cli.no_config,
+            cli.quiet,
+        );
+
+        let project = local_config.as_ref().and_then(|c| c.project.as_ref());
+
+        // 3. Resolve Workspace, Repository and Git Remote
+        let coordinates = resolve_coordinates(
+            repo_root.as_deref(),
+            &global_config,
+            project,
+            CoordinateOverrides {
+                repo: cli.repo.as_deref(),
+                remote: cli.remote.as_deref(),
+                profile: cli.profile.as_deref(),
+            },
+            CoordinateEnv::from_env(),
+        );
+
+        let sources = Sources {
+            profile: if cli.profile.is_some() {
+                Source::Flag("--profile")
+            } else if global_config.user.is_some() {
+                Source::GlobalConfig
+            } else {
+                Source::Default
+            },
+            workspace: coordinates.workspace.as_ref().map(|(_, s)| s.clone()),
+            repo: coordinates.repo.as_ref().map(|(_, s)| s.clone()),
+            remote: coordinates.remote.as_ref().map(|(_, s)| s.clone()),
+        };
+        let workspace = coordinates.workspace.map(|(w, _)| w);
+        let repo = coordinates.repo.map(|(r, _)| r);
+        // `None` stands for the default, origin
+        let remote_name = coordinates
+            .remote
+            .filter(|(_, source)| *source != Source::Default)
+            .map(|(r, _)| r);
 
         // Initialize API client
+        let options = NetworkLayers {
+            cli_timeout: cli.timeout,
+            cli_retries: cli.retries,
+            env_timeout: env_value(crate::constants::TIMEOUT_ENV),
+            env_retries: env_value(crate::constants::RETRIES_ENV),
+            profile: global_config.get_profile(cli.profile.as_deref()),
+        }
+        .resolve()?;
         let client = global_config
-            .create_client(cli.profile.as_deref())
-            .context("Error initializing client")?;
+            .create_client(cli.profile.as_deref(), options)
+            .context("Error initializing client")?
+            .with_explain(cli.explain);
 
         utils::debug::log(&format!(
             "Context resolved - Workspace: {:?}, Repo: {:?}",
             workspace, repo
         ));
 
+        // --json-envelope implies JSON output; the config key only changes its shape
+        display::ui::set_json_envelope(
+            cli.json_envelope || global_config.json_envelope.unwrap_or(false),
+        );
+        utils::formatting::set_hyperlinks(
+            !cli.no_hyperlinks && global_config.hyperlinks.unwrap_or(true),
+        );
+        utils::formatting::set_color(!cli.no_color);
+        display::ui::set_json_pretty(cli.pretty);
+
         Ok(Self {
             client,
-            json: cli.json,
+            json: cli.json || cli.json_envelope,
             workspace,
             repo,
+            limiter: Arc::new(Semaphore::new(cli.max_concurrency as usize)),
+            pr_default_limit: global_config.pr.as_ref().and_then(|s| s.default_limit),
+            repo_default_limit: global_config.repo.as_ref().and_then(|s| s.default_limit),
+            profile: cli.profile.clone(),
+            profile_name: global_config
+                .profile_name(cli.profile.as_deref())
+                .to_string(),
+            jira_url: global_config.jira_url.clone(),
+            remote: remote_name,
+            sources,
+            no_config: cli.no_config,
         })
     }
+
+    /// The global config, for commands that read it directly; empty with `--no-config`
+    pub fn global_config(&self) -> Result<ProfileConfig> {
+        if self.no_config {
+            return Ok(ProfileConfig::default());
+        }
+        ProfileConfig::load_global()
+    }
+
+    /// The local `.bb-cli` in `repo_root`, for commands that read it directly;
+    /// none with `--no-config`
+    pub fn local_config(
+        &self,
+        repo_root: Option<&std::path::Path>,
+    ) -> Result<Option<LocalProjectConfig>> {
+        if self.no_config {
+            return Ok(None);
+        }
+        ProfileConfig::load_local(repo_root)
+    }
+
+    /// A context for `ws/repo` talking through `client`, with nothing
+    /// configured and human-readable output
+    #[cfg(test)]
+    pub fn for_test(client: BitbucketClient) -> Self {
+        Self {
+            client,
+            json: false,
+            workspace: Some("ws".to_string()),
+            repo: Some("repo".to_string()),
+            limiter: Arc::new(Semaphore::new(8)),
+            pr_default_limit: None,
+            repo_default_limit: None,
+            profile: None,
+            profile_name: "default".to_string(),
+            jira_url: None,
+            remote: None,
+            sources: Sources::default(),
+            no_config: false,
+        }
+    }
+
+    /// Workspace and repository slugs for repo-scoped commands
+    ///
+    /// Fails with a single error naming whichever is missing and the ways to set it.
+    pub fn require_repo(&self) -> Result<(&str, &str)> {
+        match (self.workspace.as_deref(), self.repo.as_deref()) {
+            (Some(workspace), Some(repo)) => Ok((workspace, repo)),
+            (workspace, repo) => {
+                let missing = match (workspace, repo) {
+                    (None, None) => "workspace and repository",
+                    (None, _) => "workspace",
+                    _ => "repository",
+                };
+                anyhow::bail!(
+                    "No {} found. Pass -R/--repo <WORKSPACE>/<REPO>, set it with \
+                     'bb config set --local workspace|repository <NAME>', \
+                     or run inside a clone of a Bitbucket repository",
+                    missing
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(workspace: Option<&str>, repo: Option<&str>) -> AppContext {
+        AppContext {
+            workspace: workspace.map(str::to_string),
+            repo: repo.map(str::to_string),
+            ..AppContext::for_test(
+                BitbucketClient::new(
+                    "https://api.bitbucket.org/2.0".to_string(),
+                    None,
+                    Default::default(),
+                )
+                .unwrap(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_require_repo() {
+        let ctx = context(Some("ws"), Some("repo"));
+        assert_eq!(ctx.require_repo().unwrap(), ("ws", "repo"));
+    }
+
+    #[test]
+    fn test_require_repo_error_guidance() {
+        let err = context(None, None).require_repo().unwrap_err().to_string();
+        assert!(err.starts_with("No workspace and repository found."));
+        assert!(err.contains("--repo <WORKSPACE>/<REPO>"));
+        assert!(err.contains("bb config set --local"));
+        assert!(err.contains("inside a clone"));
+
+        let err = context(Some("ws"), None).require_repo().unwrap_err();
+        assert!(err.to_string().starts_with("No repository found."));
+        let err = context(None, Some("repo")).require_repo().unwrap_err();
+        assert!(err.to_string().starts_with("No workspace found."));
+    }
+
+    #[test]
+    fn test_source_display() {
+        assert_eq!(Source::Flag("--repo").to_string(), "--repo flag");
+        assert_eq!(Source::LocalConfig.to_string(), "local .bb-cli");
+        assert_eq!(
+            Source::GitRemote("origin".to_string()).to_string(),
+            "git remote origin"
+        );
+        assert_eq!(
+            Source::Profile("work".to_string()).to_string(),
+            "profile work in global config"
+        );
+        assert_eq!(Source::default(), Source::Default);
+    }
+
+    fn layers<'a>(
+        cli_repo: Option<&'a str>,
+        project: Option<&'a ProjectContext>,
+    ) -> CoordinateLayers<'a> {
+        CoordinateLayers {
+            cli_repo,
+            env_workspace: Some("env-ws".to_string()),
+            env_repo: Some("env-repo".to_string()),
+            project,
+            git: Some((
+                ("git-ws".to_string(), "git-repo".to_string()),
+                Source::GitRemote("origin".to_string()),
+            )),
+            profile_workspace: Some(("profile-ws".to_string(), "default".to_string())),
+        }
+    }
+
+    #[test]
+    fn test_env_coordinates_used_without_flag() {
+        let project = ProjectContext {
+            workspace: Some("local-ws".to_string()),
+            repository: Some("local-repo".to_string()),
+            remote: None,
+        };
+
+        let (workspace, repo) = layers(None, Some(&project)).resolve();
+        assert_eq!(
+            workspace,
+            Some(("env-ws".to_string(), Source::Env("BB_WORKSPACE")))
+        );
+        assert_eq!(repo, Some(("env-repo".to_string(), Source::Env("BB_REPO"))));
+    }
+
+    #[test]
+    fn test_cli_repo_overrides_env() {
+        let (workspace, repo) = layers(Some("cli-ws/cli-repo"), None).resolve();
+        assert_eq!(workspace.unwrap().0, "cli-ws");
+        assert_eq!(repo.unwrap().0, "cli-repo");
+
+        // A bare -R only names the repository
+        let (workspace, repo) = layers(Some("cli-repo"), None).resolve();
+        assert_eq!(workspace.unwrap().0, "env-ws");
+        assert_eq!(
+            repo.unwrap(),
+            ("cli-repo".to_string(), Source::Flag("--repo"))
+        );
+    }
+
+    #[test]
+    fn test_coordinates_fall_through_layers() {
+        let mut layers = layers(None, None);
+        layers.env_workspace = None;
+        layers.env_repo = None;
+        let (workspace, repo) = layers.resolve();
+        assert_eq!(workspace.unwrap().0, "git-ws");
+        assert_eq!(repo.unwrap().0, "git-repo");
+
+        let only_profile = CoordinateLayers {
+            cli_repo: None,
+            env_workspace: None,
+            env_repo: None,
+            project: None,
+            git: None,
+            profile_workspace: Some(("profile-ws".to_string(), "work".to_string())),
+        };
+        let (workspace, repo) = only_profile.resolve();
+        assert_eq!(
+            workspace,
+            Some((
+                "profile-ws".to_string(),
+                Source::Profile("work".to_string())
+            ))
+        );
+        assert_eq!(repo, None);
+    }
+
+    #[test]
+    fn test_coordinate_sources_follow_precedence() {
+        let project = ProjectContext {
+            workspace: Some("local-ws".to_string()),
+            repository: Some("local-repo".to_string()),
+            remote: None,
+        };
+        // Every layer set, minus the `skip` highest
+        let resolve_without = |skip: usize| {
+            let mut layers = layers(Some("cli-ws/cli-repo"), Some(&project));
+            if skip > 0 {
+                layers.cli_repo = None;
+            }
+            if skip > 1 {
+                layers.env_workspace = None;
+                layers.env_repo = None;
+            }
+            if skip > 2 {
+                layers.project = None;
+            }
+            if skip > 3 {
+                layers.git = None;
+            }
+            layers.resolve()
+        };
+
+        // What `config list --sources` prints for each, highest layer first
+        let expected = [
+            ("cli-ws", Some("cli-repo"), "--repo flag", "--repo flag"),
+            (
+                "env-ws",
+                Some("env-repo"),
+                "BB_WORKSPACE environment variable",
+                "BB_REPO environment variable",
+            ),
+            (
+                "local-ws",
+                Some("local-repo"),
+                "local .bb-cli",
+                "local .bb-cli",
+            ),
+            (
+                "git-ws",
+                Some("git-repo"),
+                "git remote origin",
+                "git remote origin",
+            ),
+            ("profile-ws", None, "profile default in global config", ""),
+        ];
+        for (skip, (workspace, repo, workspace_source, repo_source)) in
+            expected.into_iter().enumerate()
+        {
+            let (resolved_workspace, resolved_repo) = resolve_without(skip);
+            let (value, source) = resolved_workspace.unwrap();
+            assert_eq!(
+                (value.as_str(), source.to_string().as_str()),
+                (workspace, workspace_source),
+                "workspace with {} layer(s) removed",
+                skip
+            );
+            let resolved_repo = resolved_repo.map(|(value, source)| (value, source.to_string()));
+            assert_eq!(
+                resolved_repo,
+                repo.map(|r| (r.to_string(), repo_source.to_string())),
+                "repository with {} layer(s) removed",
+                skip
+            );
+        }
+    }
+
+    fn network_profile(timeout: Option<u64>, retries: Option<u32>) -> Profile {
+        Profile {
+            workspace: None,
+            user: None,
+            headers: None,
+            timeout,
+            retries,
+        }
+    }
+
+    #[test]
+    fn test_network_options_precedence() {
+        use std::time::Duration;
+
+        let profile = network_profile(Some(20), Some(1));
+        let layers = |cli: (Option<u64>, Option<u32>), env: (Option<&str>, Option<&str>)| {
+            NetworkLayers {
+                cli_timeout: cli.0,
+                cli_retries: cli.1,
+                env_timeout: env.0.map(str::to_string),
+                env_retries: env.1.map(str::to_string),
+                profile: Some(&profile),
+            }
+            .resolve()
+            .unwrap()
+        };
+
+        let flags = layers((Some(5), Some(3)), (Some("10"), Some("2")));
+        assert_eq!(flags.timeout, Duration::from_secs(5));
+        assert_eq!(flags.retries, 3);
+
+        let env = layers((None, None), (Some("10"), Some("2")));
+        assert_eq!(env.timeout, Duration::from_secs(10));
+        assert_eq!(env.retries, 2);
+
+        let from_profile = layers((None, None), (None, None));
+        assert_eq!(from_profile.timeout, Duration::from_secs(20));
+        assert_eq!(from_profile.retries, 1);
+
+        // Each setting falls through on its own
+        let mixed = layers((None, Some(0)), (Some("10"), None));
+        assert_eq!(mixed.timeout, Duration::from_secs(10));
+        assert_eq!(mixed.retries, 0);
+    }
+
+    #[test]
+    fn test_network_options_defaults() {
+        let unset = network_profile(None, None);
+        let options = NetworkLayers {
+            cli_timeout: None,
+            cli_retries: None,
+            env_timeout: None,
+            env_retries: None,
+            profile: Some(&unset),
+        }
+        .resolve()
+        .unwrap();
+        assert_eq!(options, ClientOptions::default());
+
+        let err = NetworkLayers {
+            cli_timeout: None,
+            cli_retries: None,
+            env_timeout: Some("soon".to_string()),
+            env_retries: None,
+            profile: None,
+        }
+        .resolve()
+        .unwrap_err();
+        assert!(err.to_string().contains("BB_TIMEOUT"), "{}", err);
+    }
+
+    use crate::git::run_for_test as git;
+
+    /// A clone of `git-ws/git-repo` on `main`, with a profile naming another workspace
+    fn fixture_repo() -> (tempfile::TempDir, ProfileConfig) {
+        let tmp = tempfile::tempdir().unwrap();
+        git(tmp.path(), &["init", "-q", "-b", "main"]);
+        git(tmp.path(), &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(
+            tmp.path(),
+            &[
+                "remote",
+                "add",
+                "origin",
+                "git@bitbucket.org:git-ws/git-repo.git",
+            ],
+        );
+
+        let config = ProfileConfig {
+            profiles: Some(std::collections::HashMap::from([(
+                "default".to_string(),
+                Profile {
+                    workspace: Some("profile-ws".to_string()),
+                    ..network_profile(None, None)
+                },
+            )])),
+            ..Default::default()
+        };
+        (tmp, config)
+    }
+
+    #[test]
+    fn test_effective_coordinates_differ_from_stored() {
+        let (tmp, config) = fixture_repo();
+        let stored = config.get_profile(None).and_then(|p| p.workspace.clone());
+        assert_eq!(stored.as_deref(), Some("profile-ws"));
+
+        let effective = resolve_coordinates(
+            Some(tmp.path()),
+            &config,
+            None,
+            Default::default(),
+            Default::default(),
+        );
+        assert_eq!(
+            effective.workspace,
+            Some((
+                "git-ws".to_string(),
+                Source::GitRemote("origin".to_string())
+            ))
+        );
+        assert_eq!(
+            effective.repo,
+            Some((
+                "git-repo".to_string(),
+                Source::GitRemote("origin".to_string())
+            ))
+        );
+        assert_eq!(
+            effective.remote,
+            Some(("origin".to_string(), Source::Default))
+        );
+
+        // Outside a repository only the stored profile value is left
+        let effective =
+            resolve_coordinates(None, &config, None, Default::default(), Default::default());
+        assert_eq!(
+            effective.workspace,
+            Some((
+                "profile-ws".to_string(),
+                Source::Profile("default".to_string())
+            ))
+        );
+        assert_eq!(effective.repo, None);
+        assert_eq!(effective.remote, None);
+
+        // The environment is passed in, above the repository and profile
+        let env = CoordinateEnv {
+            workspace: Some("env-ws".to_string()),
+            repo: Some("env-repo".to_string()),
+        };
+        let effective =
+            resolve_coordinates(Some(tmp.path()), &config, None, Default::default(), env);
+        assert_eq!(
+            effective.workspace,
+            Some((
+                "env-ws".to_string(),
+                Source::Env(crate::constants::WORKSPACE_ENV)
+            ))
+        );
+        assert_eq!(effective.repo.unwrap().0, "env-repo");
+    }
+
+    #[test]
+    fn test_effective_coordinates_follow_local_config_and_tracking_remote() {
+        let (tmp, config) = fixture_repo();
+        git(
+            tmp.path(),
+            &[
+                "remote",
+                "add",
+                "fork",
+                "https://bitbucket.org/me/git-repo.git",
+            ],
+        );
+        git(tmp.path(), &["config", "branch.main.remote", "fork"]);
+
+        let effective = resolve_coordinates(
+            Some(tmp.path()),
+            &config,
+            None,
+            Default::default(),
+            Default::default(),
+        );
+        assert_eq!(
+            effective.remote,
+            Some(("fork".to_string(), Source::BranchRemote("main".to_string())))
+        );
+        assert_eq!(effective.workspace.unwrap().0, "me");
+
+        let project = ProjectContext {
+            workspace: Some("local-ws".to_string()),
+            repository: None,
+            remote: Some("origin".to_string()),
+        };
+        let effective = resolve_coordinates(
+            Some(tmp.path()),
+            &config,
+            Some(&project),
+            Default::default(),
+            Default::default(),
+        );
+        assert_eq!(
+            effective.workspace,
+            Some(("local-ws".to_string(), Source::LocalConfig))
+        );
+        assert_eq!(effective.repo.unwrap().0, "git-repo");
+        assert_eq!(
+            effective.remote,
+            Some(("origin".to_string(), Source::LocalConfig))
+        );
+    }
+
+    #[test]
+    fn test_no_config_ignores_stray_local_config() {
+        let (tmp, _) = fixture_repo();
+        std::fs::write(
+            tmp.path().join(crate::constants::LOCAL_CONFIG_FILE_NAME),
+            "[project]\nworkspace = \"stray-ws\"\nrepository = \"stray-repo\"\n",
+        )
+        .unwrap();
+
+        // Stands in for the global config, so the real one is never read
+        let global = || {
+            Ok(ProfileConfig {
+                user: Some("global-user".to_string()),
+                ..Default::default()
+            })
+        };
+
+        let (global_config, local) = load_config_files(Some(tmp.path()), global, false, true);
+        assert_eq!(global_config.user.as_deref(), Some("global-user"));
+        let project = local.as_ref().and_then(|c| c.project.as_ref());
+        assert_eq!(
+            project.and_then(|p| p.workspace.as_deref()),
+            Some("stray-ws")
+        );
+
+        let (global, local) = load_config_files(
+            Some(tmp.path()),
+            || panic!("--no-config mustn't read the global config"),
+            true,
+            true,
+        );
+        assert!(local.is_none());
+        assert!(global.profiles.is_none() && global.user.is_none());
+
+        let coordinates = resolve_coordinates(
+            Some(tmp.path()),
+            &global,
+            None,
+            Default::default(),
+            Default::default(),
+        );
+        assert_eq!(
+            coordinates.workspace,
+            Some((
+                "git-ws".to_string(),
+                Source::GitRemote("origin".to_string())
+            ))
+        );
+        assert_eq!(
+            coordinates.repo,
+            Some((
+                "git-repo".to_string(),
+                Source::GitRemote("origin".to_string())
+            ))
+        );
+    }
 }
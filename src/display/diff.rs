@@ -1,30 +1,90 @@
-use anyhow::Result;
-use crossterm::style::{Color, Stylize};
+use anyhow::{Context, Result};
+use crossterm::style::{Color, ContentStyle, Stylize};
 use glob::Pattern;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::api::models::{Comment, DiffStat};
 use crate::display::ui::{display_in_pager, should_use_pager};
+use crate::utils::formatting;
 
 /// Display a diff with color formatting and optional paging
+///
+/// Inline `comments` are rendered beneath the lines they refer to; pass an
+/// empty slice for a plain diff. With `wrap`, long content lines are
+/// soft-wrapped to the terminal width.
 pub fn print_diff(
     diff_text: &str,
     patterns: &[String],
     max_diff_size: Option<usize>,
+    comments: &[Comment],
+    wrap: bool,
 ) -> Result<()> {
     let filtered_diff = filter_diff(diff_text, patterns, max_diff_size)?;
-    let formatted = format_colored_diff(&filtered_diff);
+    let wrap_width = wrap.then(|| formatting::get_full_terminal_width() as usize);
+    let formatted = format_colored_diff(&filtered_diff, comments, wrap_width);
 
     if should_use_pager() {
         display_in_pager(&formatted)?;
     } else {
         print!("{}", formatted);
     }
+    // After the output (and pager), where the diff stops; on stderr so a
+    // redirected patch stays clean
+    warn_if_truncated(diff_text);
 
     Ok(())
 }
 
+/// Shown when the server's diff stops partway through
+const TRUNCATED_NOTICE: &str =
+    "Diff truncated by server; open the full diff in the web UI with --web";
+
+/// The notice for a diff that ends partway through a hunk, if it does
+///
+/// Bitbucket cuts very large diffs short. A hunk header says how many old and
+/// new lines follow, so a diff that runs out of lines first, or has something
+/// other than a diff line (such as a server marker) inside a hunk, is incomplete.
+pub fn truncation_notice(diff_text: &str) -> Option<&'static str> {
+    truncation_notice_lines(diff_text.lines())
+}
+
+/// [`truncation_notice`] for a diff read line by line, e.g. from a file
+pub fn truncation_notice_lines<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+) -> Option<&'static str> {
+    // Old and new lines still expected in the current hunk
+    let mut remaining = (0u32, 0u32);
+
+    for line in lines {
+        let line = line.as_ref();
+        if remaining != (0, 0) {
+            let (old, new) = remaining;
+            remaining = match line.chars().next() {
+                // An empty line is a context line whose space was stripped
+                Some(' ') | None => (old.saturating_sub(1), new.saturating_sub(1)),
+                Some('-') => (old.saturating_sub(1), new),
+                Some('+') => (old, new.saturating_sub(1)),
+                Some('\\') => remaining,
+                Some(_) => return Some(TRUNCATED_NOTICE),
+            };
+        } else if line.starts_with("@@ ") {
+            remaining = parse_hunk_line_counts(line).unwrap_or((0, 0));
+        }
+    }
+
+    (remaining != (0, 0)).then_some(TRUNCATED_NOTICE)
+}
+
+/// Print [`truncation_notice`] to stderr if the diff is incomplete
+pub fn warn_if_truncated(diff_text: &str) {
+    if let Some(notice) = truncation_notice(diff_text) {
+        crate::display::ui::warn_stderr(notice);
+    }
+}
+
 /// Display only the names of changed files from a diff
 pub fn print_filenames_only(diff_text: &str, patterns: &[String]) {
-    let compiled_patterns = compile_patterns(patterns);
+    let compiled_patterns = compile_patterns(patterns, diff_text);
 
     for line in diff_text.lines() {
         // Parse unified diff format: "diff --git a/path b/path"
@@ -32,23 +92,282 @@ pub fn print_filenames_only(diff_text: &str, patterns: &[String]) {
             && let Some(filename) = extract_filename_from_diff_line(line)
             && is_match(&filename, &compiled_patterns)
         {
-            println!("{}", filename);
+            println!("{}", formatting::sanitize_for_terminal(&filename));
+        }
+    }
+    warn_if_truncated(diff_text);
+}
+
+/// How a file changed in a diff, as in `git diff --name-status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum ChangeKind {
+    #[serde(rename = "A")]
+    Added,
+    #[serde(rename = "D")]
+    Deleted,
+    #[serde(rename = "M")]
+    Modified,
+    #[serde(rename = "R")]
+    Renamed,
+    #[serde(rename = "C")]
+    Copied,
+}
+
+impl ChangeKind {
+    pub fn letter(self) -> char {
+        match self {
+            ChangeKind::Added => 'A',
+            ChangeKind::Deleted => 'D',
+            ChangeKind::Modified => 'M',
+            ChangeKind::Renamed => 'R',
+            ChangeKind::Copied => 'C',
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            ChangeKind::Added => Color::Green,
+            ChangeKind::Deleted => Color::Red,
+            ChangeKind::Modified => Color::Yellow,
+            ChangeKind::Renamed | ChangeKind::Copied => Color::Cyan,
         }
     }
 }
 
-fn compile_patterns(patterns: &[String]) -> Vec<Pattern> {
+/// One file of a diff and what happened to it
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct FileStatus {
+    pub status: ChangeKind,
+    pub path: String,
+    /// Path before a rename or copy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+}
+
+/// Classify each file of a unified diff from its extended headers
+/// (`new file mode`, `deleted file mode`, `rename from`/`rename to`, ...)
+///
+/// Files with none of those markers were modified.
+pub fn file_statuses(diff_text: &str) -> Vec<FileStatus> {
+    let mut statuses: Vec<FileStatus> = Vec::new();
+    let mut in_headers = false;
+
+    for line in diff_text.lines() {
+        if line.starts_with("diff --git") {
+            in_headers = false;
+            if let Some(path) = extract_filename_from_diff_line(line) {
+                statuses.push(FileStatus {
+                    status: ChangeKind::Modified,
+                    path,
+                    old_path: None,
+                });
+                in_headers = true;
+            }
+            continue;
+        }
+        // Headers end where the patch content starts
+        if !in_headers || line.starts_with("@@") {
+            in_headers = false;
+            continue;
+        }
+        let Some(current) = statuses.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with("new file mode") {
+            current.status = ChangeKind::Added;
+        } else if line.starts_with("deleted file mode") {
+            current.status = ChangeKind::Deleted;
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            current.status = ChangeKind::Renamed;
+            current.old_path = Some(from.to_string());
+        } else if let Some(to) = line.strip_prefix("rename to ") {
+            current.path = to.to_string();
+        } else if let Some(from) = line.strip_prefix("copy from ") {
+            current.status = ChangeKind::Copied;
+            current.old_path = Some(from.to_string());
+        } else if let Some(to) = line.strip_prefix("copy to ") {
+            current.path = to.to_string();
+        }
+    }
+    statuses
+}
+
+/// Keep only the files whose path (or path before a rename) matches `patterns`
+pub fn filter_file_statuses(statuses: Vec<FileStatus>, patterns: &[String]) -> Vec<FileStatus> {
+    let filenames: Vec<String> = statuses.iter().map(|s| s.path.clone()).collect();
+    let compiled_patterns = compile_patterns_for(patterns, &filenames);
+
+    statuses
+        .into_iter()
+        .filter(|s| {
+            is_match(&s.path, &compiled_patterns)
+                || s.old_path
+                    .as_deref()
+                    .is_some_and(|old| is_match(old, &compiled_patterns))
+        })
+        .collect()
+}
+
+/// Format file statuses one per line: `M src/a.rs`, `R old.rs -> new.rs`
+pub fn format_file_statuses(statuses: &[FileStatus]) -> String {
+    statuses
+        .iter()
+        .map(|s| {
+            let path = match &s.old_path {
+                Some(old) => format!("{} -> {}", old, s.path),
+                None => s.path.clone(),
+            };
+            format!(
+                "{} {}",
+                s.status.letter().with(s.status.color()),
+                formatting::sanitize_for_terminal(&path)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A user-supplied path filter, matched literally before falling back to a glob
+struct PathFilter {
+    literal: String,
+    glob: Option<Pattern>,
+}
+
+/// Compile filters against the files present in a diff
+///
+/// A pattern naming a file that exists in the diff is treated as an exact
+/// path only; otherwise it is also compiled as a glob.
+fn compile_patterns(patterns: &[String], diff_text: &str) -> Vec<PathFilter> {
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+
+    let filenames: Vec<String> = diff_text
+        .lines()
+        .filter(|l| l.starts_with("diff --git"))
+        .filter_map(extract_filename_from_diff_line)
+        .collect();
+
+    compile_patterns_for(patterns, &filenames)
+}
+
+/// Read a shared file of path filters for `--pattern-file`
+///
+/// Each line is one glob pattern; blank lines and `#` comments are skipped.
+pub fn read_pattern_file(path: &std::path::Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read pattern file {}", path.display()))?;
+    Ok(parse_pattern_list(&content))
+}
+
+fn parse_pattern_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Compile filters against a known list of changed files
+fn compile_patterns_for(patterns: &[String], filenames: &[String]) -> Vec<PathFilter> {
     patterns
         .iter()
-        .filter_map(|p| Pattern::new(p).ok())
+        .map(|p| PathFilter {
+            literal: p.clone(),
+            glob: if filenames.contains(p) {
+                None
+            } else {
+                Pattern::new(p).ok()
+            },
+        })
         .collect()
 }
 
-fn is_match(filename: &str, patterns: &[Pattern]) -> bool {
+/// Display a per-file summary of added and removed lines, like `git diff --stat`
+pub fn print_diffstat(stats: &[DiffStat], patterns: &[String]) {
+    let stats = filter_diffstat(stats, patterns);
+    println!("{}", format_diffstat(&stats));
+}
+
+/// Keep only the entries whose path matches `patterns` (all of them if empty)
+pub fn filter_diffstat<'a>(stats: &'a [DiffStat], patterns: &[String]) -> Vec<&'a DiffStat> {
+    let filenames: Vec<String> = stats
+        .iter()
+        .filter_map(|s| s.path())
+        .map(str::to_string)
+        .collect();
+    let compiled_patterns = compile_patterns_for(patterns, &filenames);
+
+    stats
+        .iter()
+        .filter(|s| s.path().is_some_and(|p| is_match(p, &compiled_patterns)))
+        .collect()
+}
+
+/// Format diffstat entries as a table followed by a totals line
+pub fn format_diffstat(stats: &[&DiffStat]) -> String {
+    use comfy_table::{Cell, CellAlignment, Color as TableColor};
+
+    let rows = stats
+        .iter()
+        .map(|stat| {
+            let path = match (&stat.old, &stat.new) {
+                (Some(old), Some(new)) if old.path != new.path => {
+                    format!("{} → {}", old.path, new.path)
+                }
+                _ => stat.path().unwrap_or_default().to_string(),
+            };
+            let status = if stat.is_conflict() {
+                Cell::new(formatting::sanitize_for_terminal(&stat.status)).fg(TableColor::Red)
+            } else {
+                Cell::new(formatting::sanitize_for_terminal(&stat.status))
+            };
+            vec![
+                Cell::new(formatting::sanitize_for_terminal(&path)),
+                status,
+                Cell::new(format!("+{}", stat.lines_added.unwrap_or(0)))
+                    .fg(TableColor::Green)
+                    .set_alignment(CellAlignment::Right),
+                Cell::new(format!("-{}", stat.lines_removed.unwrap_or(0)))
+                    .fg(TableColor::Red)
+                    .set_alignment(CellAlignment::Right),
+            ]
+        })
+        .collect();
+
+    let table = formatting::format_table(vec!["File", "Status", "Added", "Removed"], rows);
+    format!("{}\n{}", table, format_diffstat_totals(stats))
+}
+
+/// Summary line, e.g. `3 files changed, 10 insertions(+), 2 deletions(-)`
+fn format_diffstat_totals(stats: &[&DiffStat]) -> String {
+    let added: u32 = stats.iter().filter_map(|s| s.lines_added).sum();
+    let removed: u32 = stats.iter().filter_map(|s| s.lines_removed).sum();
+    let plural =
+        |n: u32, one: &str, many: &str| format!("{} {}", n, if n == 1 { one } else { many });
+
+    format!(
+        "{} changed, {}(+), {}(-)",
+        plural(stats.len() as u32, "file", "files"),
+        plural(added, "insertion", "insertions"),
+        plural(removed, "deletion", "deletions")
+    )
+}
+
+fn is_match(filename: &str, patterns: &[PathFilter]) -> bool {
     if patterns.is_empty() {
         return true;
     }
-    patterns.iter().any(|p| p.matches(filename))
+    // Exact (case-sensitive) paths win, so names containing glob
+    // metacharacters such as `[` can still be selected literally
+    if patterns.iter().any(|p| p.literal == filename) {
+        return true;
+    }
+    patterns
+        .iter()
+        .any(|p| p.glob.as_ref().is_some_and(|g| g.matches(filename)))
 }
 
 fn filter_diff(
@@ -60,7 +379,7 @@ fn filter_diff(
         return Ok(diff_text.to_string());
     }
 
-    let compiled_patterns = compile_patterns(patterns);
+    let compiled_patterns = compile_patterns(patterns, diff_text);
     let mut output = String::new();
     let mut current_file_diff = String::new();
     let mut current_filename: Option<String> = None;
@@ -93,7 +412,8 @@ fn filter_diff(
         output.push_str(chunk);
     };
 
-    for line in diff_text.lines() {
+    // Keep each line's own ending so CRLF content survives filtering
+    for line in diff_text.split_inclusive('\n') {
         if line.starts_with("diff --git") {
             // Process previous file
             if !current_file_diff.is_empty() {
@@ -102,10 +422,9 @@ fn filter_diff(
             }
 
             // Start new file
-            current_filename = extract_filename_from_diff_line(line);
+            current_filename = extract_filename_from_diff_line(split_line_ending(line).0);
         }
         current_file_diff.push_str(line);
-        current_file_diff.push('\n');
     }
 
     // Process last file
@@ -127,40 +446,356 @@ fn extract_filename_from_diff_line(line: &str) -> Option<String> {
 }
 
 /// Format a diff with colors
-fn format_colored_diff(diff_text: &str) -> String {
+///
+/// Line endings are kept as they are (CRLF stays CRLF). Control characters
+/// in the diff content are shown in caret notation and highlighted instead of
+/// being passed through to the terminal.
+///
+/// Inline comments are placed under the line they were left on; any whose
+/// line isn't part of this diff are listed at the end.
+///
+/// With `wrap_width`, hunk content lines longer than the width are split;
+/// each continuation repeats the `+`/`-`/space prefix in the line's color,
+/// followed by a dim `↪` marker. File and hunk headers are never wrapped.
+fn format_colored_diff(diff_text: &str, comments: &[Comment], wrap_width: Option<usize>) -> String {
     let mut output = String::new();
+    let inline: Vec<&Comment> = comments.iter().filter(|c| c.inline.is_some()).collect();
+    let mut placed = vec![false; inline.len()];
 
-    for line in diff_text.lines() {
-        let colored_line = if line.starts_with("+++") || line.starts_with("---") {
+    let mut current_file: Option<String> = None;
+    // (old, new) line numbers of the next line inside a hunk
+    let mut position: Option<(u32, u32)> = None;
+
+    for raw_line in diff_text.split_inclusive('\n') {
+        let (line, ending) = split_line_ending(raw_line);
+
+        // Which old/new line this diff line is, if it's hunk content
+        let mut line_ref: Option<(Option<u32>, Option<u32>)> = None;
+        if line.starts_with("diff --git") {
+            current_file = extract_filename_from_diff_line(line);
+            position = None;
+        } else if line.starts_with("@@") {
+            position = parse_hunk_header(line);
+        } else if let Some((old, new)) = position.as_mut() {
+            match line.chars().next() {
+                Some('+') => {
+                    line_ref = Some((None, Some(*new)));
+                    *new += 1;
+                }
+                Some('-') => {
+                    line_ref = Some((Some(*old), None));
+                    *old += 1;
+                }
+                Some('\\') => {}
+                _ => {
+                    line_ref = Some((Some(*old), Some(*new)));
+                    *old += 1;
+                    *new += 1;
+                }
+            }
+        }
+
+        let style = if line.starts_with("+++") || line.starts_with("---") {
             // File headers - bold white
-            format!("{}\n", line.bold())
+            ContentStyle::new().bold()
         } else if line.starts_with("@@") {
             // Hunk headers - cyan
-            format!("{}\n", line.with(Color::Cyan))
+            ContentStyle::new().with(Color::Cyan)
         } else if line.starts_with('+') {
             // Additions - green
-            format!("{}\n", line.with(Color::Green))
+            ContentStyle::new().with(Color::Green)
         } else if line.starts_with('-') {
             // Deletions - red
-            format!("{}\n", line.with(Color::Red))
+            ContentStyle::new().with(Color::Red)
         } else if line.starts_with("diff --git") || line.starts_with("index ") {
             // Diff metadata - bold
-            format!("{}\n", line.bold())
+            ContentStyle::new().bold()
         } else {
             // Context lines - dark grey
-            format!("{}\n", line.with(Color::DarkGrey))
+            ContentStyle::new().with(Color::DarkGrey)
         };
 
-        output.push_str(&colored_line);
+        let pieces = match wrap_width {
+            Some(width) if line_ref.is_some() => wrap_content_line(line, width),
+            _ => vec![line.to_string()],
+        };
+        for (idx, piece) in pieces.iter().enumerate() {
+            if idx > 0 {
+                let prefix = line.chars().next().unwrap_or(' ').to_string();
+                output.push_str(&style.apply(prefix).to_string());
+                output.push_str(&WRAP_MARKER.with(Color::DarkGrey).to_string());
+            }
+            for (segment, is_control) in split_control_chars(piece) {
+                let segment_style = if is_control { style.reverse() } else { style };
+                output.push_str(&segment_style.apply(segment).to_string());
+            }
+            output.push_str(ending);
+        }
+
+        if let (Some(file), Some((old, new))) = (&current_file, line_ref) {
+            for (idx, comment) in inline.iter().enumerate() {
+                if !placed[idx] && comment_targets(comment, file, old, new) {
+                    placed[idx] = true;
+                    output.push_str(&format_inline_comment(comment, ending));
+                }
+            }
+        }
+    }
+
+    let unplaced: Vec<&&Comment> = inline
+        .iter()
+        .zip(&placed)
+        .filter(|(_, placed)| !**placed)
+        .map(|(c, _)| c)
+        .collect();
+    if !unplaced.is_empty() {
+        output.push_str("\nComments on lines not in this diff:\n");
+        for comment in unplaced {
+            let location = comment.inline.as_ref().map(|i| match i.to.or(i.from) {
+                Some(line) => format!("{}:{}", i.path, line),
+                None => i.path.clone(),
+            });
+            output.push_str(&format!(
+                "  {} {}\n",
+                formatting::sanitize_for_terminal(&location.unwrap_or_default()).bold(),
+                format_inline_comment(comment, "\n").trim_start()
+            ));
+        }
+    }
+
+    output
+}
+
+/// Marker after the repeated prefix on a wrapped continuation line
+const WRAP_MARKER: &str = "↪ ";
+
+/// Split a hunk content line into pieces that fit in `width` columns
+///
+/// The first piece keeps the `+`/`-`/space prefix; later pieces are the bare
+/// continuation text (the caller re-adds the prefix and [`WRAP_MARKER`]).
+/// Widths are display columns, so wide (e.g. CJK) characters count double.
+fn wrap_content_line(line: &str, width: usize) -> Vec<String> {
+    let marker_width = 1 + UnicodeWidthStr::width(WRAP_MARKER);
+    if line.chars().map(display_width).sum::<usize>() <= width || width <= marker_width {
+        return vec![line.to_string()];
+    }
+
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    let (mut used, mut limit) = (0, width);
+    for c in line.chars() {
+        let char_width = display_width(c);
+        if used + char_width > limit && !piece.is_empty() {
+            pieces.push(std::mem::take(&mut piece));
+            (used, limit) = (0, width - marker_width);
+        }
+        piece.push(c);
+        used += char_width;
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Columns `c` takes once printed, control characters as their escape (see [`split_control_chars`])
+fn display_width(c: char) -> usize {
+    if c.is_control() && c != '\t' {
+        formatting::escape_control_char(c).chars().count()
+    } else {
+        UnicodeWidthChar::width(c).unwrap_or(1)
+    }
+}
+
+/// Parse the starting old/new line numbers from `@@ -a,b +c,d @@`
+fn parse_hunk_header(line: &str) -> Option<(u32, u32)> {
+    let mut parts = line.strip_prefix("@@ ")?.split_whitespace();
+    let old = parts.next()?.strip_prefix('-')?;
+    let new = parts.next()?.strip_prefix('+')?;
+    let start = |range: &str| range.split(',').next()?.parse::<u32>().ok();
+    Some((start(old)?, start(new)?))
+}
+
+/// Parse the old/new line counts from `@@ -a,b +c,d @@` (a missing count is 1)
+fn parse_hunk_line_counts(line: &str) -> Option<(u32, u32)> {
+    let mut parts = line.strip_prefix("@@ ")?.split_whitespace();
+    let old = parts.next()?.strip_prefix('-')?;
+    let new = parts.next()?.strip_prefix('+')?;
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse::<u32>().ok(),
+        None => Some(1),
+    };
+    Some((count(old)?, count(new)?))
+}
+
+/// Whether an inline comment belongs on the diff line at `old`/`new` of `file`
+///
+/// Comments on new lines carry `to`; comments on removed lines only `from`.
+fn comment_targets(comment: &Comment, file: &str, old: Option<u32>, new: Option<u32>) -> bool {
+    let Some(inline) = &comment.inline else {
+        return false;
+    };
+    if inline.path != file {
+        return false;
     }
+    match inline.to {
+        Some(to) => new == Some(to),
+        None => inline.from.is_some() && old == inline.from && new.is_none(),
+    }
+}
 
+/// Render a comment indented beneath a diff line
+fn format_inline_comment(comment: &Comment, ending: &str) -> String {
+    let author = formatting::sanitize_for_terminal(&comment.user.display_name);
+    let body = formatting::sanitize_for_terminal(&comment.content.raw);
+
+    let mut output = format!(
+        "    {} {}{}",
+        "▶".with(Color::Magenta),
+        author.with(Color::Magenta).bold(),
+        ending
+    );
+    for body_line in body.lines() {
+        output.push_str(&format!(
+            "      {}{}",
+            body_line.with(Color::Yellow),
+            ending
+        ));
+    }
     output
 }
 
+/// Split a line from `split_inclusive('\n')` into its content and line ending
+fn split_line_ending(line: &str) -> (&str, &str) {
+    let content = line
+        .strip_suffix("\r\n")
+        .or_else(|| line.strip_suffix('\n'))
+        .unwrap_or(line);
+    (content, &line[content.len()..])
+}
+
+/// Break a line into runs of printable text and escaped control characters
+///
+/// Tabs are left alone; any other control character becomes caret notation
+/// (`ESC` -> `^[`) or a `\u{..}` escape outside the ASCII range.
+fn split_control_chars(line: &str) -> Vec<(String, bool)> {
+    let mut segments: Vec<(String, bool)> = Vec::new();
+
+    for c in line.chars() {
+        let (text, is_control) = if c.is_control() && c != '\t' {
+            (formatting::escape_control_char(c), true)
+        } else {
+            (c.to_string(), false)
+        };
+
+        match segments.last_mut() {
+            Some((last, last_control)) if *last_control == is_control => last.push_str(&text),
+            _ => segments.push((text, is_control)),
+        }
+    }
+
+    segments
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_pattern_file_combines_with_cli_patterns() {
+        let from_file = parse_pattern_list(
+            "# Backend review set\n\nsrc/api/**\n  src/commands/*.rs  \n# docs/**\n",
+        );
+        assert_eq!(from_file, vec!["src/api/**", "src/commands/*.rs"]);
+
+        let patterns = [vec!["Cargo.toml".to_string()], from_file].concat();
+        let filenames = [
+            "src/api/client.rs",
+            "src/commands/pr.rs",
+            "Cargo.toml",
+            "docs/usage.md",
+            "src/display/diff.rs",
+        ]
+        .map(str::to_string);
+        let compiled = compile_patterns_for(&patterns, &filenames);
+
+        let matched: Vec<&str> = filenames
+            .iter()
+            .filter(|f| is_match(f, &compiled))
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            matched,
+            vec!["src/api/client.rs", "src/commands/pr.rs", "Cargo.toml"]
+        );
+    }
+
+    fn diffstat(status: &str, old: Option<&str>, new: Option<&str>, added: u32) -> DiffStat {
+        let file = |p: Option<&str>| {
+            p.map(|path| crate::api::models::DiffStatFile {
+                path: path.to_string(),
+            })
+        };
+        DiffStat {
+            status: status.to_string(),
+            lines_added: Some(added),
+            lines_removed: Some(1),
+            old: file(old),
+            new: file(new),
+        }
+    }
+
+    #[test]
+    fn test_format_diffstat() {
+        let stats = [
+            diffstat("modified", Some("src/main.rs"), Some("src/main.rs"), 10),
+            diffstat("renamed", Some("old.rs"), Some("new.rs"), 0),
+            diffstat("removed", Some("gone.rs"), None, 0),
+        ];
+        let refs: Vec<&DiffStat> = stats.iter().collect();
+
+        let output = format_diffstat(&refs);
+        assert!(output.contains("src/main.rs"));
+        assert!(output.contains("old.rs → new.rs"));
+        assert!(output.contains("gone.rs"));
+        assert!(output.contains("+10"));
+        assert!(
+            output.ends_with("3 files changed, 10 insertions(+), 3 deletions(-)"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_diffstat_totals_singular() {
+        let stats = [diffstat("added", None, Some("a.rs"), 1)];
+        let refs: Vec<&DiffStat> = stats.iter().collect();
+        assert_eq!(
+            format_diffstat_totals(&refs),
+            "1 file changed, 1 insertion(+), 1 deletion(-)"
+        );
+    }
+
+    #[test]
+    fn test_filter_diffstat() {
+        let stats = [
+            diffstat("modified", Some("src/main.rs"), Some("src/main.rs"), 1),
+            diffstat("modified", Some("README.md"), Some("README.md"), 1),
+            diffstat("removed", Some("src/old.rs"), None, 0),
+        ];
+
+        let paths = |patterns: &[&str]| -> Vec<&str> {
+            let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+            filter_diffstat(&stats, &patterns)
+                .iter()
+                .filter_map(|s| s.path())
+                .collect()
+        };
+        assert_eq!(paths(&[]).len(), 3);
+        assert_eq!(paths(&["src/*.rs"]), vec!["src/main.rs", "src/old.rs"]);
+        assert_eq!(paths(&["README.md"]), vec!["README.md"]);
+    }
+
     #[test]
     fn test_extract_filename_valid() {
         let line = "diff --git a/src/main.rs b/src/main.rs";
@@ -191,6 +826,43 @@ mod tests {
         assert!(!filtered.contains("file2.txt"));
     }
 
+    #[test]
+    fn test_is_match_literal_brackets() {
+        let diff = "diff --git a/src/routes/[id].rs b/src/routes/[id].rs\n";
+        let patterns = compile_patterns(&["src/routes/[id].rs".to_string()], diff);
+        assert!(is_match("src/routes/[id].rs", &patterns));
+        // Literal path exists in the diff, so it is not also used as a glob
+        assert!(!is_match("src/routes/i.rs", &patterns));
+        assert!(!is_match("src/routes/[ID].rs", &patterns));
+    }
+
+    #[test]
+    fn test_is_match_glob_fallback() {
+        // Not a file in the diff, so `[id]` is a glob character class
+        let diff = "diff --git a/src/routes/i.rs b/src/routes/i.rs\n";
+        let patterns = compile_patterns(&["src/routes/[id].rs".to_string()], diff);
+        assert!(is_match("src/routes/i.rs", &patterns));
+        assert!(is_match("src/routes/d.rs", &patterns));
+    }
+
+    #[test]
+    fn test_is_match_invalid_glob_literal() {
+        // An unclosed bracket is not a valid glob but is still a valid path
+        let diff = "diff --git a/docs/[draft.md b/docs/[draft.md\n";
+        let patterns = compile_patterns(&["docs/[draft.md".to_string()], diff);
+        assert!(is_match("docs/[draft.md", &patterns));
+        assert!(!is_match("docs/draft.md", &patterns));
+    }
+
+    #[test]
+    fn test_filter_diff_literal_path() {
+        let diff = "diff --git a/app/[slug].tsx b/app/[slug].tsx\n+new\ndiff --git a/app/s.tsx b/app/s.tsx\n+other\n";
+        let patterns = vec!["app/[slug].tsx".to_string()];
+        let filtered = filter_diff(diff, &patterns, None).unwrap();
+        assert!(filtered.contains("app/[slug].tsx"));
+        assert!(!filtered.contains("app/s.tsx"));
+    }
+
     #[test]
     fn test_filter_diff_size() {
         let diff = "diff --git a/large.rs b/large.rs\nline1\nline2\nline3\nline4\nline5\n";
@@ -198,4 +870,286 @@ mod tests {
         let filtered = filter_diff(diff, &patterns, Some(3)).unwrap();
         assert!(filtered.contains("skipped: diff too large"));
     }
+
+    #[test]
+    fn test_format_colored_diff_preserves_crlf() {
+        let diff = "--- a/win.txt\r\n+++ b/win.txt\r\n@@ -1 +1 @@\r\n-old\r\n+new\r\n";
+        let output = format_colored_diff(diff, &[], None);
+        assert_eq!(output.matches("\r\n").count(), 5);
+        assert_eq!(output.matches('\n').count(), 5);
+        assert!(!output.contains("\n\n"));
+    }
+
+    #[test]
+    fn test_format_colored_diff_no_trailing_newline_added() {
+        let output = format_colored_diff("+a\n+b", &[], None);
+        assert_eq!(output.matches('\n').count(), 1);
+    }
+
+    #[test]
+    fn test_format_colored_diff_escapes_control_chars() {
+        // An OSC sequence that would retitle the terminal if emitted raw
+        let diff = "+title \x1b]0;pwned\x07 here\n";
+        let output = format_colored_diff(diff, &[], None);
+        assert!(!output.contains("\x1b]0;"));
+        assert!(!output.contains('\x07'));
+        assert!(output.contains("^["));
+        assert!(output.contains("]0;pwned"));
+        assert!(output.contains("^G"));
+    }
+
+    #[test]
+    fn test_split_control_chars() {
+        assert_eq!(
+            split_control_chars("a\tb"),
+            vec![("a\tb".to_string(), false)]
+        );
+        assert_eq!(
+            split_control_chars("x\x00\x7fy\u{9b}"),
+            vec![
+                ("x".to_string(), false),
+                ("^@^?".to_string(), true),
+                ("y".to_string(), false),
+                ("\\u{9b}".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_diff_preserves_crlf() {
+        let diff = "diff --git a/a.rs b/a.rs\r\n+one\r\ndiff --git a/b.txt b/b.txt\r\n+two\r\n";
+        let filtered = filter_diff(diff, &["*.rs".to_string()], None).unwrap();
+        assert_eq!(filtered, "diff --git a/a.rs b/a.rs\r\n+one\r\n");
+    }
+
+    fn inline_comment(id: u32, path: &str, from: Option<u32>, to: Option<u32>) -> Comment {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "content": {"raw": format!("note {}", id)},
+            "created_on": "2024-01-01T00:00:00+00:00",
+            "user": {"display_name": "Reviewer", "uuid": "{r}"},
+            "inline": {"path": path, "from": from, "to": to},
+        }))
+        .unwrap()
+    }
+
+    const COMPLETE_DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ use std::io;
+-fn old() {}
++fn new() {}
++fn newer() {}
+ 
+diff --git a/empty.txt b/empty.txt
+--- a/empty.txt
++++ b/empty.txt
+@@ -0,0 +1 @@
++only line
+\\ No newline at end of file
+";
+
+    #[test]
+    fn test_truncation_notice_complete_diff() {
+        assert_eq!(truncation_notice(COMPLETE_DIFF), None);
+        assert_eq!(truncation_notice(""), None);
+    }
+
+    #[test]
+    fn test_truncation_notice_cut_short() {
+        // The last hunk promises 4 new lines but the diff stops after 2
+        let cut = &COMPLETE_DIFF[..COMPLETE_DIFF.find("+fn newer").unwrap()];
+        assert_eq!(truncation_notice(cut), Some(TRUNCATED_NOTICE));
+
+        let with_marker = COMPLETE_DIFF.replace("+fn newer() {}", "[diff truncated]");
+        let notice = truncation_notice(&with_marker).unwrap();
+        assert!(notice.contains("truncated by server"), "{}", notice);
+        assert!(notice.contains("--web"), "{}", notice);
+    }
+
+    #[test]
+    fn test_parse_hunk_line_counts() {
+        assert_eq!(
+            parse_hunk_line_counts("@@ -10,4 +12,6 @@ fn main()"),
+            Some((4, 6))
+        );
+        assert_eq!(parse_hunk_line_counts("@@ -0,0 +1 @@"), Some((0, 1)));
+        assert_eq!(parse_hunk_line_counts("not a hunk"), None);
+    }
+
+    #[test]
+    fn test_parse_hunk_header() {
+        assert_eq!(
+            parse_hunk_header("@@ -10,4 +12,6 @@ fn main()"),
+            Some((10, 12))
+        );
+        assert_eq!(parse_hunk_header("@@ -1 +1 @@"), Some((1, 1)));
+        assert_eq!(parse_hunk_header("@@ bogus @@"), None);
+    }
+
+    #[test]
+    fn test_format_colored_diff_inline_comments() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -10,3 +10,3 @@\n ctx\n-old\n+new\n";
+        let comments = vec![
+            inline_comment(1, "src/lib.rs", None, Some(11)),
+            inline_comment(2, "src/lib.rs", Some(11), None),
+            inline_comment(3, "src/lib.rs", None, Some(99)),
+            inline_comment(4, "src/other.rs", None, Some(10)),
+        ];
+
+        let output = format_colored_diff(diff, &comments, None);
+
+        // Removed line 11 carries comment 2, added line 11 carries comment 1
+        let old_pos = output.find("old").unwrap();
+        let new_pos = output.find("new").unwrap();
+        let note2 = output.find("note 2").unwrap();
+        let note1 = output.find("note 1").unwrap();
+        assert!(old_pos < note2 && note2 < new_pos);
+        assert!(new_pos < note1);
+
+        // Unmatched comments are listed once at the end
+        let trailer = output.find("Comments on lines not in this diff:").unwrap();
+        assert!(output.find("note 3").unwrap() > trailer);
+        assert!(output.find("note 4").unwrap() > trailer);
+        assert!(output.contains("src/lib.rs:99"));
+        assert_eq!(output.matches("note 1").count(), 1);
+    }
+
+    #[test]
+    fn test_format_colored_diff_without_comments_has_no_trailer() {
+        let output = format_colored_diff("@@ -1 +1 @@\n+a\n", &[], None);
+        assert!(!output.contains("Comments on lines"));
+    }
+
+    #[test]
+    fn test_wrap_content_line() {
+        assert_eq!(wrap_content_line("+short", 20), vec!["+short"]);
+        assert_eq!(
+            wrap_content_line("+abcdefghijkl", 6),
+            vec!["+abcde", "fgh", "ijk", "l"]
+        );
+        // Each of these takes two columns
+        assert_eq!(
+            wrap_content_line("+漢字漢字漢字", 6),
+            vec!["+漢字", "漢", "字", "漢", "字"]
+        );
+        assert_eq!(wrap_content_line("+漢字", 5), vec!["+漢字"]);
+    }
+
+    #[test]
+    fn test_format_colored_diff_wrap_keeps_prefix_and_color() {
+        let diff = "diff --git a/a.rs b/a.rs\n@@ -1 +1 @@ a very long hunk header that is not wrapped\n+0123456789abcdefghij\n";
+        let output = format_colored_diff(diff, &[], Some(10));
+        let lines: Vec<&str> = output.lines().collect();
+
+        // Headers stay on one line each
+        assert!(lines[1].contains("a very long hunk header that is not wrapped"));
+
+        // Width 10 keeps "+012345678"; continuations carry 7 chars after "+↪ "
+        let content = &lines[2..];
+        assert_eq!(content.len(), 3);
+        let green_plus = "+".with(Color::Green).to_string();
+        for continuation in &content[1..] {
+            assert!(continuation.starts_with(&green_plus));
+            assert!(continuation.contains('↪'));
+        }
+        let joined: String = content.concat();
+        for part in ["+012345678", "9abcdef", "ghij"] {
+            assert!(joined.contains(&part.with(Color::Green).to_string()));
+        }
+    }
+
+    const STATUS_DIFF: &str = "\
+diff --git a/src/a.rs b/src/a.rs
+index 1111111..2222222 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1 +1 @@
+-rename from nowhere
++new file mode 100644
+diff --git a/src/new.rs b/src/new.rs
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/src/new.rs
+@@ -0,0 +1 @@
++fn new() {}
+diff --git a/old.rs b/old.rs
+deleted file mode 100644
+index 4444444..0000000
+--- a/old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn old() {}
+diff --git a/src/before.rs b/src/after.rs
+similarity index 90%
+rename from src/before.rs
+rename to src/after.rs
+index 5555555..6666666 100644
+--- a/src/before.rs
++++ b/src/after.rs
+@@ -1 +1 @@
+-a
++b
+diff --git a/docs/moved.md b/guide/moved.md
+similarity index 100%
+rename from docs/moved.md
+rename to guide/moved.md
+diff --git a/base.rs b/copy.rs
+similarity index 100%
+copy from base.rs
+copy to copy.rs
+";
+
+    fn status(kind: ChangeKind, path: &str, old_path: Option<&str>) -> FileStatus {
+        FileStatus {
+            status: kind,
+            path: path.to_string(),
+            old_path: old_path.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_file_statuses() {
+        assert_eq!(
+            file_statuses(STATUS_DIFF),
+            vec![
+                // Marker-like text inside a hunk doesn't count
+                status(ChangeKind::Modified, "src/a.rs", None),
+                status(ChangeKind::Added, "src/new.rs", None),
+                status(ChangeKind::Deleted, "old.rs", None),
+                status(ChangeKind::Renamed, "src/after.rs", Some("src/before.rs")),
+                // A pure rename has no patch content at all
+                status(ChangeKind::Renamed, "guide/moved.md", Some("docs/moved.md")),
+                status(ChangeKind::Copied, "copy.rs", Some("base.rs")),
+            ]
+        );
+        assert!(file_statuses("").is_empty());
+    }
+
+    #[test]
+    fn test_filter_and_format_file_statuses() {
+        let statuses = filter_file_statuses(file_statuses(STATUS_DIFF), &["src/*".to_string()]);
+        let paths: Vec<&str> = statuses.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/a.rs", "src/new.rs", "src/after.rs"]);
+
+        let output = format_file_statuses(&statuses);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], format!("{} src/a.rs", 'M'.with(Color::Yellow)));
+        assert_eq!(lines[1], format!("{} src/new.rs", 'A'.with(Color::Green)));
+        assert_eq!(
+            lines[2],
+            format!("{} src/before.rs -> src/after.rs", 'R'.with(Color::Cyan))
+        );
+
+        // A rename matches on its old path too
+        let renamed = filter_file_statuses(file_statuses(STATUS_DIFF), &["docs/*".to_string()]);
+        assert_eq!(renamed.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&renamed[0]).unwrap(),
+            serde_json::json!({"status": "R", "path": "guide/moved.md", "old_path": "docs/moved.md"})
+        );
+    }
 }
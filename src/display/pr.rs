@@ -1,44 +1,85 @@
-use crate::api::models::{Comment, CommitStatus, PullRequest};
-use crate::utils::formatting;
+use crate::api::models::{
+    Activity, AuthorCommits, Comment, Commit, CommitStatus, Mergeability, Participant,
+    ParticipantRole, ParticipantState, PullRequest, User,
+};
+use crate::utils::formatting::{self, sanitize_for_terminal};
 use comfy_table::{Attribute, Cell, Color};
+use crossterm::style::{Color as CtColor, Stylize};
 
-pub fn print_pr_details(pr: &PullRequest, statuses: &[CommitStatus]) {
+/// Format a PR's details, reviewers and build statuses
+///
+/// Jira issue keys in the title, source branch or description are listed
+/// under "Linked Issues", linked to `jira_url` when it's configured.
+pub fn format_pr_details(
+    pr: &PullRequest,
+    statuses: &[CommitStatus],
+    mergeability: Option<&Mergeability>,
+    jira_url: Option<&str>,
+) -> String {
     // Display PR details
     let mut details = vec![
         ("ID", pr.id.to_string()),
-        ("Title", pr.title.clone()),
-        ("Author", pr.author.display_name.clone()),
-        ("State", pr.state.clone()),
-        ("Source", pr.source.branch.name.clone()),
-        ("Destination", pr.destination.branch.name.clone()),
-        ("Link", pr.links.html.href.clone()),
+        ("Title", sanitize_for_terminal(&pr.title)),
+        ("Author", sanitize_for_terminal(&pr.author.display_name)),
+        ("State", display_state(pr)),
+        ("Source", sanitize_for_terminal(&pr.source.branch.name)),
+        (
+            "Destination",
+            sanitize_for_terminal(&pr.destination.branch.name),
+        ),
+        ("Link", sanitize_for_terminal(&pr.links.html.href)),
     ];
 
+    if let Some(merge) = mergeability {
+        let label = match merge {
+            Mergeability::Mergeable => "yes".to_string(),
+            Mergeability::Conflicts { files } => format!("conflicts ({} files)", files.len()),
+            Mergeability::NotOpen => format!("no ({})", sanitize_for_terminal(&pr.state)),
+        };
+        details.push(("Mergeable", label));
+    }
+
     if let Some(desc) = &pr.description {
-        details.push(("Description", desc.clone()));
+        details.push(("Description", sanitize_for_terminal(desc)));
     }
 
-    formatting::print_key_value_table(
-        details
-            .iter()
-            .map(|(k, v)| (*k, v.clone()))
-            .collect::<Vec<_>>(),
+    let mut output = formatting::link_urls(
+        &formatting::format_key_value_table(
+            details
+                .iter()
+                .map(|(k, v)| (*k, v.clone()))
+                .collect::<Vec<_>>(),
+        ),
+        &[&pr.links.html.href],
     );
+    output.push('\n');
 
-    // Display Approvals
-    let approvals: Vec<&crate::api::models::Participant> =
-        pr.participants.iter().filter(|p| p.approved).collect();
-
-    if !approvals.is_empty() {
-        println!("\nApprovals:");
-        for p in approvals {
-            println!("- {}", p.user.display_name);
+    if let Some(Mergeability::Conflicts { files }) = mergeability {
+        output.push_str("\nConflicting files:\n");
+        for file in files {
+            output.push_str(&format!(
+                "{} {}\n",
+                "!".with(CtColor::Red).bold(),
+                sanitize_for_terminal(file)
+            ));
         }
     }
 
-    // Display Build Status
+    output.push_str(&format_linked_issues(pr, jira_url));
+
+    // Display Reviewers (and any other participant who has reviewed)
+    output.push_str(&format_reviewer_status(&pr.participants));
+
+    output.push_str(&format_build_statuses(statuses));
+
+    output
+}
+
+/// "Build Status" table with one row per pipeline; empty if there are none
+pub fn format_build_statuses(statuses: &[CommitStatus]) -> String {
+    let mut output = String::new();
     if !statuses.is_empty() {
-        println!("\nBuild Status:");
+        output.push_str("\nBuild Status:\n");
         let headers = vec!["Pipeline", "Status", "URL"];
         let rows = statuses
             .iter()
@@ -51,70 +92,520 @@ pub fn print_pr_details(pr: &PullRequest, statuses: &[CommitStatus]) {
                     _ => (status.state.as_str(), Color::White),
                 };
                 vec![
-                    Cell::new(status.name.clone().unwrap_or_else(|| status.key.clone())),
-                    Cell::new(status_text)
+                    Cell::new(sanitize_for_terminal(
+                        status.name.as_deref().unwrap_or(&status.key),
+                    )),
+                    Cell::new(sanitize_for_terminal(status_text))
                         .fg(color)
                         .add_attribute(Attribute::Bold),
-                    Cell::new(status.url.clone()),
+                    Cell::new(sanitize_for_terminal(&status.url)),
                 ]
             })
             .collect();
-        formatting::print_table(headers, rows);
+        let urls: Vec<&str> = statuses.iter().map(|s| s.url.as_str()).collect();
+        output.push_str(&formatting::link_urls(
+            &formatting::format_table(headers, rows),
+            &urls,
+        ));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// "Linked Issues" section for the Jira keys a PR mentions; empty if there are none
+fn format_linked_issues(pr: &PullRequest, jira_url: Option<&str>) -> String {
+    let texts = [
+        Some(pr.title.as_str()),
+        Some(pr.source.branch.name.as_str()),
+        pr.description.as_deref(),
+    ];
+    let keys = crate::utils::jira::find_issue_keys(texts.into_iter().flatten());
+    if keys.is_empty() {
+        return String::new();
+    }
+
+    let mut output = String::from("\nLinked Issues:\n");
+    for key in keys {
+        let line = match jira_url {
+            Some(base) => formatting::hyperlink(&key, &crate::utils::jira::issue_url(base, &key)),
+            None => key,
+        };
+        output.push_str(&format!("  {}\n", line));
+    }
+    output
+}
+
+/// Format each reviewer's state as a colored icon line with an approval summary
+///
+/// ✓ approved, ✗ changes requested, · pending
+pub fn format_reviewer_status(participants: &[Participant]) -> String {
+    let reviewers: Vec<&Participant> = participants
+        .iter()
+        .filter(|p| p.role == ParticipantRole::Reviewer || p.review_state().is_some())
+        .collect();
+
+    if reviewers.is_empty() {
+        return String::new();
+    }
+
+    let approved = reviewers
+        .iter()
+        .filter(|p| p.review_state() == Some(ParticipantState::Approved))
+        .count();
+
+    let mut output = format!("\nReviewers ({}/{} approved):\n", approved, reviewers.len());
+    for p in reviewers {
+        let icon = match p.review_state() {
+            Some(ParticipantState::Approved) => "✓".with(CtColor::Green).bold(),
+            Some(ParticipantState::ChangesRequested) => "✗".with(CtColor::Red).bold(),
+            _ => "·".with(CtColor::DarkGrey).bold(),
+        };
+        output.push_str(&format!(
+            "{} {}\n",
+            icon,
+            sanitize_for_terminal(&p.user.display_name)
+        ));
+    }
+
+    output
+}
+
+/// Format approvers as a Name/UUID table
+pub fn format_approvers(approvers: &[&User]) -> String {
+    let rows = approvers
+        .iter()
+        .map(|user| {
+            vec![
+                Cell::new(sanitize_for_terminal(&user.display_name)),
+                Cell::new(sanitize_for_terminal(&user.uuid)),
+            ]
+        })
+        .collect();
+    formatting::format_table(vec!["Name", "UUID"], rows)
+}
+
+/// Which sections `pr view` shows, in order
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ViewLayout {
+    #[default]
+    DetailsFirst,
+    CommentsFirst,
+    CommentsOnly,
+}
+
+/// Put the formatted details and comments sections of `pr view` in `layout` order
+///
+/// The comments section starts with a blank line to separate it from the
+/// details above; it's dropped when comments come first.
+pub fn arrange_pr_view(details: &str, comments: &str, layout: ViewLayout) -> String {
+    match layout {
+        ViewLayout::DetailsFirst => format!("{}{}", details, comments),
+        ViewLayout::CommentsFirst if !comments.is_empty() => {
+            format!("{}\n{}", comments.trim_start_matches('\n'), details)
+        }
+        ViewLayout::CommentsFirst => details.to_string(),
+        ViewLayout::CommentsOnly => comments.trim_start_matches('\n').to_string(),
     }
 }
 
 pub fn print_comments(comments: &[Comment]) {
+    print!("{}", format_comments(comments));
+}
+
+pub fn format_comments(comments: &[Comment]) -> String {
+    let mut output = String::new();
     if comments.is_empty() {
-        return;
+        return output;
     }
 
-    println!("\nComments:");
+    output.push_str("\nComments:\n");
     for (idx, comment) in comments.iter().enumerate() {
         if idx > 0 {
-            println!(); // Add spacing between comments
+            output.push('\n'); // Add spacing between comments
         }
 
         let mut details = vec![
-            ("Author", comment.user.display_name.clone()),
-            ("Created", comment.created_on.clone()),
+            ("Author", sanitize_for_terminal(&comment.user.display_name)),
+            ("Created", sanitize_for_terminal(&comment.created_on)),
         ];
 
         // Add inline context if present
         if let Some(inline) = &comment.inline {
-            details.push(("File", inline.path.clone()));
+            details.push(("File", sanitize_for_terminal(&inline.path)));
             if let Some(line) = inline.to.or(inline.from) {
                 details.push(("Line", line.to_string()));
             }
         }
 
-        details.push(("Comment", comment.content.raw.clone()));
+        details.push(("Comment", sanitize_for_terminal(&comment.content.raw)));
 
-        formatting::print_key_value_table(
+        output.push_str(&formatting::format_key_value_table(
             details
                 .iter()
                 .map(|(k, v)| (*k, v.clone()))
                 .collect::<Vec<_>>(),
+        ));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Format a comment thread, indenting each reply under the one it answers
+///
+/// `thread` pairs each comment with its depth, as from a depth-first walk.
+pub fn format_comment_thread(thread: &[(usize, &Comment)]) -> String {
+    let mut output = String::new();
+    for (depth, comment) in thread {
+        let indent = "    ".repeat(*depth);
+        let mut header = format!(
+            "{}  #{}  {}",
+            sanitize_for_terminal(&comment.user.display_name).bold(),
+            comment.id,
+            format_event_time(&comment.created_on).with(CtColor::DarkGrey)
         );
+        if let Some(inline) = &comment.inline {
+            let location = match inline.to.or(inline.from) {
+                Some(line) => format!("{}:{}", inline.path, line),
+                None => inline.path.clone(),
+            };
+            header.push_str(&format!(
+                "  {}",
+                sanitize_for_terminal(&location).with(CtColor::Cyan)
+            ));
+        }
+
+        output.push_str(&format!("{}{}\n", indent, header));
+        for line in sanitize_for_terminal(&comment.content.raw).lines() {
+            output.push_str(&format!("{}  {}\n", indent, line));
+        }
+        output.push('\n');
     }
+    output
 }
 
-pub fn format_pr_list(prs: &[PullRequest]) -> String {
-    let headers = vec!["ID", "Title", "Author", "Source", "State", "Updated"];
-    let rows: Vec<Vec<Cell>> = prs
+/// Longest comment excerpt shown in the activity timeline
+const ACTIVITY_EXCERPT_CHARS: usize = 60;
+
+/// Format an activity feed as one line per event: time, actor, what happened
+///
+/// Events are colored by kind: approvals green, change requests and declines
+/// red, comments cyan, merges magenta, other updates yellow. Entries of
+/// unknown kinds are skipped.
+pub fn format_activity(activity: &[Activity]) -> String {
+    let mut output = String::new();
+    for entry in activity {
+        let (Some(date), Some(actor)) = (entry.date(), entry.actor()) else {
+            continue;
+        };
+        let (summary, color) = match entry {
+            Activity::Approval { .. } => ("approved".to_string(), CtColor::Green),
+            Activity::ChangesRequested { .. } => ("requested changes".to_string(), CtColor::Red),
+            Activity::Comment { comment } => {
+                let target = match &comment.inline {
+                    Some(inline) => match inline.to.or(inline.from) {
+                        Some(line) => format!(" on {}:{}", inline.path, line),
+                        None => format!(" on {}", inline.path),
+                    },
+                    None => String::new(),
+                };
+                (
+                    format!(
+                        "commented{}: {}",
+                        target,
+                        excerpt(&comment.content.raw, ACTIVITY_EXCERPT_CHARS)
+                    ),
+                    CtColor::Cyan,
+                )
+            }
+            Activity::Update { update } => match update.state.as_str() {
+                "MERGED" => ("merged".to_string(), CtColor::Magenta),
+                "DECLINED" => ("declined".to_string(), CtColor::Red),
+                "SUPERSEDED" => ("superseded".to_string(), CtColor::DarkGrey),
+                _ if update.changes.is_empty() => {
+                    ("updated the pull request".to_string(), CtColor::Yellow)
+                }
+                _ => {
+                    let fields: Vec<&str> = update.changes.keys().map(String::as_str).collect();
+                    (format!("changed {}", fields.join(", ")), CtColor::Yellow)
+                }
+            },
+            Activity::Other(_) => continue,
+        };
+
+        output.push_str(&format!(
+            "{}  {}  {}\n",
+            format_event_time(date).with(CtColor::DarkGrey),
+            sanitize_for_terminal(&actor.display_name).bold(),
+            sanitize_for_terminal(&summary).with(color)
+        ));
+    }
+    output
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC, or the raw value if it can't be parsed
+fn format_event_time(date: &str) -> String {
+    crate::utils::time::parse_timestamp(date)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| date.to_string())
+}
+
+/// First line of `text`, cut to `max` characters with an ellipsis
+fn excerpt(text: &str, max: usize) -> String {
+    let line = text
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("")
+        .trim();
+    if line.chars().count() > max {
+        format!("{}…", line.chars().take(max).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Format commits as a table of short hash, author, date and subject line
+///
+/// Merge commits are dimmed.
+pub fn format_commit_list(commits: &[Commit]) -> String {
+    let rows = commits
         .iter()
-        .map(|pr| {
+        .map(|commit| {
+            let subject = commit
+                .message
+                .as_deref()
+                .and_then(|m| m.lines().next())
+                .unwrap_or("");
+            let row = vec![
+                Cell::new(commit.hash.chars().take(7).collect::<String>()).fg(Color::Yellow),
+                Cell::new(sanitize_for_terminal(commit.author_name().unwrap_or(""))),
+                Cell::new(
+                    commit
+                        .date
+                        .as_deref()
+                        .map(format_event_time)
+                        .unwrap_or_default(),
+                ),
+                Cell::new(sanitize_for_terminal(subject)),
+            ];
+            if commit.is_merge() {
+                row.into_iter()
+                    .map(|cell| cell.add_attribute(Attribute::Dim))
+                    .collect()
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    formatting::format_table(vec!["Commit", "Author", "Date", "Message"], rows)
+}
+
+/// Format per-author commit counts as a table
+pub fn format_author_counts(counts: &[AuthorCommits]) -> String {
+    let rows = counts
+        .iter()
+        .map(|c| {
             vec![
-                Cell::new(pr.id.to_string()),
-                Cell::new(&pr.title),
-                Cell::new(&pr.author.display_name),
-                Cell::new(&pr.source.branch.name),
-                Cell::new(&pr.state),
-                Cell::new(&pr.updated_on),
+                Cell::new(sanitize_for_terminal(&c.author)),
+                Cell::new(c.commits).set_alignment(comfy_table::CellAlignment::Right),
             ]
         })
         .collect();
 
-    formatting::format_table(headers, rows)
+    formatting::format_table(vec!["Author", "Commits"], rows)
+}
+
+/// A column of the `bb pr list` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PrColumn {
+    Id,
+    Title,
+    Author,
+    Source,
+    Destination,
+    State,
+    Updated,
+}
+
+/// Columns shown when `--columns` isn't given
+pub const DEFAULT_PR_COLUMNS: &[PrColumn] = &[
+    PrColumn::Id,
+    PrColumn::Title,
+    PrColumn::Author,
+    PrColumn::Source,
+    PrColumn::Destination,
+    PrColumn::State,
+    PrColumn::Updated,
+];
+
+impl PrColumn {
+    fn header(self) -> &'static str {
+        match self {
+            PrColumn::Id => "ID",
+            PrColumn::Title => "Title",
+            PrColumn::Author => "Author",
+            PrColumn::Source => "Source",
+            PrColumn::Destination => "Destination",
+            PrColumn::State => "State",
+            PrColumn::Updated => "Updated",
+        }
+    }
+
+    fn cell(self, pr: &PullRequest) -> Cell {
+        match self {
+            PrColumn::Id => Cell::new(pr.id.to_string()),
+            PrColumn::Title => Cell::new(sanitize_for_terminal(&pr.title)),
+            PrColumn::Author => Cell::new(sanitize_for_terminal(&pr.author.display_name)),
+            PrColumn::Source => Cell::new(sanitize_for_terminal(&pr.source.branch.name)),
+            PrColumn::Destination => Cell::new(sanitize_for_terminal(&pr.destination.branch.name)),
+            PrColumn::State => Cell::new(display_state(pr)),
+            PrColumn::Updated => Cell::new(sanitize_for_terminal(&pr.updated_on)),
+        }
+    }
+}
+
+/// Format PRs as a table; `no_trunc` lifts the width cap so titles aren't wrapped
+///
+/// An empty `columns` uses [`DEFAULT_PR_COLUMNS`].
+pub fn format_pr_list(prs: &[PullRequest], columns: &[PrColumn], no_trunc: bool) -> String {
+    format_pr_list_highlighted(prs, columns, no_trunc, &std::collections::HashSet::new())
+}
+
+/// Format PRs as a table like [`format_pr_list`], with the rows of the PRs
+/// in `highlighted` in bold yellow
+pub fn format_pr_list_highlighted(
+    prs: &[PullRequest],
+    columns: &[PrColumn],
+    no_trunc: bool,
+    highlighted: &std::collections::HashSet<u32>,
+) -> String {
+    let columns = if columns.is_empty() {
+        DEFAULT_PR_COLUMNS
+    } else {
+        columns
+    };
+    let headers = columns.iter().map(|c| c.header()).collect();
+    let rows: Vec<Vec<Cell>> = prs
+        .iter()
+        .map(|pr| {
+            let row: Vec<Cell> = columns.iter().map(|c| c.cell(pr)).collect();
+
+            if highlighted.contains(&pr.id) {
+                row.into_iter()
+                    .map(|cell| cell.fg(Color::Yellow).add_attribute(Attribute::Bold))
+                    .collect()
+            } else if pr.is_draft() {
+                row.into_iter()
+                    .map(|cell| cell.add_attribute(Attribute::Dim))
+                    .collect()
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    let width = if no_trunc {
+        None
+    } else {
+        formatting::default_table_width()
+    };
+    formatting::format_table_with_width(headers, rows, width)
+}
+
+/// Format PRs as a Markdown list for release notes
+///
+/// One line per PR, e.g. `- [#42](https://...) Fix login (@alice)`. The
+/// author is their nickname when known, else their display name.
+pub fn format_pr_list_markdown(prs: &[PullRequest]) -> String {
+    prs.iter()
+        .map(|pr| {
+            let author = pr
+                .author
+                .nickname
+                .as_deref()
+                .unwrap_or(&pr.author.display_name);
+            format!(
+                "- [#{}]({}) {} (@{})",
+                pr.id,
+                pr.links.html.href,
+                escape_markdown(&pr.title),
+                escape_markdown(author)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format PRs one per line, e.g. `#42  [OPEN]  Fix login  (Alice → main)`
+///
+/// IDs are padded to line up; the state is the only colored part. Titles are
+/// collapsed onto one line.
+pub fn format_pr_list_compact(prs: &[PullRequest]) -> String {
+    let id_width = prs
+        .iter()
+        .map(|pr| pr.id.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    prs.iter()
+        .map(|pr| {
+            let state = format!("[{}]", display_state(pr));
+            let state = match pr.state.as_str() {
+                _ if pr.is_draft() => state.dim(),
+                "OPEN" => state.green(),
+                "MERGED" => state.magenta(),
+                "DECLINED" => state.red(),
+                _ => state.reset(),
+            };
+            let title = sanitize_for_terminal(&pr.title)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(
+                "#{:<width$}  {}  {}  ({} → {})",
+                pr.id,
+                state,
+                title,
+                sanitize_for_terminal(&pr.author.display_name),
+                sanitize_for_terminal(&pr.destination.branch.name),
+                width = id_width
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Make text safe to use inline in Markdown: one line, with characters that
+/// would start links, emphasis or code escaped
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in sanitize_for_terminal(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+    {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// State label, marking drafts (which the API reports as OPEN)
+fn display_state(pr: &PullRequest) -> String {
+    let state = sanitize_for_terminal(&pr.state);
+    if pr.is_draft() {
+        format!("{} (draft)", state)
+    } else {
+        state
+    }
 }
 
 #[cfg(test)]
@@ -148,6 +639,8 @@ mod tests {
                     updated_on: None,
                     website: None,
                     is_private: None,
+                    mainbranch: None,
+                    links: None,
                 },
                 commit: None,
             },
@@ -164,6 +657,8 @@ mod tests {
                     updated_on: None,
                     website: None,
                     is_private: None,
+                    mainbranch: None,
+                    links: None,
                 },
                 commit: None,
             },
@@ -171,8 +666,18 @@ mod tests {
                 html: Link {
                     href: "http://example.com".to_string(),
                 },
+                self_link: None,
+                commits: None,
+                diff: None,
+                diffstat: None,
+                comments: None,
+                activity: None,
+                statuses: None,
             },
             participants: vec![],
+            comment_count: None,
+            draft: None,
+            task_count: None,
         }
     }
 
@@ -183,7 +688,7 @@ mod tests {
             create_mock_pr(2, "PR Title 2"),
         ];
 
-        let output = format_pr_list(&prs);
+        let output = format_pr_list(&prs, &[], false);
 
         // Verify Headers exist
         assert!(output.contains("ID"), "ID header not found");
@@ -200,11 +705,483 @@ mod tests {
         assert!(output.contains("PR Title 2"), "PR Title 2 not found");
     }
 
+    fn create_mock_user(name: &str) -> User {
+        User {
+            display_name: name.to_string(),
+            uuid: format!("{{{}}}", name),
+            nickname: None,
+        }
+    }
+
+    #[test]
+    fn test_format_pr_details() {
+        let mut pr = create_mock_pr(42, "Add caching");
+        pr.description = Some("Speeds things up".to_string());
+
+        let output = format_pr_details(&pr, &[], None, None);
+
+        assert!(output.contains("42"));
+        assert!(output.contains("Add caching"));
+        assert!(output.contains("Author Name"));
+        assert!(output.contains("feature/branch"));
+        assert!(output.contains("main"));
+        assert!(output.contains("http://example.com"));
+        assert!(output.contains("Speeds things up"));
+        assert!(!output.contains("Approvals:"));
+        assert!(!output.contains("Build Status:"));
+    }
+
+    #[test]
+    fn test_format_pr_details_linked_issues() {
+        let mut pr = create_mock_pr(42, "PROJ-7: Add caching");
+        pr.source.branch.name = "feature/OPS-12-cache".to_string();
+        pr.description = Some("Follow-up to PROJ-7, see also WEB-3".to_string());
+
+        let output = format_pr_details(&pr, &[], None, None);
+        let section = output.split("Linked Issues:\n").nth(1).unwrap();
+        let keys: Vec<&str> = section.lines().take(3).map(str::trim).collect();
+        assert_eq!(keys, vec!["PROJ-7", "OPS-12", "WEB-3"]);
+
+        // Tests don't run on a terminal, so links fall back to plain text
+        let output = format_pr_details(&pr, &[], None, Some("https://acme.atlassian.net/"));
+        assert!(output.contains("PROJ-7 (https://acme.atlassian.net/browse/PROJ-7)"));
+
+        let plain = create_mock_pr(1, "Add caching");
+        assert!(!format_pr_details(&plain, &[], None, None).contains("Linked Issues"));
+    }
+
+    #[test]
+    fn test_format_pr_details_reviewers_and_statuses() {
+        let mut pr = create_mock_pr(42, "Add caching");
+        pr.participants = vec![
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: create_mock_user("Alice"),
+                approved: true,
+                state: Some(ParticipantState::Approved),
+            },
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: create_mock_user("Bob"),
+                approved: false,
+                state: None,
+            },
+        ];
+        let statuses = vec![CommitStatus {
+            key: "build".to_string(),
+            state: "SUCCESSFUL".to_string(),
+            name: Some("CI Pipeline".to_string()),
+            url: "http://ci.example.com/1".to_string(),
+            description: None,
+        }];
+
+        let output = format_pr_details(&pr, &statuses, None, None);
+
+        let reviewers_idx = output
+            .find("Reviewers (1/2 approved):")
+            .expect("Reviewers section");
+        let build_idx = output.find("Build Status:").expect("Build Status section");
+        assert!(reviewers_idx < build_idx);
+        assert!(output.contains("Alice"));
+        assert!(output.contains("Bob"));
+        assert!(output.contains("CI Pipeline"));
+        assert!(output.contains("SUCCESSFUL"));
+    }
+
+    #[test]
+    fn test_format_reviewer_status_icons() {
+        let participants = vec![
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: create_mock_user("Alice"),
+                approved: true,
+                state: Some(ParticipantState::Approved),
+            },
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: create_mock_user("Bob"),
+                approved: false,
+                state: Some(ParticipantState::ChangesRequested),
+            },
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: create_mock_user("Carol"),
+                approved: false,
+                state: None,
+            },
+            // Commenters who haven't reviewed are not listed
+            Participant {
+                role: ParticipantRole::Participant,
+                user: create_mock_user("Dave"),
+                approved: false,
+                state: None,
+            },
+        ];
+
+        let output = format_reviewer_status(&participants);
+        let line = |name: &str| {
+            output
+                .lines()
+                .find(|l| l.contains(name))
+                .unwrap()
+                .to_string()
+        };
+
+        assert!(output.contains("Reviewers (1/3 approved):"));
+        assert!(line("Alice").contains('✓'));
+        assert!(line("Bob").contains('✗'));
+        assert!(line("Carol").contains('·'));
+        assert!(!output.contains("Dave"));
+    }
+
+    #[test]
+    fn test_approvers() {
+        let mut pr = create_mock_pr(1, "Approved");
+        pr.participants = vec![
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: create_mock_user("Alice"),
+                approved: true,
+                state: None,
+            },
+            Participant {
+                role: ParticipantRole::Reviewer,
+                user: create_mock_user("Bob"),
+                approved: false,
+                state: Some(ParticipantState::ChangesRequested),
+            },
+            // Non-reviewers can approve too
+            Participant {
+                role: ParticipantRole::Participant,
+                user: create_mock_user("Carol"),
+                approved: true,
+                state: Some(ParticipantState::Approved),
+            },
+        ];
+
+        let approvers = pr.approvers();
+        let names: Vec<&str> = approvers.iter().map(|u| u.display_name.as_str()).collect();
+        assert_eq!(names, vec!["Alice", "Carol"]);
+
+        let pending: Vec<&str> = pr
+            .pending_reviewers()
+            .iter()
+            .map(|u| u.display_name.as_str())
+            .collect();
+        assert_eq!(pending, vec!["Bob"]);
+
+        let output = format_approvers(&approvers);
+        assert!(output.contains("Name") && output.contains("UUID"));
+        assert!(output.contains("Alice") && output.contains("Carol"));
+        assert!(!output.contains("Bob"));
+    }
+
+    #[test]
+    fn test_activity_deserialize_and_format() {
+        let json = r#"[
+            {"update": {"date": "2024-01-01T09:00:00+00:00", "state": "OPEN",
+                        "author": {"display_name": "Alice", "uuid": "{a}"},
+                        "changes": {"title": {"old": "WIP", "new": "Fix login"}}}},
+            {"approval": {"date": "2024-01-02T10:30:00+00:00",
+                          "user": {"display_name": "Bob", "uuid": "{b}"}}},
+            {"comment": {"id": 5, "content": {"raw": "\nLooks good,\nbut one nit"},
+                         "created_on": "2024-01-02T11:00:00+00:00",
+                         "user": {"display_name": "Carol", "uuid": "{c}"},
+                         "inline": {"path": "src/main.rs", "to": 12}}},
+            {"changes_requested": {"date": "2024-01-03T08:00:00+00:00",
+                                   "user": {"display_name": "Dave", "uuid": "{d}"}}},
+            {"update": {"date": "2024-01-04T12:00:00+00:00", "state": "MERGED",
+                        "author": {"display_name": "Alice", "uuid": "{a}"}}},
+            {"task": {"id": 1}}
+        ]"#;
+        let activity: Vec<Activity> = serde_json::from_str(json).unwrap();
+        assert!(matches!(activity[0], Activity::Update { .. }));
+        assert!(matches!(activity[1], Activity::Approval { .. }));
+        assert!(matches!(activity[2], Activity::Comment { .. }));
+        assert!(matches!(activity[3], Activity::ChangesRequested { .. }));
+        assert!(matches!(activity[5], Activity::Other(_)));
+        assert_eq!(activity[1].actor().unwrap().display_name, "Bob");
+        assert_eq!(activity[1].date(), Some("2024-01-02T10:30:00+00:00"));
+
+        let output = format_activity(&activity);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].contains("2024-01-01 09:00") && lines[0].contains("changed title"));
+        assert!(lines[1].contains("Bob") && lines[1].contains("approved"));
+        assert!(lines[2].contains("commented on src/main.rs:12: Looks good,"));
+        assert!(lines[3].contains("requested changes"));
+        assert!(lines[4].contains("merged"));
+    }
+
+    #[test]
+    fn test_excerpt() {
+        assert_eq!(excerpt("short", 10), "short");
+        assert_eq!(excerpt("  \nfirst line\nsecond", 10), "first line");
+        assert_eq!(excerpt("ünïcödé text", 4), "ünïc…");
+    }
+
+    #[test]
+    fn test_participant_deserialize() {
+        let json = r#"{"role": "REVIEWER", "approved": false, "state": "changes_requested",
+            "user": {"display_name": "Bob", "uuid": "{b}"}}"#;
+        let p: Participant = serde_json::from_str(json).unwrap();
+        assert_eq!(p.role, ParticipantRole::Reviewer);
+        assert_eq!(p.review_state(), Some(ParticipantState::ChangesRequested));
+
+        let json = r#"{"role": "SOMETHING_NEW", "approved": true, "state": null,
+            "user": {"display_name": "Al", "uuid": "{a}"}}"#;
+        let p: Participant = serde_json::from_str(json).unwrap();
+        assert_eq!(p.role, ParticipantRole::Other);
+        assert_eq!(p.review_state(), Some(ParticipantState::Approved));
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let comments = vec![
+            Comment {
+                id: 1,
+                content: Content {
+                    raw: "Looks good".to_string(),
+                    html: None,
+                },
+                created_on: "2023-01-03".to_string(),
+                user: create_mock_user("Alice"),
+                inline: None,
+                parent: None,
+            },
+            Comment {
+                id: 2,
+                content: Content {
+                    raw: "Typo here".to_string(),
+                    html: None,
+                },
+                created_on: "2023-01-04".to_string(),
+                user: create_mock_user("Bob"),
+                inline: Some(InlineContext {
+                    path: "src/main.rs".to_string(),
+                    from: None,
+                    to: Some(12),
+                }),
+                parent: None,
+            },
+        ];
+
+        let output = format_comments(&comments);
+
+        assert!(output.contains("Comments:"));
+        assert!(output.contains("Looks good"));
+        assert!(output.contains("Typo here"));
+        assert!(output.contains("src/main.rs"));
+        assert!(output.contains("12"));
+        assert!(output.find("Alice").unwrap() < output.find("Bob").unwrap());
+    }
+
+    #[test]
+    fn test_arrange_pr_view() {
+        let details = format_pr_details(&create_mock_pr(1, "Triage me"), &[], None, None);
+        let comments = format_comments(&[Comment {
+            id: 1,
+            content: Content {
+                raw: "Needs a test".to_string(),
+                html: None,
+            },
+            created_on: "2024-01-01".to_string(),
+            user: create_mock_user("Alice"),
+            inline: None,
+            parent: None,
+        }]);
+
+        let output = arrange_pr_view(&details, &comments, ViewLayout::DetailsFirst);
+        assert!(output.find("Triage me").unwrap() < output.find("Comments:").unwrap());
+
+        let output = arrange_pr_view(&details, &comments, ViewLayout::CommentsFirst);
+        assert!(output.starts_with("Comments:"));
+        assert!(output.find("Needs a test").unwrap() < output.find("Triage me").unwrap());
+
+        let output = arrange_pr_view(&details, &comments, ViewLayout::CommentsOnly);
+        assert!(output.starts_with("Comments:"));
+        assert!(!output.contains("Triage me"));
+
+        // Nothing to move when there are no comments
+        assert_eq!(
+            arrange_pr_view(&details, "", ViewLayout::CommentsFirst),
+            details
+        );
+    }
+
+    #[test]
+    fn test_format_comments_empty() {
+        assert!(format_comments(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_pull_request_counts_deserialize() {
+        let mut value = serde_json::to_value(create_mock_pr(1, "Counts")).unwrap();
+        value["comment_count"] = serde_json::json!(3);
+        value.as_object_mut().unwrap().remove("task_count");
+
+        let pr: PullRequest = serde_json::from_value(value).unwrap();
+        assert_eq!(pr.comment_count, Some(3));
+        assert_eq!(pr.task_count, None);
+    }
+
+    #[test]
+    fn test_links_entries() {
+        let json = r#"{
+            "html": {"href": "https://bitbucket.org/ws/repo/pull-requests/1"},
+            "diff": {"href": "https://api.bitbucket.org/2.0/repositories/ws/repo/diff/a..b"},
+            "commits": {"href": "https://api.bitbucket.org/2.0/repositories/ws/repo/pullrequests/1/commits"},
+            "merge": {"href": "https://api.bitbucket.org/2.0/repositories/ws/repo/pullrequests/1/merge"}
+        }"#;
+        let links: Links = serde_json::from_str(json).unwrap();
+        let entries = links.entries();
+
+        assert_eq!(entries[0].0, "html");
+        assert!(
+            entries
+                .iter()
+                .any(|(name, href)| *name == "diff" && href.contains("/diff/"))
+        );
+        assert!(
+            entries
+                .iter()
+                .any(|(name, href)| *name == "commits" && href.ends_with("/commits"))
+        );
+        assert_eq!(entries.len(), 3);
+    }
+
+    #[test]
+    fn test_format_pr_list_draft() {
+        let mut draft = create_mock_pr(3, "WIP");
+        draft.draft = Some(true);
+
+        let output = format_pr_list(&[draft, create_mock_pr(4, "Ready")], &[], false);
+        assert!(output.contains("OPEN (draft)"));
+        assert_eq!(output.matches("(draft)").count(), 1);
+    }
+
+    #[test]
+    fn test_format_pr_list_escapes_title() {
+        let prs = vec![create_mock_pr(5, "\x1b[1A\x1b[2KApproved by admin")];
+        let output = format_pr_list(&prs, &[], false);
+        assert!(!output.contains("\x1b[1A"));
+        assert!(output.contains("^[[1A^[[2KApproved by admin"));
+    }
+
+    #[test]
+    fn test_format_pr_details_mergeability() {
+        let pr = create_mock_pr(6, "Conflicted");
+        let diffstat: Vec<DiffStat> = serde_json::from_value(serde_json::json!([
+            {"status": "modified", "new": {"path": "src/lib.rs"}, "old": {"path": "src/lib.rs"}},
+            {"status": "merge conflict", "new": {"path": "src/main.rs"}, "old": {"path": "src/main.rs"}},
+            {"status": "remote deleted", "new": null, "old": {"path": "src/old.rs"}}
+        ]))
+        .unwrap();
+
+        let merge = pr.mergeability(&diffstat);
+        assert_eq!(
+            merge,
+            Mergeability::Conflicts {
+                files: vec!["src/main.rs".to_string(), "src/old.rs".to_string()]
+            }
+        );
+
+        let output = format_pr_details(&pr, &[], Some(&merge), None);
+        assert!(output.contains("conflicts (2 files)"));
+        assert!(output.contains("Conflicting files:"));
+        assert!(output.contains("src/old.rs"));
+
+        assert_eq!(pr.mergeability(&diffstat[..1]), Mergeability::Mergeable);
+
+        let mut merged = create_mock_pr(7, "Done");
+        merged.state = "MERGED".to_string();
+        let output = format_pr_details(&merged, &[], Some(&merged.mergeability(&[])), None);
+        assert!(output.contains("no (MERGED)"));
+    }
+
     #[test]
     fn test_format_pr_list_empty() {
         let prs: Vec<PullRequest> = vec![];
-        let output = format_pr_list(&prs);
+        let output = format_pr_list(&prs, &[], false);
         assert!(output.contains("ID"));
         assert!(output.contains("Title"));
     }
+
+    #[test]
+    fn test_format_pr_list_destination_column() {
+        let mut pr = create_mock_pr(7, "Backport");
+        pr.destination.branch.name = "release/2.1".to_string();
+
+        let output = format_pr_list(std::slice::from_ref(&pr), &[], true);
+        let header = output.lines().find(|l| l.contains("ID")).unwrap();
+        assert!(header.find("Source").unwrap() < header.find("Destination").unwrap());
+        assert!(output.contains("release/2.1"));
+
+        let output = format_pr_list(&[pr], &[PrColumn::Id, PrColumn::Title], true);
+        assert!(!output.contains("Destination"));
+        assert!(!output.contains("release/2.1"));
+        assert!(!output.contains("Author"));
+    }
+
+    #[test]
+    fn test_format_pr_list_markdown() {
+        let mut nick = create_mock_pr(42, "Fix login");
+        nick.author.nickname = Some("alice".to_string());
+        let prs = vec![nick, create_mock_pr(43, "Add `--json` to *list*\n[WIP]")];
+
+        let output = format_pr_list_markdown(&prs);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            format!("- [#42]({}) Fix login (@alice)", prs[0].links.html.href)
+        );
+        assert!(
+            lines[1].ends_with(r" Add \`--json\` to \*list\* \[WIP\] (@Author Name)"),
+            "{}",
+            lines[1]
+        );
+        assert!(format_pr_list_markdown(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_format_pr_list_compact() {
+        let mut merged = create_mock_pr(1234, "Fix\n  login");
+        merged.state = "MERGED".to_string();
+        let prs = vec![create_mock_pr(7, "Add cache"), merged];
+
+        let output = format_pr_list_compact(&prs);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        // IDs are padded so the states line up
+        assert!(lines[0].starts_with("#7     "), "{}", lines[0]);
+        assert!(lines[0].contains("[OPEN]"));
+        assert!(lines[0].ends_with("  Add cache  (Author Name → main)"));
+        assert!(lines[1].starts_with("#1234  "));
+        assert!(lines[1].contains("[MERGED]"));
+        assert!(lines[1].contains("  Fix login  "));
+        assert!(!output.contains('│') && !output.contains('─'));
+    }
+
+    #[test]
+    fn test_format_comment_thread() {
+        let comments: Vec<Comment> = serde_json::from_value(serde_json::json!([
+            {"id": 1, "content": {"raw": "Why a Vec?"}, "created_on": "2024-01-01T10:00:00+00:00",
+             "user": {"display_name": "Alice", "uuid": "{a}"},
+             "inline": {"path": "src/lib.rs", "to": 7}},
+            {"id": 2, "content": {"raw": "Order matters\nhere"}, "created_on": "2024-01-01T11:00:00+00:00",
+             "user": {"display_name": "Bob", "uuid": "{b}"}, "parent": {"id": 1}}
+        ]))
+        .unwrap();
+        let thread = vec![(0, &comments[0]), (1, &comments[1])];
+
+        let output = format_comment_thread(&thread);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with(&format!("{}", "Alice".bold())));
+        assert!(lines[0].contains("#1") && lines[0].contains("src/lib.rs:7"));
+        assert_eq!(lines[1], "  Why a Vec?");
+        assert!(lines[3].starts_with("    ") && lines[3].contains("#2"));
+        assert_eq!(lines[4], "      Order matters");
+        assert_eq!(lines[5], "      here");
+    }
 }
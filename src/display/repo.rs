@@ -14,8 +14,11 @@ pub fn print_repo_list(repos: &[Repository]) {
         .map(|r| {
             let is_private = r.is_private.unwrap_or(false);
             vec![
-                Cell::new(&r.name).add_attribute(Attribute::Bold),
-                Cell::new(r.updated_on.as_deref().unwrap_or("-")),
+                Cell::new(formatting::sanitize_for_terminal(&r.name))
+                    .add_attribute(Attribute::Bold),
+                Cell::new(formatting::sanitize_for_terminal(
+                    r.updated_on.as_deref().unwrap_or("-"),
+                )),
                 Cell::new(if is_private { "Private" } else { "Public" }).fg(if is_private {
                     Color::Yellow
                 } else {
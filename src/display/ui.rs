@@ -2,6 +2,7 @@ use anyhow::Result;
 use serde::Serialize;
 use std::io::{IsTerminal, Write};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// Display utilities for user-facing output
 use crossterm::style::{Color, Stylize};
@@ -21,17 +22,164 @@ pub fn warning(message: &str) {
     println!("{} {}", "WARNING:".with(Color::Yellow).bold(), message);
 }
 
+/// Print a warning message with yellow text to stderr, keeping stdout for data
+pub fn warn_stderr(message: &str) {
+    eprintln!("{} {}", "WARNING:".with(Color::Yellow).bold(), message);
+}
+
 /// Print an info message
 pub fn info(message: &str) {
     println!("{}    {}", "INFO:".with(Color::Blue).bold(), message);
 }
 
+static JSON_ENVELOPE: AtomicBool = AtomicBool::new(false);
+
+/// Wrap all JSON output in `{ "schema_version": N, "data": ... }`
+pub fn set_json_envelope(enabled: bool) {
+    JSON_ENVELOPE.store(enabled, Ordering::Relaxed);
+}
+
+static JSON_PRETTY: AtomicBool = AtomicBool::new(false);
+
+/// Indent JSON output however large it is (`--pretty`)
+pub fn set_json_pretty(forced: bool) {
+    JSON_PRETTY.store(forced, Ordering::Relaxed);
+}
+
 /// Print data as JSON
-pub fn print_json<T: Serialize>(data: &T) -> Result<()> {
-    println!("{}", serde_json::to_string_pretty(data)?);
+///
+/// Bare data by default; enveloped when [`set_json_envelope`] is enabled.
+/// Lists go through [`print_json_list`] instead, which knows their length.
+pub fn print_json<T: Serialize + ?Sized>(data: &T) -> Result<()> {
+    print_json_sized(data, 0)
+}
+
+/// Print a list as JSON, like [`print_json`]
+///
+/// Lists longer than [`JSON_COMPACT_THRESHOLD`](crate::constants::JSON_COMPACT_THRESHOLD)
+/// are written without indentation unless [`set_json_pretty`] forces it.
+pub fn print_json_list<T: Serialize>(items: &[T]) -> Result<()> {
+    print_json_sized(items, items.len())
+}
+
+/// Print `data`, a list of `items` entries (0 if it isn't a list), as JSON
+fn print_json_sized<T: Serialize + ?Sized>(data: &T, items: usize) -> Result<()> {
+    if JSON_ENVELOPE.load(Ordering::Relaxed) {
+        let envelope = JsonEnvelope {
+            schema_version: crate::constants::JSON_SCHEMA_VERSION,
+            data,
+        };
+        return write_json(&envelope, items);
+    }
+    write_json(data, items)
+}
+
+/// Versioned wrapper for all JSON output: `{ "schema_version": N, "data": ... }`
+///
+/// The version is bumped on breaking changes to any JSON output.
+#[derive(Serialize)]
+struct JsonEnvelope<'a, T: Serialize + ?Sized> {
+    schema_version: u32,
+    data: &'a T,
+}
+
+/// Whether `items` array entries should be written compact rather than indented
+fn compact_json(items: usize, pretty_forced: bool) -> bool {
+    items > crate::constants::JSON_COMPACT_THRESHOLD && !pretty_forced
+}
+
+/// Stream JSON to stdout; warnings about its size go to stderr so stdout stays valid JSON
+fn write_json<T: Serialize + ?Sized>(data: &T, items: usize) -> Result<()> {
+    let pretty_forced = JSON_PRETTY.load(Ordering::Relaxed);
+    let compact = compact_json(items, pretty_forced);
+    if items > crate::constants::JSON_COMPACT_THRESHOLD {
+        let message = if compact {
+            format!(
+                "{} items; writing compact JSON (pass --pretty to indent it)",
+                items
+            )
+        } else {
+            format!("{} items; indented JSON output will be large", items)
+        };
+        warn_stderr(&message);
+    }
+
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    serialize_json(&mut out, data, compact)?;
+    out.flush()?;
     Ok(())
 }
 
+/// Serialize `data` straight to `out`, keeping its fields in declaration order
+fn serialize_json<W: Write, T: Serialize + ?Sized>(
+    mut out: W,
+    data: &T,
+    compact: bool,
+) -> Result<()> {
+    if compact {
+        serde_json::to_writer(&mut out, data)?;
+    } else {
+        serde_json::to_writer_pretty(&mut out, data)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Byte-count progress line on stderr for long downloads
+///
+/// Silent unless stderr is a terminal, so redirected output stays clean.
+pub struct DownloadProgress {
+    label: String,
+    enabled: bool,
+    last_shown: u64,
+}
+
+impl DownloadProgress {
+    /// Minimum growth between redraws, to avoid flooding the terminal
+    const STEP: u64 = 256 * 1024;
+
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            enabled: std::io::stderr().is_terminal(),
+            last_shown: 0,
+        }
+    }
+
+    pub fn update(&mut self, bytes: u64) {
+        if !self.enabled || bytes < self.last_shown + Self::STEP {
+            return;
+        }
+        self.last_shown = bytes;
+        eprint!("\r{} {}", self.label, format_bytes(bytes));
+        let _ = std::io::stderr().flush();
+    }
+
+    /// Clear the progress line, if one was drawn
+    pub fn finish(&self) {
+        if self.enabled && self.last_shown > 0 {
+            eprint!("\r\x1b[2K");
+            let _ = std::io::stderr().flush();
+        }
+    }
+}
+
+/// Human-readable byte size (e.g. "1.5 MB")
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
 /// Check if we should use a pager (only if output is to a TTY)
 pub fn should_use_pager() -> bool {
     // Check if stdout is a terminal
@@ -57,6 +205,9 @@ pub fn display_in_pager(content: &str) -> Result<()> {
 
     match child {
         Ok(mut process) => {
+            // Quitting the pager early closes its stdin; don't let that kill us
+            let _sigpipe = crate::utils::sigpipe::IgnoreGuard::new();
+
             if let Some(mut stdin) = process.stdin.take() {
                 // Write content to pager stdin, ignore broken pipe errors
                 // (happens when user quits with 'q')
@@ -75,3 +226,59 @@ pub fn display_in_pager(content: &str) -> Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_envelope_shape() {
+        let data = vec!["a", "b"];
+        let envelope = JsonEnvelope {
+            schema_version: crate::constants::JSON_SCHEMA_VERSION,
+            data: &data,
+        };
+        let value = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(value["schema_version"], 1);
+        assert_eq!(value["data"], serde_json::json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_compact_json_threshold() {
+        let threshold = crate::constants::JSON_COMPACT_THRESHOLD;
+        assert!(!compact_json(10, false));
+        assert!(!compact_json(threshold, false));
+        assert!(compact_json(threshold + 1, false));
+        assert!(!compact_json(threshold + 1, true));
+    }
+
+    #[test]
+    fn test_json_keeps_field_order() {
+        #[derive(Serialize)]
+        struct Row {
+            zeta: u32,
+            alpha: u32,
+        }
+        let rows = vec![Row { zeta: 1, alpha: 2 }];
+        let envelope = JsonEnvelope {
+            schema_version: crate::constants::JSON_SCHEMA_VERSION,
+            data: &rows,
+        };
+
+        for compact in [false, true] {
+            let mut out = Vec::new();
+            serialize_json(&mut out, &envelope, compact).unwrap();
+            let json = String::from_utf8(out).unwrap();
+            let position = |needle: &str| json.find(needle).unwrap();
+            assert!(position("schema_version") < position("data"), "{}", json);
+            assert!(position("zeta") < position("alpha"), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+    }
+}
@@ -0,0 +1,143 @@
+//! Typed errors that map to distinct process exit codes
+//!
+//! | Code | Meaning                     |
+//! |------|-----------------------------|
+//! | 0    | Success                     |
+//! | 1    | General error               |
+//! | 2    | Not found / nothing matched |
+//! | 3    | Too few approvals           |
+//! | 4    | Authentication failure      |
+//!
+//! `pr status --exit-code` reuses 1 for a failed build and 2 for one that's
+//! still running.
+use std::fmt;
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_GENERAL: i32 = 1;
+pub const EXIT_NOT_FOUND: i32 = 2;
+pub const EXIT_APPROVALS: i32 = 3;
+pub const EXIT_AUTH: i32 = 4;
+pub const EXIT_BUILD_FAILED: i32 = 1;
+pub const EXIT_BUILD_PENDING: i32 = 2;
+
+#[derive(Debug)]
+pub enum CliError {
+    /// A requested resource doesn't exist or a query matched nothing
+    NotFound(String),
+    /// A pull request has fewer approvals than required (`pr approvals`,
+    /// `pr check-approvals`)
+    ApprovalsMissing(String),
+    /// Credentials are missing or were rejected
+    Auth(String),
+    /// A build failed or was stopped (`pr status --exit-code`)
+    BuildFailed(String),
+    /// A build is still running (`pr status --exit-code`)
+    BuildPending(String),
+    /// `--explain` printed the request instead of sending it; not a failure
+    ///
+    /// Every client checks the flag before sending, so a command stops at its
+    /// first request: later requests often need that one's response, and a
+    /// batch would otherwise print one request per item. Code that collects
+    /// per-item errors must pass this one on (see [`is_explained`]).
+    Explained,
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::NotFound(_) => EXIT_NOT_FOUND,
+            CliError::ApprovalsMissing(_) => EXIT_APPROVALS,
+            CliError::Auth(_) => EXIT_AUTH,
+            CliError::BuildFailed(_) => EXIT_BUILD_FAILED,
+            CliError::BuildPending(_) => EXIT_BUILD_PENDING,
+            CliError::Explained => EXIT_SUCCESS,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::NotFound(msg)
+            | CliError::ApprovalsMissing(msg)
+            | CliError::Auth(msg)
+            | CliError::BuildFailed(msg)
+            | CliError::BuildPending(msg) => write!(f, "{}", msg),
+            CliError::Explained => write!(f, "Request not sent (--explain)"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Exit code for an error returned from a command handler
+///
+/// Looks through the whole context chain so wrapped errors keep their code.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    err.chain()
+        .find_map(|e| e.downcast_ref::<CliError>())
+        .map(CliError::exit_code)
+        .unwrap_or(EXIT_GENERAL)
+}
+
+/// Whether `err` is `--explain` stopping the command rather than a failure
+pub fn is_explained(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|e| matches!(e.downcast_ref::<CliError>(), Some(CliError::Explained)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code_general() {
+        let err = anyhow::anyhow!("boom");
+        assert_eq!(exit_code(&err), EXIT_GENERAL);
+    }
+
+    #[test]
+    fn test_exit_code_not_found() {
+        let err = anyhow::Error::new(CliError::NotFound("PR #1 not found".to_string()));
+        assert_eq!(exit_code(&err), EXIT_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_exit_code_auth() {
+        let err = anyhow::Error::new(CliError::Auth("bad token".to_string()));
+        assert_eq!(exit_code(&err), EXIT_AUTH);
+    }
+
+    #[test]
+    fn test_exit_code_approvals_missing() {
+        let err = anyhow::Error::new(CliError::ApprovalsMissing(
+            "PR #1 has 1 of 2 required approvals".to_string(),
+        ));
+        assert_eq!(exit_code(&err), EXIT_APPROVALS);
+    }
+
+    #[test]
+    fn test_exit_code_builds() {
+        let err = anyhow::Error::new(CliError::BuildFailed("lint failed".to_string()));
+        assert_eq!(exit_code(&err), EXIT_BUILD_FAILED);
+        let err = anyhow::Error::new(CliError::BuildPending("tests running".to_string()));
+        assert_eq!(exit_code(&err), EXIT_BUILD_PENDING);
+    }
+
+    #[test]
+    fn test_is_explained() {
+        let result: anyhow::Result<()> = Err(CliError::Explained.into());
+        let err = result.context("Failed to comment").unwrap_err();
+        assert!(is_explained(&err));
+        assert_eq!(exit_code(&err), EXIT_SUCCESS);
+        assert!(!is_explained(&anyhow::anyhow!("boom")));
+    }
+
+    #[test]
+    fn test_exit_code_through_context() {
+        let result: anyhow::Result<()> = Err(CliError::Auth("bad token".to_string()).into());
+        let err = result.context("API authentication failed").unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_AUTH);
+    }
+}
@@ -2,8 +2,13 @@ use anyhow::{Context, Result};
 use std::process::Command;
 
 pub fn get_current_branch() -> Result<String> {
+    current_branch_in(std::path::Path::new("."))
+}
+
+pub(crate) fn current_branch_in(dir: &std::path::Path) -> Result<String> {
     let output = Command::new("git")
         .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(dir)
         .output()
         .context("Failed to execute git command")?;
 
@@ -37,11 +42,17 @@ pub fn get_repo_root() -> Result<std::path::PathBuf> {
     Ok(std::path::PathBuf::from(root_path))
 }
 
-pub fn get_repo_info(remote_name: Option<&str>) -> Result<(String, String)> {
+/// The `(workspace, repo)` of a remote's Bitbucket URL, `origin` by default,
+/// for the repository at `dir`
+pub(crate) fn repo_info_in(
+    dir: &std::path::Path,
+    remote_name: Option<&str>,
+) -> Result<(String, String)> {
     let remote = remote_name.unwrap_or("origin");
     // Get remote URL
     let output = Command::new("git")
         .args(["remote", "get-url", remote])
+        .current_dir(dir)
         .output()
         .context("Failed to execute git command")?;
 
@@ -57,6 +68,194 @@ pub fn get_repo_info(remote_name: Option<&str>) -> Result<(String, String)> {
     parse_git_url(&url_str)
 }
 
+/// Get the branch the current branch tracks, without its remote prefix
+///
+/// For a branch tracking `origin/develop` this returns `develop`. Returns
+/// `None` if there is no upstream configured (or not in a git repository).
+pub fn get_upstream_branch() -> Option<String> {
+    upstream_branch_in(std::path::Path::new("."))
+}
+
+fn upstream_branch_in(dir: &std::path::Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--symbolic-full-name", "@{u}"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let full_ref = String::from_utf8(output.stdout).ok()?;
+    let full_ref = full_ref.trim();
+
+    if let Some(local) = full_ref.strip_prefix("refs/heads/") {
+        return Some(local.to_string());
+    }
+    full_ref
+        .strip_prefix("refs/remotes/")
+        .and_then(|r| r.split_once('/'))
+        .map(|(_, branch)| branch.to_string())
+}
+
+/// Get the remote `branch` is configured to track (`branch.<name>.remote`)
+/// in the repository at `dir`
+///
+/// Returns `None` if the branch has no remote configured, tracks a local
+/// branch (`.`), or `dir` isn't in a git repository.
+pub(crate) fn branch_remote_in(dir: &std::path::Path, branch: &str) -> Option<String> {
+    let key = format!("branch.{}.remote", branch);
+    let output = Command::new("git")
+        .args(["config", "--get", &key])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let remote = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!remote.is_empty() && remote != ".").then_some(remote)
+}
+
+/// The repository `branch` is pushed to, as `workspace/repo`
+///
+/// Read from the URL of the branch's tracking remote, which for a fork-based
+/// workflow is the fork rather than the repository PRs are opened against.
+/// Returns `None` if the branch has no remote or it isn't a Bitbucket URL.
+pub fn get_branch_source_repo(branch: &str) -> Option<String> {
+    branch_source_repo_in(std::path::Path::new("."), branch)
+}
+
+fn branch_source_repo_in(dir: &std::path::Path, branch: &str) -> Option<String> {
+    let remote = branch_remote_in(dir, branch)?;
+    let output = Command::new("git")
+        .args(["remote", "get-url", &remote])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let url = String::from_utf8(output.stdout).ok()?;
+    let (workspace, repo) = parse_git_url(url.trim()).ok()?;
+    Some(format!("{}/{}", workspace, repo))
+}
+
+/// Whether `branch` exists on `remote` (`git ls-remote --heads`)
+///
+/// Contacts the remote, so it fails if the remote is unreachable or
+/// rejects the credentials.
+pub fn branch_exists_on_remote(remote: &str, branch: &str) -> Result<bool> {
+    branch_exists_on_remote_in(std::path::Path::new("."), remote, branch)
+}
+
+fn branch_exists_on_remote_in(dir: &std::path::Path, remote: &str, branch: &str) -> Result<bool> {
+    // A full ref, since a bare name would also match e.g. `feature/<branch>`
+    let head_ref = format!("refs/heads/{}", branch);
+    let output = Command::new("git")
+        .args(["ls-remote", "--heads", remote, &head_ref])
+        .current_dir(dir)
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to list branches on remote '{}': {}",
+            remote,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(!output.stdout.trim_ascii().is_empty())
+}
+
+/// Push `branch` to `remote` and set it as the upstream (`git push -u`)
+///
+/// git's own progress and prompts go straight to the terminal, on stderr so
+/// they can't mix with `--json` output.
+pub fn push_branch(remote: &str, branch: &str) -> Result<()> {
+    push_branch_in(std::path::Path::new("."), remote, branch)
+}
+
+fn push_branch_in(dir: &std::path::Path, remote: &str, branch: &str) -> Result<()> {
+    let status = Command::new("git")
+        .args(["push", "-u", remote, branch])
+        .current_dir(dir)
+        .stdout(std::io::stderr())
+        .status()
+        .context("Failed to execute git command")?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to push '{}' to remote '{}'",
+            branch,
+            remote
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `name` (e.g. `origin/HEAD`) resolves to a commit
+///
+/// `<remote>/HEAD` only exists after a clone or `git remote set-head`, so a
+/// repository that added its remote later doesn't have one.
+pub fn ref_exists(name: &str) -> bool {
+    ref_exists_in(std::path::Path::new("."), name)
+}
+
+fn ref_exists_in(dir: &std::path::Path, name: &str) -> bool {
+    Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", name),
+        ])
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// List commits reachable from `head` but not from `base`, newest first
+///
+/// # Returns
+///
+/// A vector of `(subject, body)` tuples, one per commit.
+pub fn log_between(base: &str, head: &str) -> Result<Vec<(String, String)>> {
+    let range = format!("{}..{}", base, head);
+    let output = Command::new("git")
+        .args(["log", "--format=%s%x1f%b%x1e", &range])
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to list commits for '{}': {}",
+            range,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let log = String::from_utf8(output.stdout).context("Invalid UTF-8 in git log output")?;
+    Ok(parse_log_output(&log))
+}
+
+fn parse_log_output(log: &str) -> Vec<(String, String)> {
+    log.split('\x1e')
+        .map(|entry| entry.trim_matches('\n'))
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (subject, body) = entry.split_once('\x1f').unwrap_or((entry, ""));
+            (subject.trim().to_string(), body.trim().to_string())
+        })
+        .collect()
+}
+
 fn parse_git_url(url: &str) -> Result<(String, String)> {
     // Basic support for ssh:// and user@ formats
     // This handles:
@@ -90,6 +289,19 @@ fn parse_git_url(url: &str) -> Result<(String, String)> {
     Ok((workspace.to_string(), repo.to_string()))
 }
 
+/// Run git in `dir` to set up a test fixture, failing the test if it fails
+#[cfg(test)]
+pub fn run_for_test(dir: &std::path::Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,7 +333,8 @@ mod tests {
         ];
 
         for (url, (expected_workspace, expected_repo)) in cases {
-            let (workspace, repo) = parse_git_url(url).expect(&format!("Failed to parse {}", url));
+            let (workspace, repo) =
+                parse_git_url(url).unwrap_or_else(|_| panic!("Failed to parse {}", url));
             assert_eq!(
                 workspace, expected_workspace,
                 "Workspace mismatch for {}",
@@ -131,6 +344,151 @@ mod tests {
         }
     }
 
+    use super::run_for_test as git;
+
+    fn init_repo_with_remote() -> (tempfile::TempDir, std::path::PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let remote = tmp.path().join("remote.git");
+        let work = tmp.path().join("work");
+        std::fs::create_dir_all(&work).unwrap();
+
+        git(
+            tmp.path(),
+            &["init", "-q", "--bare", remote.to_str().unwrap()],
+        );
+        git(&work, &["init", "-q", "-b", "main"]);
+        git(&work, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(
+            &work,
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        );
+        git(&work, &["push", "-q", "origin", "main:develop"]);
+        (tmp, work)
+    }
+
+    #[test]
+    fn test_upstream_branch_tracking_remote() {
+        let (_tmp, work) = init_repo_with_remote();
+        git(
+            &work,
+            &[
+                "checkout",
+                "-q",
+                "-b",
+                "feature/x",
+                "--track",
+                "origin/develop",
+            ],
+        );
+
+        assert_eq!(upstream_branch_in(&work), Some("develop".to_string()));
+    }
+
+    #[test]
+    fn test_upstream_branch_none() {
+        let (_tmp, work) = init_repo_with_remote();
+        git(&work, &["checkout", "-q", "-b", "feature/x"]);
+
+        assert_eq!(upstream_branch_in(&work), None);
+    }
+
+    #[test]
+    fn test_branch_remote() {
+        let (_tmp, work) = init_repo_with_remote();
+        git(
+            &work,
+            &["remote", "add", "upstream", "https://example.com/x.git"],
+        );
+        git(&work, &["fetch", "-q", "origin"]);
+        git(
+            &work,
+            &[
+                "checkout",
+                "-q",
+                "-b",
+                "feature/x",
+                "--track",
+                "origin/develop",
+            ],
+        );
+        git(&work, &["config", "branch.feature/x.remote", "upstream"]);
+        git(&work, &["checkout", "-q", "-b", "local-only", "main"]);
+        git(&work, &["branch", "-q", "--set-upstream-to", "main"]);
+
+        assert_eq!(
+            branch_remote_in(&work, "feature/x"),
+            Some("upstream".to_string())
+        );
+        // Tracking a local branch records remote "."
+        assert_eq!(branch_remote_in(&work, "local-only"), None);
+        assert_eq!(branch_remote_in(&work, "main"), None);
+    }
+
+    #[test]
+    fn test_branch_source_repo() {
+        let (_tmp, work) = init_repo_with_remote();
+        git(
+            &work,
+            &[
+                "remote",
+                "add",
+                "fork",
+                "git@bitbucket.org:me/repo-fork.git",
+            ],
+        );
+        git(&work, &["checkout", "-q", "-b", "feature/x"]);
+        git(&work, &["config", "branch.feature/x.remote", "fork"]);
+        git(&work, &["config", "branch.main.remote", "origin"]);
+
+        assert_eq!(
+            branch_source_repo_in(&work, "feature/x"),
+            Some("me/repo-fork".to_string())
+        );
+        // origin is a local path here, not a Bitbucket URL
+        assert_eq!(branch_source_repo_in(&work, "main"), None);
+        assert_eq!(branch_source_repo_in(&work, "untracked"), None);
+    }
+
+    #[test]
+    fn test_branch_exists_on_remote_and_push() {
+        let (_tmp, work) = init_repo_with_remote();
+        git(&work, &["checkout", "-q", "-b", "x"]);
+
+        assert!(branch_exists_on_remote_in(&work, "origin", "develop").unwrap());
+        assert!(!branch_exists_on_remote_in(&work, "origin", "x").unwrap());
+        assert!(branch_exists_on_remote_in(&work, "missing", "x").is_err());
+
+        push_branch_in(&work, "origin", "x").unwrap();
+        assert!(branch_exists_on_remote_in(&work, "origin", "x").unwrap());
+        assert_eq!(upstream_branch_in(&work), Some("x".to_string()));
+    }
+
+    #[test]
+    fn test_ref_exists() {
+        let (_tmp, work) = init_repo_with_remote();
+        git(&work, &["fetch", "-q", "origin"]);
+
+        assert!(ref_exists_in(&work, "origin/develop"));
+        // Added with `remote add`, not cloned, so there's no origin/HEAD yet
+        assert!(!ref_exists_in(&work, "origin/HEAD"));
+        git(&work, &["remote", "set-head", "origin", "develop"]);
+        assert!(ref_exists_in(&work, "origin/HEAD"));
+    }
+
+    #[test]
+    fn test_parse_log_output() {
+        let log = "Second\x1fBody line 1\nBody line 2\n\x1e\nFirst\x1f\x1e\n";
+        let commits = parse_log_output(log);
+        assert_eq!(
+            commits,
+            vec![
+                ("Second".to_string(), "Body line 1\nBody line 2".to_string()),
+                ("First".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_log_output("").is_empty());
+    }
+
     #[test]
     fn test_parse_git_url_errors() {
         let invalid_urls = vec![
@@ -8,6 +8,7 @@ mod config;
 mod constants;
 mod context;
 mod display;
+mod error;
 mod git;
 mod utils;
 
@@ -15,9 +16,21 @@ use cli::{Cli, Commands};
 
 #[tokio::main]
 async fn main() {
-    let cli = Cli::parse();
+    utils::sigpipe::reset_to_default();
+
+    let mut cli = Cli::parse();
     utils::debug::set_enabled(cli.verbose);
 
+    // `bb pr <ID>` is parsed again as `bb pr view <ID>`; do it before any setup
+    let pr_command = match &mut cli.command {
+        Commands::Pr(args) => Some(
+            std::mem::take(args)
+                .into_command()
+                .unwrap_or_else(|e| e.exit()),
+        ),
+        _ => None,
+    };
+
     // Initialize AppContext
     let ctx = match context::AppContext::new(&cli) {
         Ok(c) => c,
@@ -27,15 +40,19 @@ async fn main() {
         }
     };
 
-    let result = match cli.command {
-        Commands::Pr(args) => commands::pr::handle(&ctx, args).await,
-        Commands::Auth(args) => commands::auth::handle(&ctx, args).await,
-        Commands::Config(args) => commands::config::handle(&ctx, args).await,
-        Commands::Repo(args) => commands::repo::handle(&ctx, args).await,
+    let result = match (cli.command, pr_command) {
+        (Commands::Pr(_), Some(command)) => commands::pr::handle(&ctx, command).await,
+        (Commands::Pr(_), None) => unreachable!("pr commands are resolved above"),
+        (Commands::Auth(args), _) => commands::auth::handle(&ctx, args).await,
+        (Commands::Config(args), _) => commands::config::handle(&ctx, args).await,
+        (Commands::Repo(args), _) => commands::repo::handle(&ctx, args).await,
     };
 
     if let Err(e) = result {
-        display::ui::error(&format!("{:#}", e));
-        process::exit(1);
+        let code = error::exit_code(&e);
+        if code != error::EXIT_SUCCESS {
+            display::ui::error(&format!("{:#}", e));
+        }
+        process::exit(code);
     }
 }
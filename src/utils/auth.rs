@@ -1,14 +1,170 @@
-/// Keyring authentication utilities
+/// Credential storage utilities
 use anyhow::{Context, Result};
 use keyring::Entry;
 
-/// Create a keyring entry for the given username
-fn create_entry(username: &str) -> Result<Entry> {
-    Entry::new(crate::constants::KEYRING_SERVICE_NAME, username)
-        .context("Failed to create keyring entry")
+/// A backend that persists API tokens per username
+pub trait CredentialStore {
+    fn save(&self, username: &str, api_token: &str) -> Result<()>;
+    fn get(&self, username: &str) -> Result<String>;
+    fn delete(&self, username: &str) -> Result<()>;
+    /// Whether a token is stored, without reading it out; errors only if the
+    /// store itself can't be queried
+    fn contains(&self, username: &str) -> Result<bool>;
 }
 
-/// Save credentials to the system keyring
+/// The system keyring (default)
+pub struct KeyringStore;
+
+impl KeyringStore {
+    /// Create a keyring entry for the given username
+    fn create_entry(username: &str) -> Result<Entry> {
+        Entry::new(crate::constants::KEYRING_SERVICE_NAME, username)
+            .context("Failed to create keyring entry")
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn save(&self, username: &str, api_token: &str) -> Result<()> {
+        Self::create_entry(username)?
+            .set_password(api_token)
+            .context("Failed to save API token to keyring")
+    }
+
+    fn get(&self, username: &str) -> Result<String> {
+        Self::create_entry(username)?
+            .get_password()
+            .context("No API token found in keyring")
+    }
+
+    fn delete(&self, username: &str) -> Result<()> {
+        Self::create_entry(username)?
+            .delete_credential()
+            .context("Failed to delete credentials from keyring")
+    }
+
+    fn contains(&self, username: &str) -> Result<bool> {
+        match Self::create_entry(username)?.get_password() {
+            Ok(_) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(e).context("Failed to query keyring"),
+        }
+    }
+}
+
+/// A plaintext JSON file readable only by the owner (0600 on Unix)
+///
+/// Intended for headless machines without a secret service. Tokens are NOT
+/// encrypted.
+pub struct FileStore {
+    path: std::path::PathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: std::path::PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The default credentials file next to the global config
+    pub fn default_path() -> Option<std::path::PathBuf> {
+        crate::config::manager::get_config_dir().map(|d| {
+            d.join(crate::constants::CONFIG_DIR_NAME)
+                .join(crate::constants::CREDENTIALS_FILE_NAME)
+        })
+    }
+
+    fn read(&self) -> Result<std::collections::BTreeMap<String, String>> {
+        if !self.path.exists() {
+            return Ok(Default::default());
+        }
+        let content =
+            std::fs::read_to_string(&self.path).context("Failed to read credentials file")?;
+        serde_json::from_str(&content).context("Failed to parse credentials file")
+    }
+
+    fn write(&self, tokens: &std::collections::BTreeMap<String, String>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options
+            .open(&self.path)
+            .context("Failed to open credentials file")?;
+
+        // Tighten permissions on files created before this store existed
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        use std::io::Write;
+        file.write_all(serde_json::to_string_pretty(tokens)?.as_bytes())
+            .context("Failed to write credentials file")
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn save(&self, username: &str, api_token: &str) -> Result<()> {
+        let mut tokens = self.read()?;
+        tokens.insert(username.to_string(), api_token.to_string());
+        self.write(&tokens)
+    }
+
+    fn get(&self, username: &str) -> Result<String> {
+        self.read()?
+            .remove(username)
+            .context("No API token found in credentials file")
+    }
+
+    fn delete(&self, username: &str) -> Result<()> {
+        let mut tokens = self.read()?;
+        if tokens.remove(username).is_none() {
+            return Err(anyhow::anyhow!(
+                "No credentials for '{}' in credentials file",
+                username
+            ));
+        }
+        self.write(&tokens)
+    }
+
+    fn contains(&self, username: &str) -> Result<bool> {
+        Ok(self.read()?.contains_key(username))
+    }
+}
+
+/// The `credential_store` config key, if set
+fn configured_store_kind() -> Option<String> {
+    crate::config::manager::ProfileConfig::load_global()
+        .ok()
+        .and_then(|c| c.credential_store)
+}
+
+/// Build the credential store for a `credential_store` value
+///
+/// `keyring` (default) or `file`.
+fn store_for(kind: Option<&str>) -> Result<Box<dyn CredentialStore>> {
+    match kind {
+        None | Some("keyring") => Ok(Box::new(KeyringStore)),
+        Some("file") => {
+            let path = FileStore::default_path().context("No config directory available")?;
+            Ok(Box::new(FileStore::new(path)))
+        }
+        Some(other) => Err(anyhow::anyhow!(
+            "Unknown credential_store '{}' (expected 'keyring' or 'file')",
+            other
+        )),
+    }
+}
+
+/// Save credentials to the configured credential store
 ///
 /// # Arguments
 ///
@@ -22,16 +178,20 @@ fn create_entry(username: &str) -> Result<Entry> {
 /// auth::save_credentials("user@example.com", "secret_token").unwrap();
 /// ```
 pub fn save_credentials(username: &str, api_token: &str) -> Result<()> {
-    let entry = create_entry(username)?;
+    let kind = configured_store_kind();
+    store_for(kind.as_deref())?.save(username, api_token)?;
 
-    entry
-        .set_password(api_token)
-        .context("Failed to save API token to keyring")?;
+    if kind.as_deref() == Some("file") {
+        crate::display::ui::warning(&format!(
+            "API token stored UNENCRYPTED in {:?} (credential_store = \"file\")",
+            FileStore::default_path().unwrap_or_default()
+        ));
+    }
 
     Ok(())
 }
 
-/// Retrieve credentials from the system keyring
+/// Retrieve credentials from the configured credential store
 ///
 /// # Arguments
 ///
@@ -39,26 +199,433 @@ pub fn save_credentials(username: &str, api_token: &str) -> Result<()> {
 ///
 /// # Returns
 ///
-/// Returns the password/token if found, or an error if not found or keyring is inaccessible.
+/// Returns the password/token if found, or an error if not found or the store is inaccessible.
 pub fn get_credentials(username: &str) -> Result<String> {
-    let entry = create_entry(username)?;
-
-    let api_token = entry
-        .get_password()
-        .context("No API token found in keyring")?;
-
-    Ok(api_token)
+    store_for(configured_store_kind().as_deref())?.get(username)
 }
 
-/// Delete credentials from the system keyring
+/// Delete credentials from the configured credential store
 ///
 /// # Arguments
 ///
 /// * `username` - The username to delete credentials for
 pub fn delete_credentials(username: &str) -> Result<()> {
-    let entry = create_entry(username)?;
+    store_for(configured_store_kind().as_deref())?.delete(username)
+}
+
+/// Delete credentials for several users from the configured credential store
+///
+/// Every user is attempted even if some fail; results are in input order.
+pub fn delete_all_credentials(usernames: &[&str]) -> Result<Vec<Result<()>>> {
+    let store = store_for(configured_store_kind().as_deref())?;
+    Ok(delete_each(store.as_ref(), usernames))
+}
+
+fn delete_each(store: &dyn CredentialStore, usernames: &[&str]) -> Vec<Result<()>> {
+    usernames
+        .iter()
+        .map(|username| store.delete(username))
+        .collect()
+}
+
+/// Whether the configured store holds a token for the username
+pub fn has_credentials(username: &str) -> Result<bool> {
+    store_for(configured_store_kind().as_deref())?.contains(username)
+}
+
+/// Outcome of `bb config doctor`'s credential store check
+pub struct StoreDiagnosis {
+    /// The `credential_store` value in effect (`keyring` or `file`)
+    pub kind: String,
+    /// Human-readable description of the backend behind it
+    pub backend: String,
+    /// Result of the write/read/delete round-trip
+    pub result: Result<()>,
+    /// What to try if the round-trip failed
+    pub remediation: Option<String>,
+}
+
+/// Check the configured credential store with a throwaway entry
+pub fn diagnose_store() -> StoreDiagnosis {
+    let kind = configured_store_kind().unwrap_or_else(|| "keyring".to_string());
+    let backend = describe_backend(&kind);
+    let result = store_for(Some(&kind)).and_then(|store| round_trip_check(store.as_ref()));
+    let remediation = result.is_err().then(|| remediation_for(&kind));
+
+    StoreDiagnosis {
+        kind,
+        backend,
+        result,
+        remediation,
+    }
+}
+
+/// Whether the keyring backend compiled into this build outlives the process
+fn keyring_is_persistent() -> bool {
+    use keyring::credential::CredentialPersistence;
+
+    matches!(
+        keyring::default::default_credential_builder().persistence(),
+        CredentialPersistence::UntilDelete | CredentialPersistence::UntilReboot
+    )
+}
+
+fn describe_backend(kind: &str) -> String {
+    if kind == "file" {
+        return match FileStore::default_path() {
+            Some(path) => format!("plaintext file {:?}", path),
+            None => "plaintext file (no config directory available)".to_string(),
+        };
+    }
+    if !keyring_is_persistent() {
+        return "in-memory only, no system keyring support in this build".to_string();
+    }
+    if cfg!(target_os = "macos") {
+        "macOS Keychain".to_string()
+    } else if cfg!(target_os = "windows") {
+        "Windows Credential Manager".to_string()
+    } else {
+        "Secret Service (D-Bus)".to_string()
+    }
+}
+
+fn remediation_for(kind: &str) -> String {
+    const USE_FILE: &str = "run 'bb config set credential_store file' to store tokens in a \
+        0600 plaintext file instead";
+
+    if kind == "file" {
+        return "Check that the config directory and credentials.json are writable by you"
+            .to_string();
+    }
+    if !keyring_is_persistent() {
+        return format!("No system keyring is available to this build; {}", USE_FILE);
+    }
+    if cfg!(target_os = "macos") {
+        "Unlock your login keychain (Keychain Access > File > Unlock Keychain \"login\", \
+         or 'security unlock-keychain login.keychain') and run the check again"
+            .to_string()
+    } else if cfg!(target_os = "windows") {
+        "Make sure the Credential Manager service is running (services.msc) and that \
+         you're logged in with a full user profile"
+            .to_string()
+    } else {
+        format!(
+            "Install and unlock a Secret Service provider (e.g. 'install gnome-keyring' \
+             or enable it in KeePassXC) and make sure a D-Bus session is running, or {}",
+            USE_FILE
+        )
+    }
+}
+
+/// Write, read back and delete a throwaway entry
+///
+/// The error names the step that failed.
+pub fn round_trip_check(store: &dyn CredentialStore) -> Result<()> {
+    let username = format!("bb-cli-doctor-{}", std::process::id());
+    let token = "bb-cli-doctor-check";
+
+    store.save(&username, token).context("Write failed")?;
+    let read = store.get(&username).context("Read failed");
+    // Clean up even if the read failed, but report the first problem
+    let deleted = store.delete(&username).context("Delete failed");
+
+    if read? != token {
+        return Err(anyhow::anyhow!(
+            "Read failed: stored value came back different"
+        ));
+    }
+    deleted?;
+    if store.contains(&username).context("Read failed")? {
+        return Err(anyhow::anyhow!("Delete failed: entry still present"));
+    }
+    Ok(())
+}
+
+/// Prefix of Atlassian API tokens
+const API_TOKEN_PREFIX: &str = "ATATT";
+
+/// An API token as entered at login, after cleanup
+#[derive(Debug, PartialEq)]
+pub enum EnteredToken {
+    /// Used as entered, apart from surrounding whitespace
+    Clean(String),
+    /// An Atlassian API token that had whitespace inside it (e.g. from a
+    /// wrapped paste), with the whitespace removed
+    Rejoined(String),
+    /// Contains spaces but isn't a recognizable token, so is left as is
+    HasSpaces(String),
+}
+
+impl EnteredToken {
+    pub fn token(&self) -> &str {
+        match self {
+            EnteredToken::Clean(t) | EnteredToken::Rejoined(t) | EnteredToken::HasSpaces(t) => t,
+        }
+    }
+}
+
+/// Clean up a typed or pasted API token
+///
+/// Surrounding whitespace is dropped. Whitespace inside is removed only when
+/// what's left is a recognizable Atlassian API token; otherwise line breaks,
+/// tabs and other control characters are rejected rather than sent as part of
+/// the credential, and spaces are kept but reported.
+pub fn clean_entered_token(input: &str) -> Result<EnteredToken> {
+    let token = input.trim();
+    if !token.contains(char::is_whitespace) && !token.contains(char::is_control) {
+        return Ok(EnteredToken::Clean(token.to_string()));
+    }
+
+    let joined: String = token.chars().filter(|c| !c.is_whitespace()).collect();
+    if looks_like_api_token(&joined) {
+        return Ok(EnteredToken::Rejoined(joined));
+    }
+    if token.contains(|c: char| c.is_control() || (c.is_whitespace() && c != ' ')) {
+        anyhow::bail!(
+            "API token contains line breaks, tabs or control characters, probably from \
+             a paste; enter it again on a single line"
+        );
+    }
+    Ok(EnteredToken::HasSpaces(token.to_string()))
+}
+
+/// How long to wait for more lines of a paste after the first one
+const PASTE_GRACE: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Read an API token from stdin, including every line of a multi-line paste
+///
+/// The terminal hands over a paste one line at a time, so lines that are
+/// already waiting once the first is read are taken as part of the same entry.
+/// Pass the result to [`clean_entered_token`].
+pub fn read_entered_token() -> std::io::Result<String> {
+    let mut stdin = std::io::stdin().lock();
+    read_pasted_lines(&mut stdin, |_| stdin_has_input(PASTE_GRACE))
+}
+
+/// Read a line, then keep reading lines while `pending` says more are waiting
+fn read_pasted_lines<R: std::io::BufRead>(
+    reader: &mut R,
+    mut pending: impl FnMut(&mut R) -> bool,
+) -> std::io::Result<String> {
+    let mut input = String::new();
+    reader.read_line(&mut input)?;
+    while pending(reader) {
+        if reader.read_line(&mut input)? == 0 {
+            break;
+        }
+    }
+    Ok(input)
+}
+
+/// Whether stdin has input ready within `wait`
+#[cfg(unix)]
+fn stdin_has_input(wait: std::time::Duration) -> bool {
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: polls a single valid pollfd for the duration of the call
+    let ready = unsafe { libc::poll(&mut fd, 1, wait.as_millis() as libc::c_int) };
+    ready > 0 && fd.revents & libc::POLLIN != 0
+}
+
+#[cfg(not(unix))]
+fn stdin_has_input(_wait: std::time::Duration) -> bool {
+    false
+}
+
+/// Whether `token` has the shape of an Atlassian API token (`ATATT...`)
+fn looks_like_api_token(token: &str) -> bool {
+    token.len() > API_TOKEN_PREFIX.len()
+        && token.starts_with(API_TOKEN_PREFIX)
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '='))
+}
+
+/// User details recorded at login so later commands can avoid a `/user` round-trip
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct CachedUser {
+    pub username: String,
+    pub display_name: String,
+    pub uuid: String,
+    pub nickname: Option<String>,
+    /// Scopes granted to the token, if the server reported them
+    pub scopes: Option<Vec<String>>,
+}
+
+/// Path of the cached user file for the given username
+fn cached_user_path(username: &str) -> Option<std::path::PathBuf> {
+    dirs::cache_dir().map(|d| {
+        d.join(crate::constants::CACHE_DIR_NAME)
+            .join("users")
+            .join(format!("{}.json", username))
+    })
+}
+
+/// Save the user details for the given username
+pub fn save_cached_user(user: &CachedUser) -> Result<()> {
+    let path = cached_user_path(&user.username).context("No cache directory available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create cache directory")?;
+    }
+    let content = serde_json::to_string_pretty(user)?;
+    std::fs::write(&path, content).context("Failed to write cached user")?;
+    Ok(())
+}
+
+/// Load the cached user details for the given username, if present
+pub fn load_cached_user(username: &str) -> Option<CachedUser> {
+    let path = cached_user_path(username)?;
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path().join("credentials.json"));
+
+        store.save("a@example.com", "token-a").unwrap();
+        store.save("b@example.com", "token-b").unwrap();
+        assert_eq!(store.get("a@example.com").unwrap(), "token-a");
+        assert_eq!(store.get("b@example.com").unwrap(), "token-b");
+
+        assert!(store.contains("a@example.com").unwrap());
+        store.delete("a@example.com").unwrap();
+        assert!(!store.contains("a@example.com").unwrap());
+        assert!(store.get("a@example.com").is_err());
+        assert_eq!(store.get("b@example.com").unwrap(), "token-b");
+        assert!(store.delete("a@example.com").is_err());
+    }
+
+    #[test]
+    fn test_delete_each_continues_past_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path().join("credentials.json"));
+        store.save("a@example.com", "token-a").unwrap();
+        store.save("c@example.com", "token-c").unwrap();
+
+        let results = delete_each(&store, &["a@example.com", "b@example.com", "c@example.com"]);
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+        assert!(store.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path().join("credentials.json"));
+        store.save("me@example.com", "real-token").unwrap();
+
+        round_trip_check(&store).unwrap();
+
+        // Existing credentials are untouched and the throwaway entry is gone
+        assert_eq!(store.get("me@example.com").unwrap(), "real-token");
+        assert_eq!(store.read().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_round_trip_check_reports_step() {
+        // A directory where the file should be makes every write fail
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path().to_path_buf());
+
+        let err = round_trip_check(&store).unwrap_err();
+        assert!(err.to_string().starts_with("Write failed"), "{:#}", err);
+    }
+
+    #[test]
+    fn test_store_for_unknown_kind() {
+        assert!(store_for(Some("vault")).is_err());
+        assert!(store_for(Some("file")).is_ok());
+        assert!(store_for(None).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_store_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.json");
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        FileStore::new(path.clone()).save("a", "t").unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_clean_entered_token() {
+        assert_eq!(
+            clean_entered_token("  ATATT3xFf-a_b=C1  ").unwrap(),
+            EnteredToken::Clean("ATATT3xFf-a_b=C1".to_string())
+        );
+        assert_eq!(
+            clean_entered_token("app-password").unwrap(),
+            EnteredToken::Clean("app-password".to_string())
+        );
+    }
+
+    #[test]
+    fn test_clean_entered_token_rejoins_wrapped_paste() {
+        let cleaned = clean_entered_token("ATATT3xFf GF0abc\r\n\tdef=12 34").unwrap();
+        assert_eq!(
+            cleaned,
+            EnteredToken::Rejoined("ATATT3xFfGF0abcdef=1234".to_string())
+        );
+        assert_eq!(cleaned.token(), "ATATT3xFfGF0abcdef=1234");
+    }
+
+    #[test]
+    fn test_multi_line_paste_is_read_whole() {
+        let mut paste = std::io::Cursor::new("ATATT3xFf\nGF0abc\ndef=12\n");
+        let pending = |r: &mut std::io::Cursor<&str>| {
+            std::io::BufRead::fill_buf(r).is_ok_and(|rest| !rest.is_empty())
+        };
+
+        let entered = read_pasted_lines(&mut paste, pending).unwrap();
+        assert_eq!(entered, "ATATT3xFf\nGF0abc\ndef=12\n");
+        assert_eq!(
+            clean_entered_token(&entered).unwrap(),
+            EnteredToken::Rejoined("ATATT3xFfGF0abcdef=12".to_string())
+        );
+
+        // A typed token ends at the first line when nothing else is waiting
+        let mut typed = std::io::Cursor::new("ATATT3xFf\nnext answer\n");
+        assert_eq!(
+            read_pasted_lines(&mut typed, |_| false).unwrap(),
+            "ATATT3xFf\n"
+        );
+    }
 
-    entry
-        .delete_credential()
-        .context("Failed to delete credentials from keyring")
+    #[test]
+    fn test_clean_entered_token_unknown_format() {
+        // Spaces are suspicious but might be real, so they're kept and reported
+        assert_eq!(
+            clean_entered_token("my secret token").unwrap(),
+            EnteredToken::HasSpaces("my secret token".to_string())
+        );
+        for input in [
+            "abc\tdef",
+            "abc\ndef",
+            "abc\r\ndef",
+            "abc\u{1b}def",
+            "ATATT!\tx",
+        ] {
+            let err = clean_entered_token(input).unwrap_err().to_string();
+            assert!(err.contains("single line"), "{:?}: {}", input, err);
+        }
+    }
 }
@@ -0,0 +1,162 @@
+//! Opening URLs for the `--web` flags
+//!
+//! `open::that` picks the platform default, which does nothing useful under
+//! WSL or over SSH. Launchers are tried in order and the URL is printed if
+//! none of them works, so there's always a way to get to the page.
+use std::process::{Command, Stdio};
+
+/// Environment variable naming the browser command, overriding detection
+pub const BROWSER_ENV: &str = "BB_BROWSER";
+
+/// One way of opening a URL
+#[derive(Debug, PartialEq)]
+enum Launcher {
+    /// Run this program (with any leading arguments), URL appended
+    Command(Vec<String>),
+    /// The platform default via the `open` crate
+    Default,
+}
+
+/// Open `url` in a browser, printing it instead if nothing could be launched
+///
+/// Returns whether a browser was launched.
+pub fn open_url(url: &str) -> bool {
+    let browser = std::env::var(BROWSER_ENV).ok();
+    let headless = is_headless_ssh(
+        std::env::var_os("SSH_CONNECTION").is_some(),
+        std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some(),
+    );
+
+    for launcher in launchers(browser.as_deref(), is_wsl(), headless) {
+        let result = match &launcher {
+            Launcher::Command(argv) => spawn(argv, url),
+            Launcher::Default => open::that(url),
+        };
+        match result {
+            Ok(()) => return true,
+            Err(e) => crate::utils::debug::log(&format!(
+                "Failed to open browser via {:?}: {}",
+                launcher, e
+            )),
+        }
+    }
+
+    crate::display::ui::warning(&format!(
+        "Could not open a browser (set {} to choose one); open this URL instead:",
+        BROWSER_ENV
+    ));
+    println!("{}", url);
+    false
+}
+
+/// Launchers to try, in order
+///
+/// `BB_BROWSER` replaces detection entirely. Under WSL the Windows side is
+/// reached through `wslview` or `explorer.exe`. Over SSH without a display
+/// there's nothing to try.
+fn launchers(browser: Option<&str>, wsl: bool, headless: bool) -> Vec<Launcher> {
+    if let Some(browser) = browser.filter(|b| !b.trim().is_empty()) {
+        let argv = browser.split_whitespace().map(str::to_string).collect();
+        return vec![Launcher::Command(argv)];
+    }
+
+    if wsl {
+        return vec![
+            Launcher::Command(vec!["wslview".to_string()]),
+            Launcher::Command(vec!["explorer.exe".to_string()]),
+            Launcher::Default,
+        ];
+    }
+
+    if headless {
+        return vec![];
+    }
+    vec![Launcher::Default]
+}
+
+/// Start the browser without waiting for it; `explorer.exe` exits non-zero
+/// even on success, and GUI browsers may not exit until closed
+fn spawn(argv: &[String], url: &str) -> std::io::Result<()> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| std::io::Error::other("empty browser command"))?;
+    Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+/// Whether we're running under Windows Subsystem for Linux
+fn is_wsl() -> bool {
+    cfg!(target_os = "linux")
+        && std::fs::read_to_string("/proc/version").is_ok_and(|v| is_wsl_kernel(&v))
+}
+
+/// WSL kernels identify themselves in `/proc/version`, e.g. `...-microsoft-standard-WSL2`
+fn is_wsl_kernel(proc_version: &str) -> bool {
+    proc_version.to_ascii_lowercase().contains("microsoft")
+}
+
+/// An SSH session with no forwarded display can't show a browser (macOS and
+/// Windows don't use `DISPLAY`, so only Linux and the BSDs are affected)
+fn is_headless_ssh(ssh: bool, display: bool) -> bool {
+    ssh && !display && !cfg!(any(target_os = "macos", target_os = "windows"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_wsl_kernel() {
+        assert!(is_wsl_kernel(
+            "Linux version 5.15.153.1-microsoft-standard-WSL2 (root@65c757a075e2) (gcc (GCC) 11.2.0)"
+        ));
+        assert!(is_wsl_kernel("Linux version 4.4.0-19041-Microsoft"));
+        assert!(!is_wsl_kernel(
+            "Linux version 6.8.0-45-generic (buildd@lcy02-amd64-115) (x86_64-linux-gnu-gcc-13)"
+        ));
+    }
+
+    #[test]
+    fn test_launchers_env_override() {
+        assert_eq!(
+            launchers(Some("firefox --new-tab"), true, true),
+            vec![Launcher::Command(vec![
+                "firefox".to_string(),
+                "--new-tab".to_string()
+            ])]
+        );
+        // Blank means unset
+        assert_eq!(launchers(Some("  "), false, false), vec![Launcher::Default]);
+    }
+
+    #[test]
+    fn test_launchers_wsl() {
+        assert_eq!(
+            launchers(None, true, false),
+            vec![
+                Launcher::Command(vec!["wslview".to_string()]),
+                Launcher::Command(vec!["explorer.exe".to_string()]),
+                Launcher::Default,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_launchers_headless() {
+        assert!(launchers(None, false, true).is_empty());
+        assert_eq!(launchers(None, false, false), vec![Launcher::Default]);
+    }
+
+    #[test]
+    fn test_spawn_missing_program() {
+        let argv = vec!["bb-cli-no-such-browser".to_string()];
+        assert!(spawn(&argv, "https://example.com").is_err());
+        assert!(spawn(&[], "https://example.com").is_err());
+    }
+}
@@ -0,0 +1,384 @@
+//! On-disk JSON cache for API results, stored under the user's cache directory
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Deserialize, Serialize)]
+struct CacheEntry<T> {
+    /// Unix timestamp (seconds) of when the data was written
+    stored_at: u64,
+    data: T,
+}
+
+/// A cache hit along with how old it is
+pub struct Cached<T> {
+    pub data: T,
+    pub age: Duration,
+}
+
+/// Path of a cache entry: `<cache dir>/bb-cli/<namespace>/<key>.json`
+///
+/// The key is escaped so any string (workspace names, filter values) maps to
+/// a distinct, valid file name.
+pub fn cache_path(namespace: &str, key: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| {
+        d.join(crate::constants::CACHE_DIR_NAME)
+            .join(namespace)
+            .join(format!("{}.json", escape_key(key)))
+    })
+}
+
+/// Read a cache entry if it exists, parses, and is younger than `ttl`
+pub fn read<T: DeserializeOwned>(namespace: &str, key: &str, ttl: Duration) -> Option<Cached<T>> {
+    read_from(&cache_path(namespace, key)?, ttl)
+}
+
+/// Write a cache entry, replacing any previous one
+pub fn write<T: Serialize>(namespace: &str, key: &str, data: &T) -> Result<()> {
+    let path = cache_path(namespace, key).context("No cache directory available")?;
+    write_to(&path, data)
+}
+
+/// [`read`] from an explicit path (see [`cache_path`])
+pub fn read_from<T: DeserializeOwned>(path: &Path, ttl: Duration) -> Option<Cached<T>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let entry: CacheEntry<T> = serde_json::from_str(&content).ok()?;
+
+    // Entries from the future (clock changes) count as fresh but age zero
+    let age = Duration::from_secs(now_secs().saturating_sub(entry.stored_at));
+    if age > ttl {
+        return None;
+    }
+
+    Some(Cached {
+        data: entry.data,
+        age,
+    })
+}
+
+/// [`write`] to an explicit path (see [`cache_path`])
+pub fn write_to<T: Serialize>(path: &Path, data: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create cache directory")?;
+    }
+    let entry = CacheEntry {
+        stored_at: now_secs(),
+        data,
+    };
+    let content = serde_json::to_string(&entry)?;
+    std::fs::write(path, content).with_context(|| format!("Failed to write cache at {:?}", path))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Percent-escape everything outside `[A-Za-z0-9._-]`
+fn escape_key(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'_' | b'-' => {
+                escaped.push(byte as char)
+            }
+            _ => escaped.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    escaped
+}
+
+/// Short human description of a cache age, e.g. "42s" or "5m"
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h", secs / 3600)
+    }
+}
+
+/// Name prefix of [`FileStore::temp_file`] files, which aren't entries yet
+const TEMP_FILE_PREFIX: &str = "~partial-";
+
+/// Raw files (e.g. patches) under `<cache dir>/bb-cli/<namespace>/`, one per key
+///
+/// Unlike [`read`]/[`write`] entries, contents are stored as-is and the
+/// directory is size-capped: the least recently read or written files are
+/// removed once the total passes `max_bytes`.
+pub struct FileStore {
+    dir: PathBuf,
+    max_entry_bytes: u64,
+    max_bytes: u64,
+}
+
+impl FileStore {
+    /// The store for `namespace`, if there's a cache directory
+    pub fn open(namespace: &str, max_entry_bytes: u64, max_bytes: u64) -> Option<Self> {
+        let dir = dirs::cache_dir()?
+            .join(crate::constants::CACHE_DIR_NAME)
+            .join(namespace);
+        Some(Self::at(dir, max_entry_bytes, max_bytes))
+    }
+
+    fn at(dir: PathBuf, max_entry_bytes: u64, max_bytes: u64) -> Self {
+        Self {
+            dir,
+            max_entry_bytes,
+            max_bytes,
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(escape_key(key))
+    }
+
+    /// Whether any stored key starts with `prefix`
+    pub fn has_prefix(&self, prefix: &str) -> bool {
+        let prefix = escape_key(prefix);
+        self.entries()
+            .iter()
+            .any(|(name, _)| name.starts_with(&prefix))
+    }
+
+    /// The file stored under `key`, marking it recently used
+    pub fn get(&self, key: &str) -> Option<std::fs::File> {
+        let path = self.path(key);
+        let file = std::fs::File::options()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .ok()?;
+        if let Err(e) = file.set_modified(SystemTime::now()) {
+            crate::utils::debug::log(&format!("Failed to touch cache file {:?}: {}", path, e));
+        }
+        Some(file)
+    }
+
+    /// A file to fill and then hand to [`write`](Self::write)
+    ///
+    /// It lives in the store's directory, so storing it is a rename; it's
+    /// removed if dropped instead.
+    pub fn temp_file(&self) -> Result<tempfile::NamedTempFile> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create cache directory")?;
+        tempfile::Builder::new()
+            .prefix(TEMP_FILE_PREFIX)
+            .tempfile_in(&self.dir)
+            .context("Failed to create cache file")
+    }
+
+    /// Store `file` (from [`temp_file`](Self::temp_file)) under `key`, replacing
+    /// any keys that share `replaces_prefix`, and hand it back for reading
+    ///
+    /// A file over the per-entry limit, or one that fails to store, is handed
+    /// back without being stored (logged with `--debug`).
+    pub fn write(
+        &self,
+        key: &str,
+        replaces_prefix: &str,
+        file: tempfile::NamedTempFile,
+    ) -> std::fs::File {
+        let len = file.as_file().metadata().map_or(u64::MAX, |m| m.len());
+        if len > self.max_entry_bytes {
+            crate::utils::debug::log(&format!(
+                "Not caching {}: {} is over the per-entry limit",
+                key,
+                crate::display::ui::format_bytes(len)
+            ));
+            return file.into_file();
+        }
+
+        let stale = escape_key(replaces_prefix);
+        for (name, _) in self.entries() {
+            if name.starts_with(&stale) {
+                let _ = std::fs::remove_file(self.dir.join(name));
+            }
+        }
+
+        // Filled aside and renamed, so a concurrent read never sees half a file
+        let path = self.path(key);
+        match file.persist(&path) {
+            Ok(file) => {
+                self.evict();
+                file
+            }
+            Err(e) => {
+                crate::utils::debug::log(&format!(
+                    "Failed to write cache at {:?}: {}",
+                    path, e.error
+                ));
+                e.file.into_file()
+            }
+        }
+    }
+
+    /// Remove least recently used files until the total fits in `max_bytes`
+    fn evict(&self) {
+        let mut entries = self.entries();
+        let mut total: u64 = entries.iter().map(|(_, meta)| meta.len()).sum();
+        entries.sort_by_key(|(_, meta)| meta.modified().unwrap_or(UNIX_EPOCH));
+
+        for (name, meta) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if std::fs::remove_file(self.dir.join(&name)).is_ok() {
+                total -= meta.len();
+            }
+        }
+    }
+
+    /// File names and metadata of every stored file
+    fn entries(&self) -> Vec<(String, std::fs::Metadata)> {
+        let Ok(dir) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        dir.filter_map(|entry| {
+            let entry = entry.ok()?;
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            let name = entry.file_name().into_string().ok()?;
+            // Files still being filled; `~` is always escaped in keys
+            (!name.starts_with(TEMP_FILE_PREFIX)).then_some((name, meta))
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("entry.json");
+
+        write_to(&path, &vec!["a".to_string(), "b".to_string()]).unwrap();
+        let cached: Cached<Vec<String>> = read_from(&path, Duration::from_secs(60)).unwrap();
+
+        assert_eq!(cached.data, vec!["a", "b"]);
+        assert!(cached.age <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_cache_expired_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.json");
+        let entry = CacheEntry {
+            stored_at: now_secs() - 600,
+            data: 1,
+        };
+        std::fs::write(&path, serde_json::to_string(&entry).unwrap()).unwrap();
+
+        assert!(read_from::<u32>(&path, Duration::from_secs(300)).is_none());
+        assert!(read_from::<u32>(&path, Duration::from_secs(900)).is_some());
+    }
+
+    #[test]
+    fn test_cache_corrupt_or_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.json");
+        assert!(read_from::<u32>(&path, Duration::from_secs(60)).is_none());
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(read_from::<u32>(&path, Duration::from_secs(60)).is_none());
+    }
+
+    #[test]
+    fn test_escape_key_is_distinct() {
+        assert_eq!(escape_key("acme_role-member"), "acme_role-member");
+        assert_ne!(escape_key("a/b"), escape_key("a_b"));
+        assert_eq!(escape_key("a/b c"), "a%2Fb%20c");
+    }
+
+    /// Store `content` the way callers do, through a temp file
+    fn put(store: &FileStore, key: &str, replaces_prefix: &str, content: &[u8]) -> bool {
+        let mut file = store.temp_file().unwrap();
+        std::io::Write::write_all(&mut file, content).unwrap();
+        store.write(key, replaces_prefix, file);
+        store.get(key).is_some()
+    }
+
+    fn content(store: &FileStore, key: &str) -> Option<String> {
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut store.get(key)?, &mut content).unwrap();
+        Some(content)
+    }
+
+    #[test]
+    fn test_file_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::at(dir.path().join("diffs"), 100, 1000);
+
+        assert!(!store.has_prefix("ws/repo/1/"));
+        assert!(put(&store, "ws/repo/1/abc", "ws/repo/1/", b"patch"));
+        assert!(store.has_prefix("ws/repo/1/"));
+        assert!(!store.has_prefix("ws/repo/10/"));
+        assert_eq!(content(&store, "ws/repo/1/abc").as_deref(), Some("patch"));
+
+        // A newer patch for the same PR replaces the old one
+        assert!(put(&store, "ws/repo/1/def", "ws/repo/1/", b"newer"));
+        assert_eq!(content(&store, "ws/repo/1/abc"), None);
+        assert_eq!(content(&store, "ws/repo/1/def").as_deref(), Some("newer"));
+    }
+
+    #[test]
+    fn test_file_store_ignores_unfinished_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::at(dir.path().to_path_buf(), 100, 1000);
+
+        let file = store.temp_file().unwrap();
+        assert!(store.entries().is_empty());
+        drop(file);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_file_store_skips_oversized_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::at(dir.path().to_path_buf(), 4, 1000);
+
+        assert!(!put(&store, "big", "big", b"too large"));
+        assert_eq!(content(&store, "big"), None);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_file_store_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::at(dir.path().to_path_buf(), 10, 10);
+        let age = |key: &str, secs: u64| {
+            std::fs::File::options()
+                .append(true)
+                .open(store.path(key))
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(secs))
+                .unwrap();
+        };
+
+        put(&store, "a", "a", b"aaaa");
+        put(&store, "b", "b", b"bbbb");
+        age("a", 200);
+        age("b", 100);
+        // Reading `a` makes `b` the least recently used
+        assert!(content(&store, "a").is_some());
+
+        put(&store, "c", "c", b"cccc");
+        assert_eq!(content(&store, "b"), None);
+        assert!(content(&store, "a").is_some());
+        assert!(content(&store, "c").is_some());
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(42)), "42s");
+        assert_eq!(format_age(Duration::from_secs(300)), "5m");
+        assert_eq!(format_age(Duration::from_secs(7200)), "2h");
+    }
+}
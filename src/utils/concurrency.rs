@@ -0,0 +1,54 @@
+/// Helpers for bounding concurrent API requests with the shared `--max-concurrency` limiter
+use std::future::Future;
+use tokio::sync::Semaphore;
+
+/// Run a future once a permit is available from the limiter
+pub async fn bounded<F: Future>(limiter: &Semaphore, fut: F) -> F::Output {
+    // The limiter is never closed, so acquiring can't fail
+    let _permit = limiter.acquire().await.expect("concurrency limiter closed");
+    fut.await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn track(in_flight: &AtomicUsize, peak: &AtomicUsize) {
+        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_limits_in_flight() {
+        let limiter = Semaphore::new(2);
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        tokio::join!(
+            bounded(&limiter, track(&in_flight, &peak)),
+            bounded(&limiter, track(&in_flight, &peak)),
+            bounded(&limiter, track(&in_flight, &peak)),
+            bounded(&limiter, track(&in_flight, &peak)),
+        );
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_single_permit_is_serial() {
+        let limiter = Semaphore::new(1);
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        tokio::join!(
+            bounded(&limiter, track(&in_flight, &peak)),
+            bounded(&limiter, track(&in_flight, &peak)),
+            bounded(&limiter, track(&in_flight, &peak)),
+        );
+
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+}
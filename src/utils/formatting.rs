@@ -1,5 +1,6 @@
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::*;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// Apply consistent styling to all tables
 fn apply_table_style(table: &mut Table) {
@@ -14,6 +15,15 @@ fn apply_table_style(table: &mut Table) {
 ///
 /// * `data` - Vector of (key, value) tuples
 pub fn print_key_value_table(data: Vec<(&str, String)>) {
+    println!("{}", format_key_value_table(data));
+}
+
+/// Format a key-value table as a string
+///
+/// # Arguments
+///
+/// * `data` - Vector of (key, value) tuples
+pub fn format_key_value_table(data: Vec<(&str, String)>) -> String {
     let mut table = Table::new();
     apply_table_style(&mut table);
 
@@ -29,32 +39,56 @@ pub fn print_key_value_table(data: Vec<(&str, String)>) {
         ]);
     }
 
-    println!("{}", table);
+    table.to_string()
 }
 
-/// Print a generic table to stdout
+/// Format a table as a string
+///
+/// Cells are never wrapped, so URLs in them stay whole for [`link_urls`].
 ///
 /// # Arguments
 ///
 /// * `headers` - Vector of header strings
 /// * `rows` - Vector of rows, where each row is a vector of Cells
-pub fn print_table(headers: Vec<&str>, rows: Vec<Vec<Cell>>) {
-    let table = format_table(headers, rows);
-    println!("{}", table);
+pub fn format_table(headers: Vec<&str>, rows: Vec<Vec<Cell>>) -> String {
+    let mut table = Table::new();
+    apply_table_style(&mut table);
+
+    let width = get_terminal_width();
+    table.set_width(width);
+
+    table.set_header(headers);
+
+    for row in rows {
+        table.add_row(row);
+    }
+
+    table.to_string()
 }
 
-/// Format a table as a string
+/// Format a table as a string, wrapping cells to fit in `width` columns
+///
+/// With `None` the table is as wide as its content, so nothing is truncated.
+/// Only `pr list` fits its table to the terminal (see `--no-trunc`).
 ///
 /// # Arguments
 ///
 /// * `headers` - Vector of header strings
 /// * `rows` - Vector of rows, where each row is a vector of Cells
-pub fn format_table(headers: Vec<&str>, rows: Vec<Vec<Cell>>) -> String {
+/// * `width` - Optional maximum table width
+pub fn format_table_with_width(
+    headers: Vec<&str>,
+    rows: Vec<Vec<Cell>>,
+    width: Option<u16>,
+) -> String {
     let mut table = Table::new();
     apply_table_style(&mut table);
 
-    let width = get_terminal_width();
-    table.set_width(width);
+    if let Some(width) = width {
+        table
+            .set_width(width)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+    }
 
     table.set_header(headers);
 
@@ -65,6 +99,153 @@ pub fn format_table(headers: Vec<&str>, rows: Vec<Vec<Cell>>) -> String {
     table.to_string()
 }
 
+/// Make untrusted text (PR titles, comments, branch names, ...) safe to print
+///
+/// Control characters, including the ESC that starts ANSI sequences, are
+/// replaced with caret notation (`ESC[31m` -> `^[[31m`) so they show up as
+/// text instead of being interpreted by the terminal. Newlines and tabs are kept.
+pub fn sanitize_for_terminal(text: &str) -> String {
+    if !text.chars().any(is_unsafe_char) {
+        return text.to_string();
+    }
+
+    text.chars()
+        .map(|c| {
+            if is_unsafe_char(c) {
+                escape_control_char(c)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Control characters other than newline and tab
+fn is_unsafe_char(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+/// Visible form of a control character: caret notation for C0 and DEL, `\u{..}` otherwise
+pub fn escape_control_char(c: char) -> String {
+    match c as u32 {
+        code @ 0x00..=0x1f => format!("^{}", char::from(code as u8 + b'@')),
+        0x7f => "^?".to_string(),
+        code => format!("\\u{{{:x}}}", code),
+    }
+}
+
+static HYPERLINKS: AtomicBool = AtomicBool::new(true);
+static COLOR: AtomicBool = AtomicBool::new(true);
+
+/// Allow or forbid colored output (`--no-color`)
+///
+/// Disabling also turns off the colors crossterm and the tables emit, as
+/// `NO_COLOR` would.
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        crossterm::style::force_color_output(false);
+    }
+}
+
+/// Whether colors are allowed: not turned off by `--no-color` or `NO_COLOR`
+pub fn colors_enabled() -> bool {
+    COLOR.load(Ordering::Relaxed) && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Allow or forbid OSC 8 hyperlinks (`--no-hyperlinks`, `hyperlinks = false`)
+pub fn set_hyperlinks(enabled: bool) {
+    HYPERLINKS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether to emit OSC 8 hyperlinks: enabled, writing to a terminal, and
+/// colors aren't turned off (`--no-color`, `NO_COLOR`, `TERM=dumb`)
+fn hyperlinks_supported() -> bool {
+    use std::io::IsTerminal;
+
+    HYPERLINKS.load(Ordering::Relaxed)
+        && std::io::stdout().is_terminal()
+        && colors_enabled()
+        && std::env::var("TERM").map_or(true, |t| t != "dumb")
+}
+
+/// Link `text` to `url`: a clickable OSC 8 hyperlink where supported,
+/// otherwise the text followed by the URL
+pub fn hyperlink(text: &str, url: &str) -> String {
+    if hyperlinks_supported() {
+        osc8_hyperlink(text, url)
+    } else if text == url {
+        url.to_string()
+    } else {
+        format!("{} ({})", text, url)
+    }
+}
+
+/// Make each of `urls` clickable where it appears in already-rendered output
+///
+/// Tables measure their cells before this runs, so the invisible escape
+/// sequences don't skew column widths. A URL that was wrapped across lines
+/// no longer matches and stays plain text.
+pub fn link_urls(rendered: &str, urls: &[&str]) -> String {
+    if !hyperlinks_supported() {
+        return rendered.to_string();
+    }
+    linkify(rendered, urls)
+}
+
+fn linkify(rendered: &str, urls: &[&str]) -> String {
+    let mut urls: Vec<String> = urls
+        .iter()
+        .filter(|u| !u.is_empty())
+        .map(|u| sanitize_for_terminal(u))
+        .collect();
+    // Longest first, so a URL that extends another is matched whole
+    urls.sort_by_key(|u| std::cmp::Reverse(u.len()));
+
+    let mut output = String::with_capacity(rendered.len());
+    let mut rest = rendered;
+    while let Some(c) = rest.chars().next() {
+        match urls.iter().find(|u| rest.starts_with(u.as_str())) {
+            Some(url) => {
+                output.push_str(&osc8_hyperlink(url, url));
+                rest = &rest[url.len()..];
+            }
+            None => {
+                output.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    output
+}
+
+fn osc8_hyperlink(text: &str, url: &str) -> String {
+    format!(
+        "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+        sanitize_for_terminal(url),
+        text
+    )
+}
+
+/// Width cap for tables: the terminal width on a TTY, unbounded when piped
+pub fn default_table_width() -> Option<u16> {
+    use std::io::IsTerminal;
+
+    std::io::stdout().is_terminal().then(get_terminal_width)
+}
+
+/// Get terminal width for tables, capped at `MAX_TABLE_WIDTH`, with fallback to default
+pub fn get_terminal_width() -> u16 {
+    get_full_terminal_width().min(crate::constants::MAX_TABLE_WIDTH)
+}
+
+/// Get the terminal's full width (for text that should use all of it), with fallback to default
+pub fn get_full_terminal_width() -> u16 {
+    crossterm::terminal::size()
+        .map(|(w, _)| w)
+        .unwrap_or(crate::constants::DEFAULT_TABLE_WIDTH)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,13 +302,80 @@ mod tests {
         assert!(output.contains("Col1"));
         assert!(output.contains("Col2"));
     }
-}
 
-/// Get terminal width, with fallback to default
-fn get_terminal_width() -> u16 {
-    use crossterm::terminal;
+    #[test]
+    fn test_sanitize_for_terminal_ansi() {
+        let title = "\x1b[2J\x1b[31mFix login\x1b[0m";
+        let clean = sanitize_for_terminal(title);
+        assert!(!clean.contains('\x1b'));
+        assert_eq!(clean, "^[[2J^[[31mFix login^[[0m");
+    }
 
-    terminal::size()
-        .map(|(w, _)| w.min(crate::constants::MAX_TABLE_WIDTH))
-        .unwrap_or(crate::constants::DEFAULT_TABLE_WIDTH)
+    #[test]
+    fn test_sanitize_for_terminal_c1_and_carriage_return() {
+        // C1 CSI (U+009B) and a carriage return that could overwrite the line
+        let clean = sanitize_for_terminal("ok\u{9b}31m\rSpoofed");
+        assert_eq!(clean, "ok\\u{9b}31m^MSpoofed");
+    }
+
+    #[test]
+    fn test_sanitize_for_terminal_keeps_plain_text() {
+        let text = "Multi-line\n\tdescription with ünïcödé ✓";
+        assert_eq!(sanitize_for_terminal(text), text);
+    }
+
+    #[test]
+    fn test_osc8_hyperlink() {
+        assert_eq!(
+            osc8_hyperlink("PROJ-1", "https://jira/browse/PROJ-1"),
+            "\x1b]8;;https://jira/browse/PROJ-1\x1b\\PROJ-1\x1b]8;;\x1b\\"
+        );
+        // A URL can't smuggle its own escape sequence
+        assert!(!osc8_hyperlink("x", "https://a\x1b]8;;evil").contains("a\x1b"));
+    }
+
+    #[test]
+    fn test_linkify() {
+        let rendered = "│ Link │ https://x/pr/1 │\n│ Diff │ https://x/pr/1/diff │";
+        let linked = linkify(rendered, &["https://x/pr/1", "https://x/pr/1/diff"]);
+
+        assert_eq!(
+            linked,
+            format!(
+                "│ Link │ {} │\n│ Diff │ {} │",
+                osc8_hyperlink("https://x/pr/1", "https://x/pr/1"),
+                osc8_hyperlink("https://x/pr/1/diff", "https://x/pr/1/diff")
+            )
+        );
+        assert_eq!(linkify(rendered, &[]), rendered);
+        assert_eq!(linkify(rendered, &[""]), rendered);
+    }
+
+    #[test]
+    fn test_hyperlink_disabled_falls_back() {
+        set_hyperlinks(false);
+        assert_eq!(
+            hyperlink("PROJ-1", "https://j/PROJ-1"),
+            "PROJ-1 (https://j/PROJ-1)"
+        );
+        assert_eq!(hyperlink("https://j", "https://j"), "https://j");
+        assert_eq!(link_urls("see https://j", &["https://j"]), "see https://j");
+        set_hyperlinks(true);
+    }
+
+    #[test]
+    fn test_format_table_unbounded_keeps_long_values() {
+        let long_title = format!("Refactor {} module", "very-long-segment-".repeat(20));
+        let rows = vec![vec![Cell::new("1"), Cell::new(&long_title)]];
+
+        let output = format_table_with_width(vec!["ID", "Title"], rows.clone(), None);
+        assert!(output.contains(&long_title));
+
+        let capped = format_table_with_width(vec!["ID", "Title"], rows.clone(), Some(80));
+        assert!(!capped.contains(&long_title));
+
+        // Other tables keep every cell on one line
+        let output = format_table(vec!["ID", "Title"], rows);
+        assert!(output.contains(&long_title));
+    }
 }
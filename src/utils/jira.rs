@@ -0,0 +1,103 @@
+//! Detecting Jira issue keys (e.g. `PROJ-123`) in pull request text
+
+/// Issue keys mentioned in `texts`, in order of first appearance, without duplicates
+///
+/// A key is an uppercase project key (letter first, then letters, digits or
+/// `_`), a dash and a number, not embedded in a longer word: `PROJ-12` in
+/// `feature/PROJ-12-login` counts, `xPROJ-12` doesn't.
+pub fn find_issue_keys<'a>(texts: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for text in texts {
+        for key in keys_in(text) {
+            if !keys.iter().any(|k| k == key) {
+                keys.push(key.to_string());
+            }
+        }
+    }
+    keys
+}
+
+/// Browse URL of an issue on a Jira site, e.g. `https://acme.atlassian.net/browse/PROJ-1`
+pub fn issue_url(jira_url: &str, key: &str) -> String {
+    format!("{}/browse/{}", jira_url.trim_end_matches('/'), key)
+}
+
+fn keys_in(text: &str) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut keys = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let boundary = i == 0 || !is_word_byte(bytes[i - 1]);
+        if !boundary || !bytes[i].is_ascii_uppercase() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let mut j = i + 1;
+        while j < bytes.len() && is_project_byte(bytes[j]) {
+            j += 1;
+        }
+        let project_end = j;
+        if j < bytes.len() && bytes[j] == b'-' {
+            j += 1;
+            let digits_start = j;
+            while j < bytes.len() && bytes[j].is_ascii_digit() {
+                j += 1;
+            }
+            let ends_word = j == bytes.len() || !is_word_byte(bytes[j]);
+            if j > digits_start && project_end - start >= 2 && ends_word {
+                keys.push(&text[start..j]);
+                i = j;
+                continue;
+            }
+        }
+        i = project_end;
+    }
+    keys
+}
+
+fn is_project_byte(b: u8) -> bool {
+    b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_'
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_issue_keys() {
+        let keys = find_issue_keys([
+            "PROJ-123: Fix login",
+            "feature/PROJ-123-login",
+            "Also fixes AB2-7 and (OPS_X-42).\nSee PROJ-9",
+        ]);
+        assert_eq!(keys, vec!["PROJ-123", "AB2-7", "OPS_X-42", "PROJ-9"]);
+    }
+
+    #[test]
+    fn test_find_issue_keys_ignores_non_keys() {
+        assert!(
+            find_issue_keys(["xPROJ-1", "PROJ-1a", "A-1", "proj-1", "PROJ-", "2FA-3"]).is_empty()
+        );
+        assert!(find_issue_keys(["ünïcödé PROJ"]).is_empty());
+        assert_eq!(find_issue_keys(["é PROJ-5 é"]), vec!["PROJ-5"]);
+    }
+
+    #[test]
+    fn test_issue_url() {
+        assert_eq!(
+            issue_url("https://acme.atlassian.net/", "PROJ-1"),
+            "https://acme.atlassian.net/browse/PROJ-1"
+        );
+        assert_eq!(
+            issue_url("https://jira.example.com", "OPS-2"),
+            "https://jira.example.com/browse/OPS-2"
+        );
+    }
+}
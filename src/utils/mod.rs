@@ -1,3 +1,9 @@
 pub mod auth;
+pub mod browser;
+pub mod cache;
+pub mod concurrency;
 pub mod debug;
 pub mod formatting;
+pub mod jira;
+pub mod sigpipe;
+pub mod time;
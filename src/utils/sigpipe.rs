@@ -0,0 +1,79 @@
+//! SIGPIPE handling so `bb ... | head` exits quietly instead of panicking
+//!
+//! Rust ignores SIGPIPE by default, which turns a closed stdout into a
+//! `BrokenPipe` panic inside `println!`. Restoring the default disposition
+//! lets the process terminate silently like other Unix tools.
+
+/// Restore the default SIGPIPE disposition (no-op on non-Unix platforms)
+pub fn reset_to_default() {
+    #[cfg(unix)]
+    // SAFETY: signal(2) with a standard disposition is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+}
+
+/// Ignores SIGPIPE while alive, restoring the previous disposition on drop
+///
+/// Used while feeding a pager: if the user quits early, the write fails with
+/// `BrokenPipe` (which is handled) rather than killing the process.
+pub struct IgnoreGuard {
+    #[cfg(unix)]
+    previous: libc::sighandler_t,
+}
+
+impl IgnoreGuard {
+    pub fn new() -> Self {
+        #[cfg(unix)]
+        {
+            // SAFETY: swapping the handler for a standard disposition
+            let previous = unsafe { libc::signal(libc::SIGPIPE, libc::SIG_IGN) };
+            Self { previous }
+        }
+        #[cfg(not(unix))]
+        {
+            Self {}
+        }
+    }
+}
+
+impl Drop for IgnoreGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: restoring the disposition captured in `new`
+        unsafe {
+            libc::signal(libc::SIGPIPE, self.previous);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn current_handler() -> libc::sighandler_t {
+        // SAFETY: querying with a null new action leaves the handler unchanged
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            libc::sigaction(libc::SIGPIPE, std::ptr::null(), &mut action);
+            action.sa_sigaction
+        }
+    }
+
+    #[test]
+    fn test_ignore_guard_restores_handler() {
+        let original = current_handler();
+        {
+            // Puts back the test binary's handler, even if an assertion fails
+            let _outer = IgnoreGuard::new();
+            reset_to_default();
+            assert_eq!(current_handler(), libc::SIG_DFL);
+            {
+                let _guard = IgnoreGuard::new();
+                assert_eq!(current_handler(), libc::SIG_IGN);
+            }
+            assert_eq!(current_handler(), libc::SIG_DFL);
+        }
+        assert_eq!(current_handler(), original);
+    }
+}
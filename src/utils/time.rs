@@ -0,0 +1,205 @@
+//! Timestamp helpers for Bitbucket API dates
+//!
+//! Cloud sends ISO 8601 strings; Server sends epoch milliseconds as numbers.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+
+/// Parse an API timestamp in either format
+///
+/// Accepts RFC 3339 (`2024-01-15T10:30:00.123456+00:00`) or an integer
+/// count of milliseconds since the Unix epoch (`1705314600123`). Returns
+/// `None` for anything else.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if !value.is_empty()
+        && value
+            .trim_start_matches('-')
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+    {
+        return value
+            .parse::<i64>()
+            .ok()
+            .and_then(DateTime::from_timestamp_millis);
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Parse an age such as `90d`, `12w`, `36h` or `30m`, at most 100 years
+///
+/// Intended for clap `value_parser`s, hence the `String` error. The cap keeps
+/// `now - age` within the range of a date.
+pub fn parse_age(value: &str) -> Result<chrono::Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("'{}' is not an age like 90d (units: m, h, d, w)", value);
+
+    let unit = value.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(invalid());
+    }
+
+    let age = match unit {
+        'm' => chrono::Duration::try_minutes(amount),
+        'h' => chrono::Duration::try_hours(amount),
+        'd' => chrono::Duration::try_days(amount),
+        'w' => chrono::Duration::try_weeks(amount),
+        _ => None,
+    };
+    let age = age.ok_or_else(invalid)?;
+    if age > chrono::Duration::days(crate::constants::MAX_AGE_DAYS) {
+        return Err(format!(
+            "'{}' is too long; ages go up to {} days (100 years)",
+            value,
+            crate::constants::MAX_AGE_DAYS
+        ));
+    }
+    Ok(age)
+}
+
+/// Parse a starting point: a `YYYY-MM-DD` date (midnight UTC), an RFC 3339
+/// timestamp, or an age such as `30d` counted back from now
+///
+/// Intended for clap `value_parser`s, hence the `String` error.
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Some(midnight) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    {
+        return Ok(midnight.and_utc());
+    }
+    // Ages are capped, so this can't go out of range
+    parse_age(value).map(|age| Utc::now() - age).map_err(|_| {
+        format!(
+            "'{}' is not a date (YYYY-MM-DD), RFC 3339 timestamp, or age like 30d",
+            value
+        )
+    })
+}
+
+/// Deserialize a timestamp field that may be a string or an epoch-millis number
+///
+/// The value is kept as a string (numbers are rendered in decimal) so models
+/// stay format-agnostic; use [`parse_timestamp`] to interpret it.
+pub fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Text(String),
+        Millis(i64),
+    }
+
+    Ok(match Raw::deserialize(deserializer)? {
+        Raw::Text(s) => s,
+        Raw::Millis(ms) => ms.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90d"), Ok(chrono::Duration::days(90)));
+        assert_eq!(parse_age("2w"), Ok(chrono::Duration::weeks(2)));
+        assert_eq!(parse_age(" 36h "), Ok(chrono::Duration::hours(36)));
+        assert_eq!(parse_age("30m"), Ok(chrono::Duration::minutes(30)));
+
+        for bad in [
+            "",
+            "d",
+            "90",
+            "0d",
+            "-1d",
+            "1.5d",
+            "90y",
+            "9999999999999999w",
+        ] {
+            assert!(parse_age(bad).is_err(), "{:?} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let midnight = parse_since("2024-03-01").unwrap();
+        assert_eq!(midnight.to_rfc3339(), "2024-03-01T00:00:00+00:00");
+        let shifted = parse_since("2024-03-01T12:00:00+02:00").unwrap();
+        assert_eq!(shifted.to_rfc3339(), "2024-03-01T10:00:00+00:00");
+
+        let week_ago = parse_since("7d").unwrap();
+        let expected = Utc::now() - chrono::Duration::days(7);
+        assert!((week_ago - expected).num_seconds().abs() < 5);
+
+        for bad in ["", "30", "yesterday", "2024-13-01"] {
+            assert!(parse_since(bad).is_err(), "{:?} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_age_stays_within_date_range() {
+        assert!(parse_age("5200w").is_ok());
+        let err = parse_age("100000000w").unwrap_err();
+        assert!(err.contains("too long"), "{}", err);
+
+        // Every accepted age can be subtracted from now
+        let longest = parse_age("36525d").unwrap();
+        assert!(chrono::Utc::now().checked_sub_signed(longest).is_some());
+    }
+
+    #[test]
+    fn test_parse_timestamp_offsets() {
+        let utc = parse_timestamp("2024-01-15T10:30:00.123456+00:00").unwrap();
+        let shifted = parse_timestamp("2024-01-15T12:30:00.123456+02:00").unwrap();
+        assert_eq!(utc, shifted);
+        assert!(parse_timestamp("2024-01-15T10:30:00Z").is_some());
+    }
+
+    #[test]
+    fn test_parse_timestamp_epoch_millis() {
+        let server = parse_timestamp("1705314600123").unwrap();
+        let cloud = parse_timestamp("2024-01-15T10:30:00.123+00:00").unwrap();
+        assert_eq!(server, cloud);
+    }
+
+    #[test]
+    fn test_parse_timestamp_invalid() {
+        assert!(parse_timestamp("").is_none());
+        assert!(parse_timestamp("yesterday").is_none());
+        assert!(parse_timestamp("2024-01-15").is_none());
+        assert!(parse_timestamp("-").is_none());
+        assert!(parse_timestamp("99999999999999999999").is_none());
+    }
+
+    #[test]
+    fn test_deserialize_timestamp_accepts_both_formats() {
+        #[derive(Deserialize)]
+        struct Item {
+            #[serde(deserialize_with = "deserialize_timestamp")]
+            created_on: String,
+        }
+
+        let cloud: Item =
+            serde_json::from_str(r#"{"created_on": "2024-01-15T10:30:00+00:00"}"#).unwrap();
+        let server: Item = serde_json::from_str(r#"{"created_on": 1705314600000}"#).unwrap();
+
+        assert_eq!(server.created_on, "1705314600000");
+        assert_eq!(
+            parse_timestamp(&cloud.created_on),
+            parse_timestamp(&server.created_on)
+        );
+    }
+}